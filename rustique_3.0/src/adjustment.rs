@@ -0,0 +1,223 @@
+use eframe::egui;
+use egui::Color32;
+use serde::{Serialize, Deserialize};
+
+use crate::brush_system::curves::{PressureCurve, curve_editor};
+use crate::color_space::{hsl_to_rgb, luminance, rgb_to_hsl};
+use crate::gradient::Gradient;
+use crate::localization::{Language, get_text};
+
+const BALANCE_STRENGTH: f32 = 0.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Levels {
+    pub input_black: f32,
+    pub input_white: f32,
+    pub gamma: f32,
+    pub output_black: f32,
+    pub output_white: f32,
+}
+
+impl Default for Levels {
+    fn default() -> Self {
+        Self {
+            input_black: 0.0,
+            input_white: 1.0,
+            gamma: 1.0,
+            output_black: 0.0,
+            output_white: 1.0,
+        }
+    }
+}
+
+impl Levels {
+    fn map(&self, value: f32) -> f32 {
+        let range = (self.input_white - self.input_black).max(f32::EPSILON);
+        let normalized = ((value - self.input_black) / range).clamp(0.0, 1.0);
+        let corrected = normalized.powf(1.0 / self.gamma.max(0.01));
+        self.output_black + (self.output_white - self.output_black) * corrected
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ColorBalance {
+    pub shadows: [f32; 3],
+    pub midtones: [f32; 3],
+    pub highlights: [f32; 3],
+    pub preserve_luminosity: bool,
+}
+
+impl Default for ColorBalance {
+    fn default() -> Self {
+        Self {
+            shadows: [0.0; 3],
+            midtones: [0.0; 3],
+            highlights: [0.0; 3],
+            preserve_luminosity: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Adjustment {
+    Levels(Levels),
+    Curves(PressureCurve),
+    HueSaturation { hue: f32, saturation: f32, lightness: f32 },
+    ColorBalance(ColorBalance),
+    Invert,
+    Threshold { level: f32 },
+    GradientMap(Gradient),
+}
+
+impl Adjustment {
+    pub fn get_name(&self, language: Language) -> String {
+        match self {
+            Adjustment::Levels(_) => get_text("adjustment_levels", language),
+            Adjustment::Curves(_) => get_text("adjustment_curves", language),
+            Adjustment::HueSaturation { .. } => get_text("adjustment_hue_saturation", language),
+            Adjustment::ColorBalance(_) => get_text("adjustment_color_balance", language),
+            Adjustment::Invert => get_text("adjustment_invert", language),
+            Adjustment::Threshold { .. } => get_text("adjustment_threshold", language),
+            Adjustment::GradientMap(_) => get_text("adjustment_gradient_map", language),
+        }
+    }
+
+    pub fn all_adjustments(shadow: Color32, highlight: Color32) -> Vec<Adjustment> {
+        vec![
+            Adjustment::Levels(Levels::default()),
+            Adjustment::Curves(PressureCurve::default()),
+            Adjustment::HueSaturation { hue: 0.0, saturation: 0.0, lightness: 0.0 },
+            Adjustment::ColorBalance(ColorBalance::default()),
+            Adjustment::Invert,
+            Adjustment::Threshold { level: 0.5 },
+            Adjustment::GradientMap(Gradient::two_colors(String::new(), shadow, highlight)),
+        ]
+    }
+
+    pub fn apply(&self, pixel: [f32; 4]) -> [f32; 4] {
+        let alpha = pixel[3];
+        if alpha <= 0.0 {
+            return pixel;
+        }
+
+        let rgb = [
+            (pixel[0] / alpha).clamp(0.0, 1.0),
+            (pixel[1] / alpha).clamp(0.0, 1.0),
+            (pixel[2] / alpha).clamp(0.0, 1.0),
+        ];
+        let adjusted = self.apply_rgb(rgb);
+        [
+            adjusted[0].clamp(0.0, 1.0) * alpha,
+            adjusted[1].clamp(0.0, 1.0) * alpha,
+            adjusted[2].clamp(0.0, 1.0) * alpha,
+            alpha,
+        ]
+    }
+
+    fn apply_rgb(&self, rgb: [f32; 3]) -> [f32; 3] {
+        match self {
+            Adjustment::Levels(levels) => rgb.map(|channel| levels.map(channel)),
+            Adjustment::Curves(curve) => rgb.map(|channel| curve.evaluate(channel)),
+            Adjustment::HueSaturation { hue, saturation, lightness } => {
+                let (h, s, l) = rgb_to_hsl(rgb[0], rgb[1], rgb[2]);
+                let l = if *lightness >= 0.0 {
+                    l + (1.0 - l) * lightness
+                } else {
+                    l * (1.0 + lightness)
+                };
+                hsl_to_rgb(h + hue / 360.0, s * (1.0 + saturation), l)
+            },
+            Adjustment::ColorBalance(balance) => {
+                let luma = luminance(rgb);
+                let shadow_weight = (1.0 - luma) * (1.0 - luma);
+                let highlight_weight = luma * luma;
+                let midtone_weight = 1.0 - shadow_weight - highlight_weight;
+
+                let mut balanced = [0.0; 3];
+                for (c, value) in balanced.iter_mut().enumerate() {
+                    let shift = balance.shadows[c] * shadow_weight
+                        + balance.midtones[c] * midtone_weight
+                        + balance.highlights[c] * highlight_weight;
+                    *value = (rgb[c] + shift * BALANCE_STRENGTH).clamp(0.0, 1.0);
+                }
+
+                if balance.preserve_luminosity {
+                    let offset = luma - luminance(balanced);
+                    balanced = balanced.map(|value| value + offset);
+                }
+                balanced
+            },
+            Adjustment::Invert => rgb.map(|channel| 1.0 - channel),
+            Adjustment::Threshold { level } => {
+                let value = if luminance(rgb) >= *level { 1.0 } else { 0.0 };
+                [value; 3]
+            },
+            Adjustment::GradientMap(gradient) => {
+                let color = gradient.sample(luminance(rgb));
+                if color[3] <= 0.0 {
+                    return rgb;
+                }
+                [color[0] / color[3], color[1] / color[3], color[2] / color[3]]
+            },
+        }
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut egui::Ui, id: &str, language: Language) -> bool {
+        let mut changed = false;
+
+        match self {
+            Adjustment::Levels(levels) => {
+                let mut slider = |ui: &mut egui::Ui, value: &mut f32, range: std::ops::RangeInclusive<f32>, key: &str| {
+                    changed |= ui.add(egui::Slider::new(value, range).text(get_text(key, language))).changed();
+                };
+                slider(ui, &mut levels.input_black, 0.0..=1.0, "levels_input_black");
+                slider(ui, &mut levels.input_white, 0.0..=1.0, "levels_input_white");
+                slider(ui, &mut levels.gamma, 0.1..=5.0, "levels_gamma");
+                slider(ui, &mut levels.output_black, 0.0..=1.0, "levels_output_black");
+                slider(ui, &mut levels.output_white, 0.0..=1.0, "levels_output_white");
+            },
+            Adjustment::Curves(curve) => {
+                changed |= curve_editor(ui, curve, None, id, language);
+            },
+            Adjustment::HueSaturation { hue, saturation, lightness } => {
+                changed |= ui.add(egui::Slider::new(hue, -180.0..=180.0).text(get_text("hue", language))).changed();
+                changed |= ui.add(egui::Slider::new(saturation, -1.0..=1.0).text(get_text("saturation", language))).changed();
+                changed |= ui.add(egui::Slider::new(lightness, -1.0..=1.0).text(get_text("lightness", language))).changed();
+            },
+            Adjustment::ColorBalance(balance) => {
+                let ranges = [
+                    (&mut balance.shadows, "balance_shadows"),
+                    (&mut balance.midtones, "balance_midtones"),
+                    (&mut balance.highlights, "balance_highlights"),
+                ];
+                for (values, key) in ranges {
+                    ui.label(get_text(key, language));
+                    let axes = ["balance_cyan_red", "balance_magenta_green", "balance_yellow_blue"];
+                    for (value, axis) in values.iter_mut().zip(axes) {
+                        changed |= ui.add(egui::Slider::new(value, -1.0..=1.0).text(get_text(axis, language))).changed();
+                    }
+                }
+                changed |= ui.checkbox(&mut balance.preserve_luminosity, get_text("preserve_luminosity", language)).changed();
+            },
+            Adjustment::Invert => {},
+            Adjustment::Threshold { level } => {
+                changed |= ui.add(egui::Slider::new(level, 0.0..=1.0).text(get_text("threshold_level", language))).changed();
+            },
+            Adjustment::GradientMap(gradient) => {
+                ui.horizontal(|ui| {
+                    for stop in gradient.stops.iter_mut() {
+                        changed |= ui.color_edit_button_srgba_unmultiplied(&mut stop.color).changed();
+                    }
+                    if ui.small_button("⇄").on_hover_text(get_text("reverse_gradient", language)).clicked() {
+                        for stop in gradient.stops.iter_mut() {
+                            stop.position = 1.0 - stop.position;
+                        }
+                        changed = true;
+                    }
+                });
+            },
+        }
+
+        changed
+    }
+}
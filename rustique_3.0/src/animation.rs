@@ -0,0 +1,441 @@
+use eframe::egui;
+use egui::Vec2;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::export_options::ExportOptions;
+use crate::localization::{Language, get_text};
+use crate::quantize::{Dithering, build_palette, map_to_palette};
+use crate::ui_theme::RustiqueTheme;
+
+pub const DEFAULT_FRAME_DURATION: u32 = 100;
+pub const MIN_FRAME_DURATION: u32 = 10;
+pub const MAX_FRAME_DURATION: u32 = 10_000;
+const MAX_ONION_FRAMES: usize = 5;
+const ONION_TINT_AMOUNT: f32 = 0.6;
+const PREVIOUS_TINT: [f32; 3] = [230.0, 60.0, 60.0];
+const NEXT_TINT: [f32; 3] = [60.0, 120.0, 230.0];
+const FRAME_CELL_SIZE: Vec2 = Vec2::new(48.0, 36.0);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AnimationExport {
+    Gif,
+    Apng,
+    PngSequence,
+}
+
+impl AnimationExport {
+    pub fn get_name(&self, language: Language) -> String {
+        match self {
+            AnimationExport::Gif => get_text("animated_gif", language),
+            AnimationExport::Apng => get_text("animated_png", language),
+            AnimationExport::PngSequence => get_text("png_sequence", language),
+        }
+    }
+
+    pub fn all_exports() -> Vec<AnimationExport> {
+        vec![AnimationExport::Gif, AnimationExport::Apng, AnimationExport::PngSequence]
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AnimationExport::Gif => "gif",
+            AnimationExport::Apng | AnimationExport::PngSequence => "png",
+        }
+    }
+}
+
+pub enum TimelineAction {
+    None,
+    SelectFrame(usize),
+    AddFrame,
+    DuplicateFrame,
+    DeleteFrame,
+    MoveFrame(isize),
+    SetDuration(usize, u32, bool),
+    TogglePlayback,
+    OnionChanged,
+    Export(AnimationExport),
+}
+
+pub struct OnionSkin {
+    pub enabled: bool,
+    pub previous: usize,
+    pub next: usize,
+    pub opacity: f32,
+    cache: Option<Vec<[f32; 4]>>,
+}
+
+impl OnionSkin {
+    fn new() -> Self {
+        Self {
+            enabled: false,
+            previous: 1,
+            next: 1,
+            opacity: 0.4,
+            cache: None,
+        }
+    }
+
+    pub fn invalidate(&mut self) {
+        self.cache = None;
+    }
+
+    pub fn needs_build(&self) -> bool {
+        self.enabled && self.cache.is_none()
+    }
+
+    pub fn overlay(&self) -> Option<&[[f32; 4]]> {
+        self.cache.as_deref().filter(|_| self.enabled)
+    }
+
+    pub fn neighbours(&self, current: usize, frame_count: usize) -> Vec<(usize, usize, bool)> {
+        let mut frames = Vec::new();
+        for distance in (1..=self.previous.max(self.next)).rev() {
+            if distance <= self.previous && distance <= current {
+                frames.push((current - distance, distance, true));
+            }
+            if distance <= self.next && current + distance < frame_count {
+                frames.push((current + distance, distance, false));
+            }
+        }
+        frames
+    }
+
+    pub fn build(&mut self, size: usize, composites: Vec<(Vec<[f32; 4]>, usize, bool)>) {
+        let mut overlay = vec![[0.0; 4]; if composites.is_empty() { 0 } else { size }];
+        for (composite, distance, previous) in composites {
+            let tint = if previous { PREVIOUS_TINT } else { NEXT_TINT };
+            let fade = self.opacity / distance as f32;
+            for (pixel, color) in overlay.iter_mut().zip(composite) {
+                if color[3] <= 0.0 {
+                    continue;
+                }
+                let coverage = color[3] / 255.0;
+                let mut tinted = color.map(|channel| channel * fade);
+                for (channel, (&original, tint)) in tinted.iter_mut().zip(color.iter().zip(tint)) {
+                    *channel = (original * (1.0 - ONION_TINT_AMOUNT) + tint * coverage * ONION_TINT_AMOUNT) * fade;
+                }
+                let inverse_alpha = 1.0 - tinted[3] / 255.0;
+                for (channel, value) in pixel.iter_mut().zip(tinted) {
+                    *channel = value + *channel * inverse_alpha;
+                }
+            }
+        }
+        self.cache = Some(overlay);
+    }
+}
+
+pub struct Playback {
+    pub playing: bool,
+    last_tick: Option<Instant>,
+    elapsed: Duration,
+}
+
+impl Playback {
+    fn new() -> Self {
+        Self {
+            playing: false,
+            last_tick: None,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.playing = !self.playing;
+        self.last_tick = None;
+        self.elapsed = Duration::ZERO;
+    }
+
+    pub fn stop(&mut self) -> bool {
+        std::mem::replace(&mut self.playing, false)
+    }
+
+    pub fn tick(&mut self, current: usize, durations: &[u32]) -> (Option<usize>, Duration) {
+        let now = Instant::now();
+        self.elapsed += self.last_tick.map_or(Duration::ZERO, |last| now - last);
+        self.last_tick = Some(now);
+
+        let mut frame = current.min(durations.len().saturating_sub(1));
+        loop {
+            let duration = Duration::from_millis(durations.get(frame).copied().unwrap_or(DEFAULT_FRAME_DURATION).max(MIN_FRAME_DURATION) as u64);
+            if self.elapsed < duration {
+                return ((frame != current).then_some(frame), duration - self.elapsed);
+            }
+            self.elapsed -= duration;
+            frame = (frame + 1) % durations.len().max(1);
+        }
+    }
+}
+
+pub struct AnimationPanel {
+    pub onion_skin: OnionSkin,
+    pub playback: Playback,
+    editing_duration: bool,
+}
+
+impl Default for AnimationPanel {
+    fn default() -> Self {
+        Self {
+            onion_skin: OnionSkin::new(),
+            playback: Playback::new(),
+            editing_duration: false,
+        }
+    }
+}
+
+impl AnimationPanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, durations: &[u32], current: usize, language: Language) -> TimelineAction {
+        let mut action = TimelineAction::None;
+        let frame_count = durations.len();
+
+        ui.horizontal(|ui| {
+            let (icon, hover) = if self.playback.playing { ("⏸", "pause_animation") } else { ("▶", "play_animation") };
+            if ui.button(icon).on_hover_text(get_text(hover, language)).clicked() {
+                action = TimelineAction::TogglePlayback;
+            }
+            if ui.add_enabled(current > 0, egui::Button::new("⏮")).on_hover_text(get_text("previous_frame", language)).clicked() {
+                action = TimelineAction::SelectFrame(current - 1);
+            }
+            if ui.add_enabled(current + 1 < frame_count, egui::Button::new("⏭")).on_hover_text(get_text("next_frame", language)).clicked() {
+                action = TimelineAction::SelectFrame(current + 1);
+            }
+
+            ui.separator();
+
+            if ui.button("+").on_hover_text(get_text("add_frame", language)).clicked() {
+                action = TimelineAction::AddFrame;
+            }
+            if ui.button("⧉").on_hover_text(get_text("duplicate_frame", language)).clicked() {
+                action = TimelineAction::DuplicateFrame;
+            }
+            if ui.add_enabled(frame_count > 1, egui::Button::new("🗑")).on_hover_text(get_text("delete_frame", language)).clicked() {
+                action = TimelineAction::DeleteFrame;
+            }
+            if ui.add_enabled(current > 0, egui::Button::new("⏴")).on_hover_text(get_text("move_frame_left", language)).clicked() {
+                action = TimelineAction::MoveFrame(-1);
+            }
+            if ui.add_enabled(current + 1 < frame_count, egui::Button::new("⏵")).on_hover_text(get_text("move_frame_right", language)).clicked() {
+                action = TimelineAction::MoveFrame(1);
+            }
+
+            ui.separator();
+
+            if let Some(&duration) = durations.get(current) {
+                let mut duration = duration;
+                let response = ui.add(egui::DragValue::new(&mut duration)
+                        .clamp_range(MIN_FRAME_DURATION..=MAX_FRAME_DURATION)
+                        .suffix(" ms"))
+                    .on_hover_text(get_text("frame_duration", language));
+                if response.changed() {
+                    action = TimelineAction::SetDuration(current, duration, !self.editing_duration);
+                    self.editing_duration = true;
+                }
+                if !response.dragged() && !response.has_focus() {
+                    self.editing_duration = false;
+                }
+            }
+
+            ui.separator();
+
+            let onion = &mut self.onion_skin;
+            let mut onion_changed = ui.checkbox(&mut onion.enabled, get_text("onion_skin", language)).changed();
+            if onion.enabled {
+                onion_changed |= ui.add(egui::DragValue::new(&mut onion.previous).clamp_range(0..=MAX_ONION_FRAMES).prefix("◀ "))
+                    .on_hover_text(get_text("onion_previous_frames", language))
+                    .changed();
+                onion_changed |= ui.add(egui::DragValue::new(&mut onion.next).clamp_range(0..=MAX_ONION_FRAMES).prefix("▶ "))
+                    .on_hover_text(get_text("onion_next_frames", language))
+                    .changed();
+                onion_changed |= ui.add(egui::Slider::new(&mut onion.opacity, 0.05..=1.0).show_value(false))
+                    .on_hover_text(get_text("opacity", language))
+                    .changed();
+            }
+            if onion_changed {
+                action = TimelineAction::OnionChanged;
+            }
+
+            ui.separator();
+
+            ui.menu_button(get_text("export_animation", language), |ui| {
+                for export in AnimationExport::all_exports() {
+                    if ui.button(export.get_name(language)).clicked() {
+                        action = TimelineAction::Export(export);
+                        ui.close_menu();
+                    }
+                }
+            });
+        });
+
+        egui::ScrollArea::horizontal().show(ui, |ui| {
+            ui.horizontal(|ui| {
+                for (index, duration) in durations.iter().enumerate() {
+                    let active = index == current;
+                    let text = egui::RichText::new(format!("{}\n{} ms", index + 1, duration)).size(11.0);
+                    let cell = ui.add(
+                        egui::Button::new(text)
+                            .fill(if active { RustiqueTheme::ACCENT_PRIMARY } else { RustiqueTheme::SURFACE_SECONDARY })
+                            .stroke(egui::Stroke::new(1.0, if active { RustiqueTheme::ACCENT_PRIMARY } else { RustiqueTheme::BORDER_LIGHT }))
+                            .rounding(RustiqueTheme::rounding_small())
+                            .min_size(FRAME_CELL_SIZE)
+                    );
+                    if cell.clicked() && !active {
+                        action = TimelineAction::SelectFrame(index);
+                    }
+                }
+            });
+        });
+
+        action
+    }
+}
+
+pub fn sequence_path(path: &Path, index: usize, frame_count: usize) -> PathBuf {
+    let stem = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or("frame");
+    let digits = frame_count.to_string().len().max(3);
+    path.with_file_name(format!("{}_{:0digits$}.png", stem, index + 1, digits = digits))
+}
+
+pub fn encode_gif(
+    options: &ExportOptions,
+    width: usize,
+    height: usize,
+    frames: &[(Vec<[u8; 4]>, u32)],
+    document_palette: Option<&[[u8; 3]]>,
+    language: Language,
+) -> Result<Vec<u8>, String> {
+    let error = |e: &dyn std::fmt::Display| format!("{}: {}", get_text("error_saving_image", language), e);
+    let (Ok(gif_width), Ok(gif_height)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(get_text("image_too_large_for_gif", language));
+    };
+
+    let frames: Vec<(Vec<[u8; 4]>, u32)> = frames
+        .iter()
+        .map(|(pixels, duration)| (pixels.iter().map(|&pixel| options.apply_matte(pixel, true)).collect(), *duration))
+        .collect();
+    let (palette, dithering) = match document_palette {
+        Some(palette) => (palette.to_vec(), Dithering::None),
+        None => {
+            let all_pixels: Vec<[u8; 4]> = frames.iter().flat_map(|(pixels, _)| pixels.iter().copied()).collect();
+            (build_palette(&all_pixels, options.palette_size, options.quantizer), options.dithering)
+        },
+    };
+
+    let transparent = palette.len() as u8;
+    let rgb: Vec<u8> = palette.iter().flatten().copied().chain([0, 0, 0]).collect();
+    let mut bytes = Vec::new();
+    let mut encoder = gif::Encoder::new(&mut bytes, gif_width, gif_height, &rgb).map_err(|e| error(&e))?;
+    encoder.set_repeat(gif::Repeat::Infinite).map_err(|e| error(&e))?;
+    for (pixels, duration) in &frames {
+        let indices: Vec<u8> = map_to_palette(pixels, width, &palette, dithering)
+            .into_iter()
+            .map(|index| index.unwrap_or(transparent))
+            .collect();
+        let mut frame = gif::Frame::from_indexed_pixels(gif_width, gif_height, indices, Some(transparent));
+        frame.delay = u16::try_from(duration / 10).unwrap_or(u16::MAX);
+        frame.dispose = gif::DisposalMethod::Background;
+        encoder.write_frame(&frame).map_err(|e| error(&e))?;
+    }
+    drop(encoder);
+    Ok(bytes)
+}
+
+pub fn encode_apng(
+    options: &ExportOptions,
+    width: usize,
+    height: usize,
+    frames: &[(Vec<[u8; 4]>, u32)],
+    language: Language,
+) -> Result<Vec<u8>, String> {
+    let error = |e: &dyn std::fmt::Display| format!("{}: {}", get_text("error_saving_image", language), e);
+
+    let mut bytes = Vec::new();
+    let mut encoder = options.png_encoder(&mut bytes, width, height).map_err(|e| error(&e))?;
+    encoder.set_animated(frames.len() as u32, 0).map_err(|e| error(&e))?;
+    encoder.set_dispose_op(png::DisposeOp::Background).map_err(|e| error(&e))?;
+    encoder.set_blend_op(png::BlendOp::Source).map_err(|e| error(&e))?;
+
+    let mut writer = encoder.write_header().map_err(|e| error(&e))?;
+    for (pixels, duration) in frames {
+        writer.set_frame_delay(u16::try_from(*duration).unwrap_or(u16::MAX), 1000).map_err(|e| error(&e))?;
+        let rgba: Vec<u8> = pixels.iter().flatten().copied().collect();
+        writer.write_image_data(&rgba).map_err(|e| error(&e))?;
+    }
+    writer.finish().map_err(|e| error(&e))?;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+    const CLEAR: [u8; 4] = [0, 0, 0, 0];
+
+    fn frames() -> Vec<(Vec<[u8; 4]>, u32)> {
+        vec![
+            (vec![RED, RED, CLEAR, BLUE], 100),
+            (vec![BLUE, CLEAR, RED, RED], 250),
+        ]
+    }
+
+    #[test]
+    fn sequence_paths_are_zero_padded() {
+        let path = Path::new("/tmp/walk.png");
+        assert_eq!(sequence_path(path, 0, 12), PathBuf::from("/tmp/walk_001.png"));
+        assert_eq!(sequence_path(path, 1233, 1500), PathBuf::from("/tmp/walk_1234.png"));
+    }
+
+    #[test]
+    fn gif_keeps_frames_delays_and_transparency() {
+        let bytes = encode_gif(&ExportOptions::default(), 2, 2, &frames(), None, Language::English).unwrap();
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(bytes.as_slice()).unwrap();
+
+        let mut decoded = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            let pixels: Vec<[u8; 4]> = frame.buffer.chunks_exact(4).map(|pixel| [pixel[0], pixel[1], pixel[2], pixel[3]]).collect();
+            decoded.push((pixels, frame.delay));
+        }
+        let expected: Vec<(Vec<[u8; 4]>, u16)> = frames()
+            .into_iter()
+            .map(|(pixels, duration)| (pixels, (duration / 10) as u16))
+            .collect();
+        assert_eq!(decoded, expected);
+    }
+
+    #[test]
+    fn gif_uses_the_document_palette() {
+        let palette = [[255, 0, 0], [0, 0, 255]];
+        let bytes = encode_gif(&ExportOptions::default(), 2, 2, &frames(), Some(&palette), Language::English).unwrap();
+        let decoder = gif::DecodeOptions::new().read_info(bytes.as_slice()).unwrap();
+        assert!(decoder.global_palette().unwrap().starts_with(&[255, 0, 0, 0, 0, 255, 0, 0, 0]));
+    }
+
+    #[test]
+    fn gif_rejects_oversized_canvas() {
+        let frames = vec![(vec![RED; 70_000], 100)];
+        assert!(encode_gif(&ExportOptions::default(), 70_000, 1, &frames, None, Language::English).is_err());
+    }
+
+    #[test]
+    fn apng_keeps_frames_and_delays() {
+        let bytes = encode_apng(&ExportOptions::default(), 2, 2, &frames(), Language::English).unwrap();
+        let mut reader = png::Decoder::new(bytes.as_slice()).read_info().unwrap();
+        assert_eq!(reader.info().animation_control.unwrap().num_frames, 2);
+
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        for (pixels, duration) in frames() {
+            reader.next_frame(&mut buffer).unwrap();
+            let control = reader.info().frame_control.unwrap();
+            assert_eq!((control.delay_num, control.delay_den), (duration as u16, 1000));
+            assert_eq!(buffer, pixels.concat());
+        }
+    }
+}
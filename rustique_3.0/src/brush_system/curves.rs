@@ -0,0 +1,342 @@
+use eframe::egui;
+use egui::{Color32, Pos2, Rect, Stroke, Vec2};
+use serde::{Serialize, Deserialize};
+
+use crate::localization::{Language, get_text};
+
+const EDITOR_SIZE: f32 = 140.0;
+const HANDLE_RADIUS: f32 = 4.0;
+const MAX_CURVE_POINTS: usize = 12;
+const MIN_POINT_SPACING: f32 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CurvePreset {
+    Linear,
+    Soft,
+    Hard,
+    SCurve,
+}
+
+impl CurvePreset {
+    pub fn get_name(&self, language: Language) -> String {
+        match self {
+            CurvePreset::Linear => get_text("curve_linear", language),
+            CurvePreset::Soft => get_text("curve_soft", language),
+            CurvePreset::Hard => get_text("curve_hard", language),
+            CurvePreset::SCurve => get_text("curve_s_curve", language),
+        }
+    }
+
+    pub fn all_presets() -> Vec<CurvePreset> {
+        vec![
+            CurvePreset::Linear,
+            CurvePreset::Soft,
+            CurvePreset::Hard,
+            CurvePreset::SCurve,
+        ]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredCurve")]
+pub struct PressureCurve {
+    pub points: Vec<(f32, f32)>,
+}
+
+#[derive(Deserialize)]
+struct StoredCurve {
+    points: Vec<(f32, f32)>,
+}
+
+impl From<StoredCurve> for PressureCurve {
+    fn from(stored: StoredCurve) -> Self {
+        let mut curve = Self { points: stored.points };
+        curve.sanitize();
+        curve
+    }
+}
+
+impl Default for PressureCurve {
+    fn default() -> Self {
+        Self::from_preset(CurvePreset::Linear)
+    }
+}
+
+impl PressureCurve {
+    pub fn from_preset(preset: CurvePreset) -> Self {
+        let points = match preset {
+            CurvePreset::Linear => vec![(0.0, 0.0), (1.0, 1.0)],
+            CurvePreset::Soft => vec![(0.0, 0.0), (0.25, 0.5), (1.0, 1.0)],
+            CurvePreset::Hard => vec![(0.0, 0.0), (0.75, 0.5), (1.0, 1.0)],
+            CurvePreset::SCurve => vec![(0.0, 0.0), (0.3, 0.15), (0.7, 0.85), (1.0, 1.0)],
+        };
+        Self { points }
+    }
+
+    pub fn evaluate(&self, input: f32) -> f32 {
+        let x = input.clamp(0.0, 1.0);
+        let points = &self.points;
+
+        match points.len() {
+            0 => return x,
+            1 => return points[0].1.clamp(0.0, 1.0),
+            _ => {}
+        }
+
+        if x <= points[0].0 {
+            return points[0].1.clamp(0.0, 1.0);
+        }
+        if x >= points[points.len() - 1].0 {
+            return points[points.len() - 1].1.clamp(0.0, 1.0);
+        }
+
+        let segment = points
+            .windows(2)
+            .position(|w| x >= w[0].0 && x <= w[1].0)
+            .unwrap_or(0);
+
+        let slopes = self.tangents();
+        let (x0, y0) = points[segment];
+        let (x1, y1) = points[segment + 1];
+        let h = x1 - x0;
+        if h <= f32::EPSILON {
+            return y1.clamp(0.0, 1.0);
+        }
+
+        let t = (x - x0) / h;
+        let t2 = t * t;
+        let t3 = t2 * t;
+        let value = (2.0 * t3 - 3.0 * t2 + 1.0) * y0
+            + (t3 - 2.0 * t2 + t) * h * slopes[segment]
+            + (-2.0 * t3 + 3.0 * t2) * y1
+            + (t3 - t2) * h * slopes[segment + 1];
+
+        value.clamp(0.0, 1.0)
+    }
+
+    fn tangents(&self) -> Vec<f32> {
+        let points = &self.points;
+        let n = points.len();
+        let secants: Vec<f32> = points
+            .windows(2)
+            .map(|w| {
+                let dx = w[1].0 - w[0].0;
+                if dx <= f32::EPSILON { 0.0 } else { (w[1].1 - w[0].1) / dx }
+            })
+            .collect();
+
+        let mut tangents = vec![0.0; n];
+        tangents[0] = secants[0];
+        tangents[n - 1] = secants[n - 2];
+        for i in 1..n - 1 {
+            tangents[i] = if secants[i - 1] * secants[i] <= 0.0 {
+                0.0
+            } else {
+                (secants[i - 1] + secants[i]) * 0.5
+            };
+        }
+
+        for i in 0..n - 1 {
+            if secants[i].abs() <= f32::EPSILON {
+                tangents[i] = 0.0;
+                tangents[i + 1] = 0.0;
+                continue;
+            }
+            let a = tangents[i] / secants[i];
+            let b = tangents[i + 1] / secants[i];
+            let magnitude = a * a + b * b;
+            if magnitude > 9.0 {
+                let scale = 3.0 / magnitude.sqrt();
+                tangents[i] = scale * a * secants[i];
+                tangents[i + 1] = scale * b * secants[i];
+            }
+        }
+
+        tangents
+    }
+
+    fn sort_points(&mut self) {
+        self.points.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+    }
+
+    /// Brings a curve loaded from a file back to the shape `evaluate` and the
+    /// editor expect: finite points in the unit square, sorted by input with
+    /// distinct inputs, and at least the two end points.
+    pub fn sanitize(&mut self) {
+        self.points.retain(|(x, y)| x.is_finite() && y.is_finite());
+        for point in &mut self.points {
+            *point = (point.0.clamp(0.0, 1.0), point.1.clamp(0.0, 1.0));
+        }
+        self.sort_points();
+        self.points.dedup_by(|next, kept| next.0 - kept.0 < MIN_POINT_SPACING);
+        self.points.truncate(MAX_CURVE_POINTS);
+
+        if self.points.len() < 2 {
+            *self = Self::default();
+        }
+    }
+}
+
+pub fn curve_editor(ui: &mut egui::Ui, curve: &mut PressureCurve, current_input: Option<f32>, id: &str, language: Language) -> bool {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        ui.label(format!("{}:", get_text("curve_preset", language)));
+        egui::ComboBox::from_id_source(format!("{}_preset", id))
+            .selected_text(get_text("curve_choose", language))
+            .show_ui(ui, |ui| {
+                for preset in CurvePreset::all_presets() {
+                    if ui.selectable_label(false, preset.get_name(language)).clicked() {
+                        *curve = PressureCurve::from_preset(preset);
+                        changed = true;
+                    }
+                }
+            });
+    });
+
+    let (rect, response) = ui.allocate_exact_size(Vec2::splat(EDITOR_SIZE), egui::Sense::click_and_drag());
+    let painter = ui.painter_at(rect);
+
+    let to_screen = |(x, y): (f32, f32)| Pos2::new(rect.left() + x * rect.width(), rect.bottom() - y * rect.height());
+    let from_screen = |pos: Pos2| {
+        (
+            ((pos.x - rect.left()) / rect.width()).clamp(0.0, 1.0),
+            ((rect.bottom() - pos.y) / rect.height()).clamp(0.0, 1.0),
+        )
+    };
+
+    painter.rect_filled(rect, 2.0, Color32::from_gray(30));
+    for i in 1..4 {
+        let t = i as f32 / 4.0;
+        let grid = Stroke::new(1.0, Color32::from_gray(55));
+        painter.line_segment([to_screen((t, 0.0)), to_screen((t, 1.0))], grid);
+        painter.line_segment([to_screen((0.0, t)), to_screen((1.0, t))], grid);
+    }
+    painter.line_segment([to_screen((0.0, 0.0)), to_screen((1.0, 1.0))], Stroke::new(1.0, Color32::from_gray(70)));
+
+    if curve.points.len() < 2 {
+        curve.sanitize();
+        changed = true;
+    }
+
+    let drag_id = ui.make_persistent_id(format!("{}_drag", id));
+    let mut dragging: Option<usize> = ui.data(|d| d.get_temp(drag_id));
+
+    if response.drag_started() {
+        dragging = response.interact_pointer_pos().and_then(|pos| {
+            curve.points
+                .iter()
+                .position(|&p| to_screen(p).distance(pos) <= HANDLE_RADIUS * 2.0)
+        });
+    }
+
+    if let (Some(index), Some(pos)) = (dragging.filter(|_| response.dragged()), response.interact_pointer_pos()) {
+        let (mut x, y) = from_screen(pos);
+        let last = curve.points.len() - 1;
+        if index == 0 {
+            x = 0.0;
+        } else if index == last {
+            x = 1.0;
+        } else {
+            let min_x = curve.points[index - 1].0 + MIN_POINT_SPACING;
+            let max_x = curve.points[index + 1].0 - MIN_POINT_SPACING;
+            x = x.clamp(min_x, max_x.max(min_x));
+        }
+        curve.points[index] = (x, y);
+        curve.sort_points();
+        dragging = curve.points.iter().position(|&p| p == (x, y));
+        changed = true;
+    }
+
+    if response.drag_released() {
+        dragging = None;
+    }
+    ui.data_mut(|d| d.insert_temp(drag_id, dragging));
+
+    let can_add = response.double_clicked() && curve.points.len() < MAX_CURVE_POINTS;
+    if let (true, Some(pos)) = (can_add, response.interact_pointer_pos()) {
+        let (x, _) = from_screen(pos);
+        curve.points.push((x, curve.evaluate(x)));
+        curve.sort_points();
+        changed = true;
+    }
+
+    if response.secondary_clicked() {
+        let last = curve.points.len() - 1;
+        let target = response.interact_pointer_pos().and_then(|pos| {
+            curve.points
+                .iter()
+                .position(|&p| to_screen(p).distance(pos) <= HANDLE_RADIUS * 2.0)
+        });
+        if let Some(index) = target.filter(|&i| i > 0 && i < last) {
+            curve.points.remove(index);
+            changed = true;
+        }
+    }
+
+    let samples: Vec<Pos2> = (0..=64)
+        .map(|i| {
+            let x = i as f32 / 64.0;
+            to_screen((x, curve.evaluate(x)))
+        })
+        .collect();
+    painter.add(egui::Shape::line(samples, Stroke::new(2.0, Color32::from_rgb(120, 180, 255))));
+
+    for &point in &curve.points {
+        painter.circle_filled(to_screen(point), HANDLE_RADIUS, Color32::WHITE);
+    }
+
+    if let Some(input) = current_input {
+        let output = curve.evaluate(input);
+        let marker = to_screen((input, output));
+        painter.line_segment([to_screen((input, 0.0)), marker], Stroke::new(1.0, Color32::from_rgb(255, 200, 80)));
+        painter.circle_filled(marker, 3.0, Color32::from_rgb(255, 200, 80));
+    }
+
+    painter.rect_stroke(Rect::from_min_max(rect.min, rect.max), 2.0, Stroke::new(1.0, Color32::from_gray(90)));
+
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(json: &str) -> PressureCurve {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn empty_curve_loads_as_linear() {
+        assert_eq!(load(r#"{"points":[]}"#), PressureCurve::default());
+        assert_eq!(load(r#"{"points":[[0.5,0.2]]}"#), PressureCurve::default());
+    }
+
+    #[test]
+    fn loaded_points_are_sorted_and_clamped() {
+        let curve = load(r#"{"points":[[1.0,1.0],[0.4,1.5],[0.0,0.0],[0.4,0.3]]}"#);
+        assert_eq!(curve.points, vec![(0.0, 0.0), (0.4, 1.0), (1.0, 1.0)]);
+    }
+
+    #[test]
+    fn evaluate_is_monotonic_for_presets() {
+        for preset in CurvePreset::all_presets() {
+            let curve = PressureCurve::from_preset(preset);
+            let mut previous = curve.evaluate(0.0);
+            for i in 1..=100 {
+                let value = curve.evaluate(i as f32 / 100.0);
+                assert!(value + 1e-6 >= previous, "{:?} at {}", preset, i);
+                previous = value;
+            }
+        }
+    }
+
+    #[test]
+    fn evaluate_hits_end_points() {
+        let curve = PressureCurve::from_preset(CurvePreset::SCurve);
+        assert_eq!(curve.evaluate(0.0), 0.0);
+        assert_eq!(curve.evaluate(1.0), 1.0);
+        assert_eq!(curve.evaluate(-3.0), 0.0);
+        assert_eq!(curve.evaluate(7.0), 1.0);
+    }
+}
@@ -0,0 +1,307 @@
+use eframe::egui;
+use egui::Color32;
+use serde::{Serialize, Deserialize};
+use std::f32::consts::PI;
+
+use crate::color_space::{hsv_to_rgb, rgb_to_hsv};
+use crate::localization::{Language, get_text};
+
+const MAX_DAB_COUNT: u32 = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum DynamicsSource {
+    Random,
+    Pressure,
+    Velocity,
+}
+
+impl DynamicsSource {
+    pub fn get_name(&self, language: Language) -> String {
+        match self {
+            DynamicsSource::Random => get_text("dynamics_source_random", language),
+            DynamicsSource::Pressure => get_text("dynamics_source_pressure", language),
+            DynamicsSource::Velocity => get_text("dynamics_source_velocity", language),
+        }
+    }
+
+    pub fn all_sources() -> Vec<DynamicsSource> {
+        vec![
+            DynamicsSource::Random,
+            DynamicsSource::Pressure,
+            DynamicsSource::Velocity,
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Dynamic {
+    pub amount: f32,
+    pub source: DynamicsSource,
+}
+
+impl Default for Dynamic {
+    fn default() -> Self {
+        Self {
+            amount: 0.0,
+            source: DynamicsSource::Random,
+        }
+    }
+}
+
+impl Dynamic {
+    pub fn is_enabled(&self) -> bool {
+        self.amount > 0.0
+    }
+
+    fn sample(&self, input: DynamicsInput, rng: &mut DabRng) -> f32 {
+        match self.source {
+            DynamicsSource::Random => rng.next_f32(),
+            DynamicsSource::Pressure => 1.0 - input.pressure.clamp(0.0, 1.0),
+            DynamicsSource::Velocity => input.velocity.clamp(0.0, 1.0),
+        }
+    }
+
+    fn sample_signed(&self, input: DynamicsInput, rng: &mut DabRng) -> f32 {
+        match self.source {
+            DynamicsSource::Random => rng.next_f32() * 2.0 - 1.0,
+            _ => self.sample(input, rng),
+        }
+    }
+
+    pub fn reduce(&self, input: DynamicsInput, rng: &mut DabRng) -> f32 {
+        if !self.is_enabled() {
+            return 1.0;
+        }
+        (1.0 - self.amount.clamp(0.0, 1.0) * self.sample(input, rng)).max(0.0)
+    }
+
+    pub fn offset(&self, input: DynamicsInput, rng: &mut DabRng) -> f32 {
+        if !self.is_enabled() {
+            return 0.0;
+        }
+        self.amount * self.sample_signed(input, rng)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DynamicsInput {
+    pub pressure: f32,
+    pub velocity: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BrushDynamics {
+    pub size_jitter: Dynamic,
+    pub angle_jitter: Dynamic,
+    pub opacity_jitter: Dynamic,
+    pub scatter: Dynamic,
+    pub dab_count: u32,
+    pub hue_jitter: Dynamic,
+    pub saturation_jitter: Dynamic,
+    pub value_jitter: Dynamic,
+    pub texture_strength: f32,
+    pub texture_scale: f32,
+    pub seed: u64,
+}
+
+impl Default for BrushDynamics {
+    fn default() -> Self {
+        Self {
+            size_jitter: Dynamic::default(),
+            angle_jitter: Dynamic::default(),
+            opacity_jitter: Dynamic::default(),
+            scatter: Dynamic::default(),
+            dab_count: 1,
+            hue_jitter: Dynamic::default(),
+            saturation_jitter: Dynamic::default(),
+            value_jitter: Dynamic::default(),
+            texture_strength: 0.0,
+            texture_scale: 4.0,
+            seed: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Dab {
+    pub offset_x: f32,
+    pub offset_y: f32,
+    pub size_factor: f32,
+    pub angle_offset: f32,
+    pub opacity_factor: f32,
+    pub color: Color32,
+}
+
+impl BrushDynamics {
+    pub fn is_identity(&self) -> bool {
+        self.dab_count <= 1
+            && !self.size_jitter.is_enabled()
+            && !self.angle_jitter.is_enabled()
+            && !self.opacity_jitter.is_enabled()
+            && !self.scatter.is_enabled()
+            && !self.has_color_jitter()
+            && self.texture_strength <= 0.0
+    }
+
+    fn has_color_jitter(&self) -> bool {
+        self.hue_jitter.is_enabled() || self.saturation_jitter.is_enabled() || self.value_jitter.is_enabled()
+    }
+
+    pub fn generate_dabs(&self, color: Color32, size: f32, stroke_angle: f32, input: DynamicsInput, rng: &mut DabRng) -> Vec<Dab> {
+        let normal_x = -stroke_angle.sin();
+        let normal_y = stroke_angle.cos();
+
+        (0..self.dab_count.clamp(1, MAX_DAB_COUNT))
+            .map(|_| {
+                let scatter = self.scatter.offset(input, rng) * size;
+                let dab_color = if self.has_color_jitter() {
+                    let (h, s, v) = rgb_to_hsv(color);
+                    hsv_to_rgb(
+                        h + self.hue_jitter.offset(input, rng) * 0.5,
+                        s + self.saturation_jitter.offset(input, rng),
+                        v + self.value_jitter.offset(input, rng),
+                        color.a(),
+                    )
+                } else {
+                    color
+                };
+
+                Dab {
+                    offset_x: normal_x * scatter,
+                    offset_y: normal_y * scatter,
+                    size_factor: self.size_jitter.reduce(input, rng),
+                    angle_offset: self.angle_jitter.offset(input, rng) * PI,
+                    opacity_factor: self.opacity_jitter.reduce(input, rng),
+                    color: dab_color,
+                }
+            })
+            .collect()
+    }
+
+    pub fn texture_factor(&self, x: i32, y: i32) -> f32 {
+        if self.texture_strength <= 0.0 {
+            return 1.0;
+        }
+        let grain = paper_grain(x as f32, y as f32, self.texture_scale.max(1.0), self.seed);
+        1.0 - self.texture_strength.clamp(0.0, 1.0) * (1.0 - grain)
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut egui::Ui, language: Language) -> bool {
+        let mut changed = false;
+
+        let mut dynamic_row = |ui: &mut egui::Ui, label: &str, dynamic: &mut Dynamic, id: &str| {
+            ui.horizontal(|ui| {
+                ui.label(format!("{}:", get_text(label, language)));
+                changed |= ui.add(egui::Slider::new(&mut dynamic.amount, 0.0..=1.0)).changed();
+                egui::ComboBox::from_id_source(id)
+                    .selected_text(dynamic.source.get_name(language))
+                    .show_ui(ui, |ui| {
+                        for source in DynamicsSource::all_sources() {
+                            changed |= ui.selectable_value(&mut dynamic.source, source, source.get_name(language)).changed();
+                        }
+                    });
+            });
+        };
+
+        dynamic_row(ui, "size_jitter", &mut self.size_jitter, "dyn_size");
+        dynamic_row(ui, "angle_jitter", &mut self.angle_jitter, "dyn_angle");
+        dynamic_row(ui, "opacity_jitter", &mut self.opacity_jitter, "dyn_opacity");
+        dynamic_row(ui, "scatter", &mut self.scatter, "dyn_scatter");
+        dynamic_row(ui, "hue_jitter", &mut self.hue_jitter, "dyn_hue");
+        dynamic_row(ui, "saturation_jitter", &mut self.saturation_jitter, "dyn_saturation");
+        dynamic_row(ui, "value_jitter", &mut self.value_jitter, "dyn_value");
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{}:", get_text("dab_count", language)));
+            changed |= ui.add(egui::Slider::new(&mut self.dab_count, 1..=MAX_DAB_COUNT)).changed();
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{}:", get_text("paper_texture", language)));
+            changed |= ui.add(egui::Slider::new(&mut self.texture_strength, 0.0..=1.0)).changed();
+        });
+
+        if self.texture_strength > 0.0 {
+            ui.horizontal(|ui| {
+                ui.label(format!("{}:", get_text("texture_scale", language)));
+                changed |= ui.add(egui::Slider::new(&mut self.texture_scale, 1.0..=32.0)).changed();
+            });
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{}:", get_text("dynamics_seed", language)));
+            changed |= ui.add(egui::DragValue::new(&mut self.seed)).changed();
+        });
+
+        changed
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DabRng {
+    state: u64,
+}
+
+impl DabRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// Derives the scatter/jitter seed of a stroke from the brush seed and the
+/// stroke's starting point, so replaying the same stroke yields the same dabs.
+pub fn stroke_seed(brush_seed: u64, start: (i32, i32)) -> u64 {
+    let hash = (start.0 as u32 as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+        ^ (start.1 as u32 as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F).rotate_left(32);
+    brush_seed ^ hash
+}
+
+fn lattice_value(x: i32, y: i32, seed: u64) -> f32 {
+    let hash = (x as u32 as u64).wrapping_mul(0x8DA6_B343)
+        ^ (y as u32 as u64).wrapping_mul(0xD816_3841)
+        ^ seed.wrapping_mul(0xCB1A_B31F);
+    DabRng::new(hash).next_f32()
+}
+
+fn paper_grain(x: f32, y: f32, scale: f32, seed: u64) -> f32 {
+    let mut value = 0.0;
+    let mut weight = 0.0;
+    let mut frequency = 1.0 / scale;
+    let mut amplitude = 1.0;
+
+    for octave in 0..3 {
+        let fx = x * frequency;
+        let fy = y * frequency;
+        let x0 = fx.floor();
+        let y0 = fy.floor();
+        let tx = fx - x0;
+        let ty = fy - y0;
+        let sx = tx * tx * (3.0 - 2.0 * tx);
+        let sy = ty * ty * (3.0 - 2.0 * ty);
+        let octave_seed = seed.wrapping_add(octave);
+
+        let corner = |dx: i32, dy: i32| lattice_value(x0 as i32 + dx, y0 as i32 + dy, octave_seed);
+        let top = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * sx;
+        let bottom = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * sx;
+
+        value += (top + (bottom - top) * sy) * amplitude;
+        weight += amplitude;
+        frequency *= 2.0;
+        amplitude *= 0.5;
+    }
+
+    value / weight
+}
@@ -2,6 +2,18 @@ use eframe::egui;
 use egui::{Color32, Vec2, Pos2, Rect, Stroke};
 use std::f32::consts::PI;
 use serde::{Serialize, Deserialize};
+use rfd::FileDialog;
+
+pub mod curves;
+pub mod dynamics;
+pub mod presets;
+pub mod stabilizer;
+pub mod tip;
+
+use curves::PressureCurve;
+use dynamics::{BrushDynamics, DabRng, DynamicsInput, stroke_seed};
+use presets::{BrushPreset, BrushPresetLibrary, BRUSH_FILE_EXTENSION};
+use tip::BrushTip;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum BrushType {
@@ -59,6 +71,18 @@ pub struct BrushProperties {
     pub pressure_affects_opacity: bool,
     pub pressure_size_min: f32,
     pub pressure_opacity_min: f32,
+    #[serde(default)]
+    pub tip: Option<BrushTip>,
+    #[serde(default)]
+    pub dynamics: BrushDynamics,
+    #[serde(default)]
+    pub pressure_affects_flow: bool,
+    #[serde(default)]
+    pub size_curve: PressureCurve,
+    #[serde(default)]
+    pub opacity_curve: PressureCurve,
+    #[serde(default)]
+    pub flow_curve: PressureCurve,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Debug)]
@@ -86,11 +110,39 @@ impl Default for BrushProperties {
             pressure_affects_opacity: true,
             pressure_size_min: 0.2,
             pressure_opacity_min: 0.1,
+            tip: None,
+            dynamics: BrushDynamics::default(),
+            pressure_affects_flow: false,
+            size_curve: PressureCurve::default(),
+            opacity_curve: PressureCurve::default(),
+            flow_curve: PressureCurve::default(),
         }
     }
 }
 
 impl BrushProperties {
+    pub fn pressure_size_factor(&self, pressure: f32) -> f32 {
+        if self.pressure_affects_size {
+            self.pressure_size_min + (1.0 - self.pressure_size_min) * self.size_curve.evaluate(pressure)
+        } else {
+            1.0
+        }
+    }
+    
+    pub fn pressure_opacity_factor(&self, pressure: f32) -> f32 {
+        let opacity = if self.pressure_affects_opacity {
+            self.pressure_opacity_min + (1.0 - self.pressure_opacity_min) * self.opacity_curve.evaluate(pressure)
+        } else {
+            1.0
+        };
+        let flow = if self.pressure_affects_flow {
+            self.flow_curve.evaluate(pressure)
+        } else {
+            1.0
+        };
+        opacity * flow
+    }
+    
     pub fn from_type(brush_type: BrushType) -> Self {
         let mut properties = Self::default();
         properties.brush_type = brush_type;
@@ -151,12 +203,212 @@ impl BrushProperties {
     }
 }
 
+pub fn generate_mask(active: &BrushProperties, current_angle: f32, size: usize) -> Vec<f32> {
+    let mut mask = vec![0.0; size * size];
+    let center = size as f32 / 2.0;
+    let radius = center;
+    let effective_angle = current_angle + active.base_rotation;
+    let cos_a = effective_angle.cos();
+    let sin_a = effective_angle.sin();
+    
+    if let Some(tip) = &active.tip {
+        return generate_tip_mask(tip, active.hardness, effective_angle, size);
+    }
+    
+    for y in 0..size {
+        for x in 0..size {
+            let rx = (x as f32 - center) / radius;
+            let ry = (y as f32 - center) / radius;
+            let mut value: f32 = 0.0;
+            
+            match active.brush_type {
+                BrushType::Round => {
+                    let dist = (rx * rx + ry * ry).sqrt();
+                    if dist <= 1.0 {
+                        value = 1.0;
+                    }
+                },
+                
+                BrushType::Flat => {
+                    let rx_rot = rx * cos_a - ry * sin_a;
+                    let ry_rot = rx * sin_a + ry * cos_a;
+                    
+                    if rx_rot.abs() <= 0.2 && ry_rot.abs() <= 1.0 {
+                        value = 1.0;
+                    }
+                },
+                
+                BrushType::Bright => {
+                    let rx_rot = rx * cos_a - ry * sin_a;
+                    let ry_rot = rx * sin_a + ry * cos_a;
+                    
+                    if rx_rot.abs() <= 0.3 && ry_rot.abs() <= 0.8 {
+                        value = 1.0;
+                    }
+                },
+                
+                BrushType::Filbert => {
+                    let rx_rot = rx * cos_a - ry * sin_a;
+                    let ry_rot = rx * sin_a + ry * cos_a;
+                    
+                    let ellipse_a = 0.6;
+                    let ellipse_b = 1.0;
+                    let ellipse_dist = (rx_rot * rx_rot) / (ellipse_a * ellipse_a) + 
+                                      (ry_rot * ry_rot) / (ellipse_b * ellipse_b);
+                    
+                    if ellipse_dist <= 1.0 {
+                        value = 1.0;
+                    }
+                },
+                
+                BrushType::Fan => {
+                    let angle_from_center = ry.atan2(rx) + std::f32::consts::PI;
+                    let dist = (rx * rx + ry * ry).sqrt();
+                    
+                    if dist <= 1.0 {
+                        let fan_segments = 5.0;
+                        let segment_width = std::f32::consts::PI * 0.9 / fan_segments;
+                        let normalized_angle = (angle_from_center % (std::f32::consts::PI * 2.0)) - std::f32::consts::PI * 0.55;
+                        
+                        for i in 0..5 {
+                            let segment_center = i as f32 * segment_width;
+                            let distance_from_segment = (normalized_angle - segment_center).abs();
+                            
+                            if distance_from_segment < segment_width * 0.4 {
+                                value = 1.0;
+                                break;
+                            }
+                        }
+                    }
+                },
+                
+                BrushType::Angle => {
+                    let rx_rot = rx * cos_a - ry * sin_a;
+                    let ry_rot = rx * sin_a + ry * cos_a;
+                    
+                    if ry_rot.abs() <= 0.7 {
+                        let left_edge = -0.8;
+                        let right_edge = 0.4;
+                        
+                        if rx_rot >= left_edge && rx_rot <= right_edge {
+                            value = 1.0;
+                        }
+                    }
+                },
+                
+                BrushType::Mop => {
+                    let dist = (rx * rx + ry * ry).sqrt();
+                    if dist <= 1.0 {
+                        value = 1.0;
+                    }
+                },
+                
+                BrushType::Rigger => {
+                    let rx_rot = rx * cos_a - ry * sin_a;
+                    let ry_rot = rx * sin_a + ry * cos_a;
+                    
+                    if rx_rot.abs() <= 0.08 && ry_rot.abs() <= 0.9 {
+                        value = 1.0;
+                    }
+                },
+            }
+            
+            if value > 0.0 && active.hardness < 1.0 {
+                value = value.powf(1.0 / active.hardness.max(0.1));
+            }
+            
+            mask[y * size + x] = value.clamp(0.0, 1.0);
+        }
+    }
+    
+    mask
+}
+
+fn generate_tip_mask(tip: &BrushTip, hardness: f32, angle: f32, size: usize) -> Vec<f32> {
+    let mut mask = vec![0.0; size * size];
+    let center = size as f32 / 2.0;
+    let longest_side = tip.width.max(tip.height) as f32;
+    let scale_x = tip.width as f32 / longest_side;
+    let scale_y = tip.height as f32 / longest_side;
+    let cos_a = angle.cos();
+    let sin_a = angle.sin();
+    
+    for y in 0..size {
+        for x in 0..size {
+            let rx = (x as f32 + 0.5 - center) / center;
+            let ry = (y as f32 + 0.5 - center) / center;
+            
+            let tx = rx * cos_a + ry * sin_a;
+            let ty = -rx * sin_a + ry * cos_a;
+            
+            let u = tx / scale_x * 0.5 + 0.5;
+            let v = ty / scale_y * 0.5 + 0.5;
+            
+            if (0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v) {
+                let mut value = tip.sample(u, v);
+                if value > 0.0 && hardness < 1.0 {
+                    value = value.powf(1.0 / hardness.max(0.1));
+                }
+                mask[y * size + x] = value.clamp(0.0, 1.0);
+            }
+        }
+    }
+    
+    mask
+}
+
+fn stamp_dab((x, y): (i32, i32), mask: &[f32], size: usize, color: Color32, opacity: f32, dynamics: &BrushDynamics, plot: &mut dyn FnMut(i32, i32, Option<Color32>)) {
+    let center = size as i32 / 2;
+    
+    for dy in 0..size as i32 {
+        for dx in 0..size as i32 {
+            let nx = x + dx - center;
+            let ny = y + dy - center;
+            let mask_value = mask[(dy as usize) * size + (dx as usize)];
+            
+            if mask_value > 0.0 {
+                let texture = dynamics.texture_factor(nx, ny);
+                let alpha = (color.a() as f32 * mask_value * opacity * texture) as u8;
+                let new_color = if alpha > 0 {
+                    Some(Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), alpha))
+                } else {
+                    None
+                };
+                
+                plot(nx, ny, new_color);
+            }
+        }
+    }
+}
+
+enum PresetAction {
+    Apply(usize),
+    ResetActive,
+    SaveActive,
+    Delete(usize),
+    Import,
+    Export(usize),
+    ExportAll,
+}
+
 pub struct BrushManager {
     pub brushes: Vec<BrushProperties>,
     pub active_brush_index: usize,
     pub current_angle: f32,
     pub last_position: Option<(f32, f32)>,
     pub current_size: f32,
+    pub current_velocity: f32,
+    pub wrap_size: Option<(usize, usize)>,
+    pub preset_library: BrushPresetLibrary,
+    pub pending_size: Option<f32>,
+    pub pending_error: Option<String>,
+    pub pending_tip_from_selection: bool,
+    tip_preview: Option<(usize, String, egui::TextureHandle)>,
+    preset_previews: Vec<egui::TextureHandle>,
+    previews_dirty: bool,
+    new_preset_name: String,
+    new_preset_category: String,
+    rng: DabRng,
 }
 
 impl Default for BrushManager {
@@ -173,6 +425,18 @@ impl Default for BrushManager {
             current_angle: 0.0,
             last_position: None,
             current_size: 3.0,
+            current_velocity: 0.0,
+            wrap_size: None,
+            preset_library: BrushPresetLibrary::load_default(),
+            pending_size: None,
+            pending_error: None,
+            pending_tip_from_selection: false,
+            tip_preview: None,
+            preset_previews: Vec::new(),
+            previews_dirty: true,
+            new_preset_name: String::new(),
+            new_preset_category: String::new(),
+            rng: DabRng::new(1),
         }
     }
 }
@@ -216,161 +480,67 @@ impl BrushManager {
     }
     
     pub fn generate_brush_mask(&self, size: usize) -> Vec<f32> {
+        generate_mask(self.active_brush(), self.current_angle, size)
+    }
+    
+    pub fn dab_mask(&mut self, x: i32, y: i32, pressure: f32) -> (Vec<f32>, usize, f32) {
+        let clamped_pressure = pressure.clamp(0.0, 1.0);
         let active = self.active_brush();
-        let mut mask = vec![0.0; size * size];
-        let center = size as f32 / 2.0;
-        let radius = center;
-        let effective_angle = self.current_angle + active.base_rotation;
-        let cos_a = effective_angle.cos();
-        let sin_a = effective_angle.sin();
-        
-        for y in 0..size {
-            for x in 0..size {
-                let rx = (x as f32 - center) / radius;
-                let ry = (y as f32 - center) / radius;
-                let mut value: f32 = 0.0;
-                
-                match active.brush_type {
-                    BrushType::Round => {
-                        let dist = (rx * rx + ry * ry).sqrt();
-                        if dist <= 1.0 {
-                            value = 1.0;
-                        }
-                    },
-                    
-                    BrushType::Flat => {
-                        let rx_rot = rx * cos_a - ry * sin_a;
-                        let ry_rot = rx * sin_a + ry * cos_a;
-                        
-                        if rx_rot.abs() <= 0.2 && ry_rot.abs() <= 1.0 {
-                            value = 1.0;
-                        }
-                    },
-                    
-                    BrushType::Bright => {
-                        let rx_rot = rx * cos_a - ry * sin_a;
-                        let ry_rot = rx * sin_a + ry * cos_a;
-                        
-                        if rx_rot.abs() <= 0.3 && ry_rot.abs() <= 0.8 {
-                            value = 1.0;
-                        }
-                    },
-                    
-                    BrushType::Filbert => {
-                        let rx_rot = rx * cos_a - ry * sin_a;
-                        let ry_rot = rx * sin_a + ry * cos_a;
-                        
-                        let ellipse_a = 0.6;
-                        let ellipse_b = 1.0;
-                        let ellipse_dist = (rx_rot * rx_rot) / (ellipse_a * ellipse_a) + 
-                                          (ry_rot * ry_rot) / (ellipse_b * ellipse_b);
-                        
-                        if ellipse_dist <= 1.0 {
-                            value = 1.0;
-                        }
-                    },
-                    
-                    BrushType::Fan => {
-                        let angle_from_center = ry.atan2(rx) + std::f32::consts::PI;
-                        let dist = (rx * rx + ry * ry).sqrt();
-                        
-                        if dist <= 1.0 {
-                            let fan_segments = 5.0;
-                            let segment_width = std::f32::consts::PI * 0.9 / fan_segments;
-                            let normalized_angle = (angle_from_center % (std::f32::consts::PI * 2.0)) - std::f32::consts::PI * 0.55;
-                            
-                            for i in 0..5 {
-                                let segment_center = i as f32 * segment_width;
-                                let distance_from_segment = (normalized_angle - segment_center).abs();
-                                
-                                if distance_from_segment < segment_width * 0.4 {
-                                    value = 1.0;
-                                    break;
-                                }
-                            }
-                        }
-                    },
-                    
-                    BrushType::Angle => {
-                        let rx_rot = rx * cos_a - ry * sin_a;
-                        let ry_rot = rx * sin_a + ry * cos_a;
-                        
-                        if ry_rot.abs() <= 0.7 {
-                            let left_edge = -0.8;
-                            let right_edge = 0.4;
-                            
-                            if rx_rot >= left_edge && rx_rot <= right_edge {
-                                value = 1.0;
-                            }
-                        }
-                    },
-                    
-                    BrushType::Mop => {
-                        let dist = (rx * rx + ry * ry).sqrt();
-                        if dist <= 1.0 {
-                            value = 1.0;
-                        }
-                    },
-                    
-                    BrushType::Rigger => {
-                        let rx_rot = rx * cos_a - ry * sin_a;
-                        let ry_rot = rx * sin_a + ry * cos_a;
-                        
-                        if rx_rot.abs() <= 0.08 && ry_rot.abs() <= 0.9 {
-                            value = 1.0;
-                        }
-                    },
-                }
-                
-                if value > 0.0 && active.hardness < 1.0 {
-                    value = value.powf(1.0 / active.hardness.max(0.1));
-                }
-                
-                mask[y * size + x] = value.clamp(0.0, 1.0);
-            }
-        }
+        let size_factor = active.pressure_size_factor(clamped_pressure);
+        let opacity = active.pressure_opacity_factor(clamped_pressure);
+        let size = (self.current_size * size_factor).max(1.0) as usize * 2 + 1;
         
-        mask
+        self.update_angle(x as f32, y as f32);
+        (self.generate_brush_mask(size), size, opacity)
+    }
+    
+    pub fn begin_stroke(&mut self, start: (i32, i32)) {
+        let seed = stroke_seed(self.active_brush().dynamics.seed, start);
+        self.rng = DabRng::new(seed);
+        self.current_angle = 0.0;
+        self.last_position = None;
     }
     
     pub fn draw_point(&mut self, x: i32, y: i32, color: Color32, pressure: f32, record_change: &mut dyn FnMut(usize, usize, Option<Color32>)) {
         let active = self.active_brush();
         let clamped_pressure = pressure.clamp(0.0, 1.0);
         
-        let effective_size = if active.pressure_affects_size {
-            let size_factor = active.pressure_size_min + (1.0 - active.pressure_size_min) * clamped_pressure;
-            (self.current_size as f32 * size_factor).max(1.0) as usize * 2 + 1
-        } else {
-            self.current_size as usize * 2 + 1
+        let size_factor = active.pressure_size_factor(clamped_pressure);
+        let effective_opacity = active.pressure_opacity_factor(clamped_pressure);
+        
+        self.update_angle(x as f32, y as f32);
+        
+        let wrap_size = self.wrap_size;
+        let mut plot = |nx: i32, ny: i32, new_color: Option<Color32>| {
+            let (nx, ny) = match wrap_size {
+                Some((width, height)) => (nx.rem_euclid(width as i32), ny.rem_euclid(height as i32)),
+                None => (nx, ny),
+            };
+            if nx >= 0 && ny >= 0 {
+                record_change(nx as usize, ny as usize, new_color);
+            }
         };
         
-        let effective_opacity = if active.pressure_affects_opacity {
-            active.pressure_opacity_min + (1.0 - active.pressure_opacity_min) * clamped_pressure
-        } else {
-            1.0
+        let dynamics = self.active_brush().dynamics.clone();
+        if dynamics.is_identity() {
+            let effective_size = (self.current_size * size_factor).max(1.0) as usize * 2 + 1;
+            let mask = self.generate_brush_mask(effective_size);
+            stamp_dab((x, y), &mask, effective_size, color, effective_opacity, &dynamics, &mut plot);
+            return;
+        }
+        
+        let input = DynamicsInput {
+            pressure: clamped_pressure,
+            velocity: self.current_velocity,
         };
+        let dabs = dynamics.generate_dabs(color, self.current_size, self.current_angle, input, &mut self.rng);
         
-        self.update_angle(x as f32, y as f32);
-        let mask = self.generate_brush_mask(effective_size);
-        let center = effective_size as i32 / 2;
-        
-        for dy in 0..effective_size as i32 {
-            for dx in 0..effective_size as i32 {
-                let nx = x + dx - center;
-                let ny = y + dy - center;
-                let mask_value = mask[(dy as usize) * effective_size + (dx as usize)];
-                
-                if mask_value > 0.0 && nx >= 0 && ny >= 0 {
-                    let alpha = (color.a() as f32 * mask_value * effective_opacity) as u8;
-                    let new_color = if alpha > 0 {
-                        Some(Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), alpha))
-                    } else {
-                        None
-                    };
-                    
-                    record_change(nx as usize, ny as usize, new_color);
-                }
-            }
+        for dab in dabs {
+            let dab_size = (self.current_size * size_factor * dab.size_factor).max(1.0) as usize * 2 + 1;
+            let mask = generate_mask(self.active_brush(), self.current_angle + dab.angle_offset, dab_size);
+            let dab_x = x + dab.offset_x.round() as i32;
+            let dab_y = y + dab.offset_y.round() as i32;
+            stamp_dab((dab_x, dab_y), &mask, dab_size, dab.color, effective_opacity * dab.opacity_factor, &dynamics, &mut plot);
         }
     }
     
@@ -426,7 +596,7 @@ impl BrushManager {
         }
     }
     
-    pub fn brush_selector_grid(&mut self, ui: &mut egui::Ui, _ctx: &egui::Context, language: crate::localization::Language) -> bool {
+    pub fn brush_selector_grid(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, language: crate::localization::Language) -> bool {
         use crate::localization::get_text;
         let mut changed = false;
         
@@ -518,9 +688,312 @@ impl BrushManager {
                 }
             });
         
+        self.tip_section(ui, ctx, language);
+        
+        if self.preset_section(ui, ctx, language) {
+            changed = true;
+        }
+        
         changed
     }
     
+    pub fn set_active_tip(&mut self, tip: Option<BrushTip>) {
+        self.active_brush_mut().tip = tip;
+        self.tip_preview = None;
+    }
+    
+    fn tip_section(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, language: crate::localization::Language) {
+        use crate::localization::get_text;
+        
+        ui.add_space(8.0);
+        ui.heading(get_text("brush_tip", language));
+        ui.separator();
+        
+        let active_index = self.active_brush_index;
+        let tip_info = self.active_brush().tip.as_ref().map(|tip| (tip.name.clone(), tip.width, tip.height));
+        if let Some((tip_name, tip_width, tip_height)) = tip_info {
+            let cached = matches!(&self.tip_preview, Some((index, name, _)) if *index == active_index && *name == tip_name);
+            if !cached {
+                self.tip_preview = self.active_brush().tip.as_ref().map(|tip| {
+                    let texture = ctx.load_texture("brush_tip_preview", tip.preview_image(), egui::TextureOptions::LINEAR);
+                    (active_index, tip_name.clone(), texture)
+                });
+            }
+            
+            ui.horizontal(|ui| {
+                if let Some((_, _, texture)) = &self.tip_preview {
+                    let aspect = tip_width as f32 / tip_height as f32;
+                    let preview_size = if aspect >= 1.0 {
+                        Vec2::new(48.0, 48.0 / aspect)
+                    } else {
+                        Vec2::new(48.0 * aspect, 48.0)
+                    };
+                    ui.image(texture.id(), preview_size);
+                }
+                ui.label(format!("{} ({}x{})", tip_name, tip_width, tip_height));
+            });
+        } else {
+            ui.label(egui::RichText::new(get_text("procedural_tip", language)).small().weak());
+        }
+        
+        let mut loaded = None;
+        
+        ui.horizontal_wrapped(|ui| {
+            if ui.button(get_text("load_tip_image", language)).clicked() {
+                loaded = FileDialog::new()
+                    .add_filter("PNG Image", &["png"])
+                    .pick_file()
+                    .map(|path| BrushTip::from_png(&path, language));
+            }
+            if ui.button(get_text("import_gbr", language)).clicked() {
+                loaded = FileDialog::new()
+                    .add_filter("GIMP Brush", &["gbr"])
+                    .pick_file()
+                    .map(|path| BrushTip::from_gbr(&path, language));
+            }
+            if ui.button(get_text("tip_from_selection", language)).clicked() {
+                self.pending_tip_from_selection = true;
+            }
+            if self.active_brush().tip.is_some() && ui.button(get_text("clear_tip", language)).clicked() {
+                self.set_active_tip(None);
+            }
+        });
+        
+        match loaded {
+            Some(Ok(tip)) => self.set_active_tip(Some(tip)),
+            Some(Err(e)) => self.pending_error = Some(e),
+            None => {}
+        }
+    }
+    
+    pub fn save_active_as_preset(&mut self, name: String, category: String, language: crate::localization::Language) -> Result<(), String> {
+        let mut properties = self.active_brush().clone();
+        properties.size = self.current_size;
+        
+        self.preset_library.add(BrushPreset {
+            name,
+            category,
+            properties,
+        });
+        self.previews_dirty = true;
+        self.preset_library.save(language)
+    }
+    
+    pub fn reset_active_brush(&mut self) {
+        let brush_type = self.active_brush().brush_type;
+        *self.active_brush_mut() = BrushProperties::from_type(brush_type);
+        self.tip_preview = None;
+    }
+    
+    pub fn apply_preset(&mut self, index: usize) {
+        if let Some(preset) = self.preset_library.presets.get(index) {
+            let properties = preset.properties.clone();
+            let slot = self.brushes
+                .iter()
+                .position(|brush| brush.brush_type == properties.brush_type)
+                .unwrap_or(self.active_brush_index);
+            
+            self.pending_size = Some(properties.size);
+            self.brushes[slot] = properties;
+            self.active_brush_index = slot;
+        }
+    }
+    
+    fn refresh_preset_previews(&mut self, ctx: &egui::Context) {
+        if self.previews_dirty || self.preset_previews.len() != self.preset_library.presets.len() {
+            self.preset_previews = self.preset_library.presets
+                .iter()
+                .enumerate()
+                .map(|(i, preset)| {
+                    ctx.load_texture(
+                        format!("brush_preset_{}", i),
+                        presets::render_preset_preview(&preset.properties),
+                        egui::TextureOptions::LINEAR,
+                    )
+                })
+                .collect();
+            self.previews_dirty = false;
+        }
+    }
+    
+    fn preset_section(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, language: crate::localization::Language) -> bool {
+        use crate::localization::get_text;
+        let mut action = None;
+        
+        ui.add_space(8.0);
+        ui.heading(get_text("brush_presets", language));
+        ui.separator();
+        
+        ui.horizontal(|ui| {
+            ui.label(get_text("preset_name", language));
+            ui.text_edit_singleline(&mut self.new_preset_name);
+        });
+        ui.horizontal(|ui| {
+            ui.label(get_text("preset_category", language));
+            ui.text_edit_singleline(&mut self.new_preset_category);
+        });
+        
+        ui.horizontal(|ui| {
+            if ui.button(get_text("save_as_preset", language)).clicked() && !self.new_preset_name.trim().is_empty() {
+                action = Some(PresetAction::SaveActive);
+            }
+            if ui.button(get_text("reset_brush", language)).clicked() {
+                action = Some(PresetAction::ResetActive);
+            }
+            if ui.button(get_text("import_presets", language)).clicked() {
+                action = Some(PresetAction::Import);
+            }
+            if ui.button(get_text("export_presets", language)).clicked() && !self.preset_library.presets.is_empty() {
+                action = Some(PresetAction::ExportAll);
+            }
+        });
+        
+        self.refresh_preset_previews(ctx);
+        
+        if self.preset_library.presets.is_empty() {
+            ui.label(egui::RichText::new(get_text("no_presets", language)).small().weak());
+        }
+        
+        let cell_size = Vec2::new(72.0, 64.0);
+        let columns = (ui.available_width() / (cell_size.x + 8.0)).floor().max(1.0) as usize;
+        
+        for category in self.preset_library.categories() {
+            ui.collapsing(&category, |ui| {
+                egui::Grid::new(("brush_preset_grid", category.as_str()))
+                    .spacing([8.0, 8.0])
+                    .show(ui, |ui| {
+                        let mut col = 0;
+                        
+                        for (i, preset) in self.preset_library.presets.iter().enumerate() {
+                            if preset.category != category {
+                                continue;
+                            }
+                            
+                            let (rect, response) = ui.allocate_exact_size(cell_size, egui::Sense::click());
+                            ui.painter().rect_filled(
+                                rect,
+                                4.0,
+                                if response.hovered() {
+                                    ui.style().visuals.widgets.hovered.bg_fill
+                                } else {
+                                    ui.style().visuals.widgets.inactive.bg_fill
+                                }
+                            );
+                            
+                            if let Some(texture) = self.preset_previews.get(i) {
+                                let preview_rect = Rect::from_min_size(
+                                    rect.min + Vec2::new(4.0, 4.0),
+                                    Vec2::new(cell_size.x - 8.0, cell_size.y - 24.0)
+                                );
+                                ui.painter().image(
+                                    texture.id(),
+                                    preview_rect,
+                                    Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)),
+                                    Color32::WHITE
+                                );
+                            }
+                            
+                            ui.painter().text(
+                                Pos2::new(rect.center().x, rect.max.y - 10.0),
+                                egui::Align2::CENTER_CENTER,
+                                &preset.name,
+                                egui::FontId::proportional(11.0),
+                                ui.style().visuals.text_color(),
+                            );
+                            
+                            if response.clicked() {
+                                action = Some(PresetAction::Apply(i));
+                            }
+                            
+                            response.context_menu(|ui| {
+                                if ui.button(get_text("export_preset", language)).clicked() {
+                                    action = Some(PresetAction::Export(i));
+                                    ui.close_menu();
+                                }
+                                if ui.button(get_text("delete", language)).clicked() {
+                                    action = Some(PresetAction::Delete(i));
+                                    ui.close_menu();
+                                }
+                            });
+                            
+                            col += 1;
+                            if col >= columns {
+                                col = 0;
+                                ui.end_row();
+                            }
+                        }
+                    });
+            });
+        }
+        
+        let result = match action {
+            Some(PresetAction::Apply(index)) => {
+                self.apply_preset(index);
+                return true;
+            },
+            Some(PresetAction::ResetActive) => {
+                self.reset_active_brush();
+                return true;
+            },
+            Some(PresetAction::SaveActive) => {
+                let category = if self.new_preset_category.trim().is_empty() {
+                    get_text("default_preset_category", language)
+                } else {
+                    self.new_preset_category.trim().to_string()
+                };
+                let name = self.new_preset_name.trim().to_string();
+                self.new_preset_name.clear();
+                self.save_active_as_preset(name, category, language)
+            },
+            Some(PresetAction::Delete(index)) => {
+                self.preset_library.remove(index);
+                self.previews_dirty = true;
+                self.preset_library.save(language)
+            },
+            Some(PresetAction::Import) => {
+                if let Some(path) = FileDialog::new()
+                    .add_filter("Rustique Brush", &[BRUSH_FILE_EXTENSION])
+                    .pick_file() {
+                    self.previews_dirty = true;
+                    self.preset_library
+                        .import_file(&path, language)
+                        .and_then(|_| self.preset_library.save(language))
+                } else {
+                    Ok(())
+                }
+            },
+            Some(PresetAction::Export(index)) => {
+                let file_name = presets::preset_file_name(&self.preset_library.presets[index].name);
+                if let Some(path) = FileDialog::new()
+                    .add_filter("Rustique Brush", &[BRUSH_FILE_EXTENSION])
+                    .set_file_name(&file_name)
+                    .save_file() {
+                    self.preset_library.export_file(&path, &[index], language)
+                } else {
+                    Ok(())
+                }
+            },
+            Some(PresetAction::ExportAll) => {
+                if let Some(path) = FileDialog::new()
+                    .add_filter("Rustique Brush", &[BRUSH_FILE_EXTENSION])
+                    .set_file_name(&format!("presets.{}", BRUSH_FILE_EXTENSION))
+                    .save_file() {
+                    let indices: Vec<usize> = (0..self.preset_library.presets.len()).collect();
+                    self.preset_library.export_file(&path, &indices, language)
+                } else {
+                    Ok(())
+                }
+            },
+            None => Ok(()),
+        };
+        
+        if let Err(e) = result {
+            self.pending_error = Some(e);
+        }
+        
+        false
+    }
+    
     fn draw_brush_preview(&self, ui: &mut egui::Ui, rect: egui::Rect, brush_type: &BrushType) {
         let painter = ui.painter();
         painter.rect_filled(rect, 4.0, Color32::from_gray(240));
@@ -613,3 +1086,56 @@ impl BrushManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_stroke(manager: &mut BrushManager, points: &[(i32, i32)]) -> Vec<(usize, usize, Option<Color32>)> {
+        let mut changes = Vec::new();
+        manager.begin_stroke(points[0]);
+        for &(x, y) in points {
+            manager.draw_point(x, y, Color32::from_rgb(200, 40, 40), 0.8, &mut |px, py, color| {
+                changes.push((px, py, color));
+            });
+        }
+        changes
+    }
+
+    fn scattered_manager() -> BrushManager {
+        let mut manager = BrushManager::new();
+        manager.current_size = 6.0;
+        let dynamics = &mut manager.active_brush_mut().dynamics;
+        dynamics.scatter.amount = 1.0;
+        dynamics.size_jitter.amount = 0.5;
+        dynamics.opacity_jitter.amount = 0.5;
+        dynamics.dab_count = 3;
+        manager
+    }
+
+    #[test]
+    fn same_stroke_renders_identically() {
+        let stroke = [(20, 20), (24, 22), (28, 25), (33, 27)];
+        let mut manager = scattered_manager();
+
+        let first = render_stroke(&mut manager, &stroke);
+        render_stroke(&mut manager, &[(60, 60), (64, 61)]);
+        let second = render_stroke(&mut manager, &stroke);
+
+        assert!(!first.is_empty());
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn different_strokes_use_different_seeds() {
+        let mut manager = scattered_manager();
+
+        let first = render_stroke(&mut manager, &[(20, 20), (24, 22)]);
+        let moved: Vec<_> = render_stroke(&mut manager, &[(21, 20), (25, 22)])
+            .into_iter()
+            .map(|(x, y, color)| (x - 1, y, color))
+            .collect();
+
+        assert_ne!(first, moved);
+    }
+}
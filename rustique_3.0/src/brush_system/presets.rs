@@ -0,0 +1,226 @@
+use eframe::egui;
+use egui::Color32;
+use serde::{Serialize, Deserialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{BrushProperties, generate_mask};
+use crate::localization::{Language, get_text};
+
+pub const BRUSH_FILE_EXTENSION: &str = "rustiqbrush";
+const BRUSH_FILE_VERSION: u32 = 1;
+const PRESET_LIBRARY_PATH: &str = "brush_presets.rustiqbrush";
+const PREVIEW_WIDTH: usize = 64;
+const PREVIEW_HEIGHT: usize = 40;
+const DEFAULT_PRESET_FILE_STEM: &str = "preset";
+const RESERVED_FILE_CHARS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+const RESERVED_FILE_STEMS: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL",
+    "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+    "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BrushPreset {
+    pub name: String,
+    pub category: String,
+    pub properties: BrushProperties,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RustiqueBrushFile {
+    version: u32,
+    presets: Vec<BrushPreset>,
+}
+
+pub struct BrushPresetLibrary {
+    pub presets: Vec<BrushPreset>,
+    library_path: PathBuf,
+}
+
+impl BrushPresetLibrary {
+    pub fn load_default() -> Self {
+        let library_path = PathBuf::from(PRESET_LIBRARY_PATH);
+        let presets = read_brush_file(&library_path, Language::English).unwrap_or_default();
+
+        Self {
+            presets,
+            library_path,
+        }
+    }
+
+    pub fn save(&self, language: Language) -> Result<(), String> {
+        write_brush_file(&self.library_path, &self.presets, language)
+    }
+
+    pub fn add(&mut self, preset: BrushPreset) {
+        if let Some(existing) = self.presets.iter_mut()
+            .find(|p| p.name == preset.name && p.category == preset.category) {
+            *existing = preset;
+        } else {
+            self.presets.push(preset);
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.presets.len() {
+            self.presets.remove(index);
+        }
+    }
+
+    pub fn categories(&self) -> Vec<String> {
+        let mut categories: Vec<String> = Vec::new();
+        for preset in &self.presets {
+            if !categories.contains(&preset.category) {
+                categories.push(preset.category.clone());
+            }
+        }
+        categories
+    }
+
+    pub fn import_file(&mut self, path: &Path, language: Language) -> Result<usize, String> {
+        let presets = read_brush_file(path, language)?;
+        let count = presets.len();
+        for preset in presets {
+            self.add(preset);
+        }
+        Ok(count)
+    }
+
+    pub fn export_file(&self, path: &Path, indices: &[usize], language: Language) -> Result<(), String> {
+        let presets: Vec<BrushPreset> = indices
+            .iter()
+            .filter_map(|&i| self.presets.get(i).cloned())
+            .collect();
+        write_brush_file(path, &presets, language)
+    }
+}
+
+fn read_brush_file(path: &Path, language: Language) -> Result<Vec<BrushPreset>, String> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("{}: {}", get_text("error_reading_file", language), e))?;
+    let file = serde_json::from_str::<RustiqueBrushFile>(&content)
+        .map_err(|e| format!("{}: {}", get_text("error_reading_brush_file", language), e))?;
+
+    if file.version > BRUSH_FILE_VERSION {
+        return Err(format!("{}: v{}", get_text("unsupported_brush_file_version", language), file.version));
+    }
+
+    Ok(file.presets)
+}
+
+fn write_brush_file(path: &Path, presets: &[BrushPreset], language: Language) -> Result<(), String> {
+    let file = RustiqueBrushFile {
+        version: BRUSH_FILE_VERSION,
+        presets: presets.to_vec(),
+    };
+
+    let json = serde_json::to_string_pretty(&file)
+        .map_err(|e| format!("{}: {}", get_text("error_writing_brush_file", language), e))?;
+    fs::write(path, json)
+        .map_err(|e| format!("{}: {}", get_text("error_writing_brush_file", language), e))
+}
+
+/// Builds a file name for exporting a preset from its user-entered name,
+/// dropping path separators, `..` and characters rejected by common file systems.
+pub fn preset_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| if c.is_control() || RESERVED_FILE_CHARS.contains(&c) { '_' } else { c })
+        .collect();
+
+    let mut stem = cleaned;
+    while stem.contains("..") {
+        stem = stem.replace("..", ".");
+    }
+    let stem = stem.trim_matches(|c: char| c == '.' || c.is_whitespace());
+
+    let stem = if stem.is_empty() {
+        DEFAULT_PRESET_FILE_STEM.to_string()
+    } else if RESERVED_FILE_STEMS.iter().any(|reserved| reserved.eq_ignore_ascii_case(stem)) {
+        format!("_{}", stem)
+    } else {
+        stem.to_string()
+    };
+
+    format!("{}.{}", stem, BRUSH_FILE_EXTENSION)
+}
+
+pub fn render_preset_preview(properties: &BrushProperties) -> egui::ColorImage {
+    let mut coverage = vec![0.0_f32; PREVIEW_WIDTH * PREVIEW_HEIGHT];
+    let dab_size = ((PREVIEW_HEIGHT as f32 * 0.45) as usize) | 1;
+    let margin = dab_size as f32 * 0.5 + 2.0;
+    let steps = 48;
+
+    for i in 0..=steps {
+        let t = i as f32 / steps as f32;
+        let x = margin + t * (PREVIEW_WIDTH as f32 - margin * 2.0);
+        let wave = (t * std::f32::consts::PI * 2.0).sin();
+        let y = PREVIEW_HEIGHT as f32 * 0.5 - wave * PREVIEW_HEIGHT as f32 * 0.2;
+        let slope = -(t * std::f32::consts::PI * 2.0).cos() * PREVIEW_HEIGHT as f32 * 0.4 * std::f32::consts::PI;
+        let angle = slope.atan2(PREVIEW_WIDTH as f32 - margin * 2.0);
+
+        let pressure = (t * std::f32::consts::PI).sin().max(0.15);
+        let size_factor = properties.pressure_size_factor(pressure);
+        let opacity = properties.pressure_opacity_factor(pressure);
+
+        let size = ((dab_size as f32 * size_factor).max(3.0) as usize) | 1;
+        let mask = generate_mask(properties, angle, size);
+        let center = size as i32 / 2;
+
+        for dy in 0..size as i32 {
+            for dx in 0..size as i32 {
+                let px = x as i32 + dx - center;
+                let py = y as i32 + dy - center;
+                if px < 0 || py < 0 || px >= PREVIEW_WIDTH as i32 || py >= PREVIEW_HEIGHT as i32 {
+                    continue;
+                }
+                let value = mask[dy as usize * size + dx as usize] * opacity;
+                let idx = py as usize * PREVIEW_WIDTH + px as usize;
+                coverage[idx] = coverage[idx].max(value);
+            }
+        }
+    }
+
+    let pixels = coverage
+        .iter()
+        .map(|&c| {
+            let shade = 240 - (c.clamp(0.0, 1.0) * 240.0) as u8;
+            Color32::from_gray(shade)
+        })
+        .collect();
+
+    egui::ColorImage {
+        size: [PREVIEW_WIDTH, PREVIEW_HEIGHT],
+        pixels,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preset_file_name_keeps_plain_names() {
+        assert_eq!(preset_file_name("Soft Ink"), "Soft Ink.rustiqbrush");
+    }
+
+    #[test]
+    fn preset_file_name_strips_path_components() {
+        assert_eq!(preset_file_name("../../etc/passwd"), "_._etc_passwd.rustiqbrush");
+        assert_eq!(preset_file_name("a\\b:c*d?e\"f<g>h|i"), "a_b_c_d_e_f_g_h_i.rustiqbrush");
+        assert!(!preset_file_name("x/../y").contains(".."));
+    }
+
+    #[test]
+    fn preset_file_name_falls_back_when_empty() {
+        assert_eq!(preset_file_name(""), "preset.rustiqbrush");
+        assert_eq!(preset_file_name(" .. "), "preset.rustiqbrush");
+    }
+
+    #[test]
+    fn preset_file_name_avoids_reserved_device_names() {
+        assert_eq!(preset_file_name("con"), "_con.rustiqbrush");
+        assert_eq!(preset_file_name("Console"), "Console.rustiqbrush");
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::VecDeque;
+
+const MIN_SPLINE_DISTANCE: f32 = 2.0;
+const SPLINE_STEP: f32 = 2.0;
+const CATCH_UP_STEPS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StabilizerMode {
+    None,
+    MovingAverage,
+    PulledString,
+    CatmullRom,
+}
+
+impl StabilizerMode {
+    pub fn get_name(&self, language: crate::localization::Language) -> String {
+        use crate::localization::get_text;
+        match self {
+            StabilizerMode::None => get_text("stabilizer_none", language),
+            StabilizerMode::MovingAverage => get_text("stabilizer_average", language),
+            StabilizerMode::PulledString => get_text("stabilizer_string", language),
+            StabilizerMode::CatmullRom => get_text("stabilizer_spline", language),
+        }
+    }
+
+    pub fn all_modes() -> Vec<StabilizerMode> {
+        vec![
+            StabilizerMode::None,
+            StabilizerMode::MovingAverage,
+            StabilizerMode::PulledString,
+            StabilizerMode::CatmullRom,
+        ]
+    }
+}
+
+pub struct Stabilizer {
+    pub mode: StabilizerMode,
+    pub window_size: usize,
+    pub string_radius: f32,
+    pub catch_up: bool,
+    samples: VecDeque<(f32, f32)>,
+    anchor: Option<(f32, f32)>,
+    last_input: Option<(f32, f32)>,
+    active: bool,
+}
+
+impl Default for Stabilizer {
+    fn default() -> Self {
+        Self {
+            mode: StabilizerMode::None,
+            window_size: 8,
+            string_radius: 20.0,
+            catch_up: true,
+            samples: VecDeque::new(),
+            anchor: None,
+            last_input: None,
+            active: false,
+        }
+    }
+}
+
+impl Stabilizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn anchor(&self) -> Option<(f32, f32)> {
+        if self.active && self.mode == StabilizerMode::PulledString {
+            self.anchor
+        } else {
+            None
+        }
+    }
+
+    pub fn begin(&mut self, x: f32, y: f32) -> Vec<(f32, f32)> {
+        self.samples.clear();
+        self.samples.push_back((x, y));
+        self.anchor = Some((x, y));
+        self.last_input = Some((x, y));
+        self.active = true;
+        vec![(x, y)]
+    }
+
+    pub fn push(&mut self, x: f32, y: f32) -> Vec<(f32, f32)> {
+        if !self.active {
+            return self.begin(x, y);
+        }
+        self.last_input = Some((x, y));
+
+        match self.mode {
+            StabilizerMode::None => vec![(x, y)],
+            StabilizerMode::MovingAverage => {
+                self.samples.push_back((x, y));
+                while self.samples.len() > self.window_size.max(1) {
+                    self.samples.pop_front();
+                }
+                vec![average(&self.samples)]
+            },
+            StabilizerMode::PulledString => {
+                let (ax, ay) = self.anchor.unwrap_or((x, y));
+                let dx = x - ax;
+                let dy = y - ay;
+                let distance = (dx * dx + dy * dy).sqrt();
+
+                if distance > self.string_radius {
+                    let pull = (distance - self.string_radius) / distance;
+                    let moved = (ax + dx * pull, ay + dy * pull);
+                    self.anchor = Some(moved);
+                    vec![moved]
+                } else {
+                    Vec::new()
+                }
+            },
+            StabilizerMode::CatmullRom => {
+                let too_close = self.samples
+                    .back()
+                    .is_some_and(|&(lx, ly)| ((x - lx).powi(2) + (y - ly).powi(2)).sqrt() < MIN_SPLINE_DISTANCE);
+                if too_close {
+                    return Vec::new();
+                }
+
+                self.samples.push_back((x, y));
+                if self.samples.len() == 3 {
+                    let first = self.samples[0];
+                    self.samples.push_front(first);
+                }
+
+                if self.samples.len() >= 4 {
+                    let points = spline_segment(self.samples[0], self.samples[1], self.samples[2], self.samples[3]);
+                    self.samples.pop_front();
+                    points
+                } else {
+                    Vec::new()
+                }
+            },
+        }
+    }
+
+    pub fn finish(&mut self) -> Vec<(f32, f32)> {
+        if !self.active {
+            return Vec::new();
+        }
+        self.active = false;
+
+        let target = match self.last_input {
+            Some(target) => target,
+            None => return Vec::new(),
+        };
+
+        let points = if !self.catch_up {
+            Vec::new()
+        } else {
+            match self.mode {
+                StabilizerMode::None => Vec::new(),
+                StabilizerMode::MovingAverage => {
+                    let mut points = Vec::new();
+                    while self.samples.len() > 1 {
+                        self.samples.pop_front();
+                        points.push(average(&self.samples));
+                    }
+                    points
+                },
+                StabilizerMode::PulledString => {
+                    let (ax, ay) = self.anchor.unwrap_or(target);
+                    (1..=CATCH_UP_STEPS)
+                        .map(|i| {
+                            let t = i as f32 / CATCH_UP_STEPS as f32;
+                            (ax + (target.0 - ax) * t, ay + (target.1 - ay) * t)
+                        })
+                        .collect()
+                },
+                StabilizerMode::CatmullRom => {
+                    if self.samples.back() != Some(&target) {
+                        self.samples.push_back(target);
+                    }
+                    if self.samples.len() == 3 {
+                        let first = self.samples[0];
+                        self.samples.push_front(first);
+                    }
+
+                    let mut points = Vec::new();
+                    if self.samples.len() == 2 {
+                        points.push(target);
+                    }
+                    while self.samples.len() >= 3 {
+                        let last = *self.samples.back().unwrap();
+                        self.samples.push_back(last);
+                        points.extend(spline_segment(self.samples[0], self.samples[1], self.samples[2], self.samples[3]));
+                        self.samples.pop_front();
+                        self.samples.pop_back();
+                    }
+                    points
+                },
+            }
+        };
+
+        self.samples.clear();
+        self.anchor = None;
+        self.last_input = None;
+        points
+    }
+}
+
+fn average(samples: &VecDeque<(f32, f32)>) -> (f32, f32) {
+    let count = samples.len().max(1) as f32;
+    let (sum_x, sum_y) = samples
+        .iter()
+        .fold((0.0, 0.0), |(sx, sy), &(x, y)| (sx + x, sy + y));
+    (sum_x / count, sum_y / count)
+}
+
+fn spline_segment(p0: (f32, f32), p1: (f32, f32), p2: (f32, f32), p3: (f32, f32)) -> Vec<(f32, f32)> {
+    let length = ((p2.0 - p1.0).powi(2) + (p2.1 - p1.1).powi(2)).sqrt();
+    let steps = (length / SPLINE_STEP).ceil().max(1.0) as usize;
+
+    (1..=steps)
+        .map(|i| {
+            let t = i as f32 / steps as f32;
+            let t2 = t * t;
+            let t3 = t2 * t;
+            let blend = |a: f32, b: f32, c: f32, d: f32| {
+                0.5 * (2.0 * b
+                    + (-a + c) * t
+                    + (2.0 * a - 5.0 * b + 4.0 * c - d) * t2
+                    + (-a + 3.0 * b - 3.0 * c + d) * t3)
+            };
+            (blend(p0.0, p1.0, p2.0, p3.0), blend(p0.1, p1.1, p2.1, p3.1))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stabilizer(mode: StabilizerMode) -> Stabilizer {
+        Stabilizer { mode, ..Stabilizer::default() }
+    }
+
+    fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 1e-3 && (a.1 - b.1).abs() < 1e-3
+    }
+
+    #[test]
+    fn moving_average_uses_the_window() {
+        let mut stabilizer = Stabilizer { window_size: 2, ..stabilizer(StabilizerMode::MovingAverage) };
+        stabilizer.begin(0.0, 0.0);
+        assert_eq!(stabilizer.push(10.0, 0.0), vec![(5.0, 0.0)]);
+        assert_eq!(stabilizer.push(20.0, 10.0), vec![(15.0, 5.0)]);
+        assert_eq!(stabilizer.finish(), vec![(20.0, 10.0)]);
+    }
+
+    #[test]
+    fn string_radius_is_a_dead_zone() {
+        let mut stabilizer = Stabilizer { string_radius: 10.0, ..stabilizer(StabilizerMode::PulledString) };
+        stabilizer.begin(0.0, 0.0);
+        assert!(stabilizer.push(6.0, 8.0).is_empty());
+        assert!(stabilizer.push(-10.0, 0.0).is_empty());
+        assert_eq!(stabilizer.anchor(), Some((0.0, 0.0)));
+
+        let moved = stabilizer.push(25.0, 0.0);
+        assert_eq!(moved.len(), 1);
+        assert!(close(moved[0], (15.0, 0.0)));
+        assert_eq!(stabilizer.anchor(), Some(moved[0]));
+    }
+
+    #[test]
+    fn catch_up_reaches_the_last_input() {
+        for mode in [StabilizerMode::MovingAverage, StabilizerMode::PulledString, StabilizerMode::CatmullRom] {
+            let mut stabilizer = stabilizer(mode);
+            stabilizer.begin(0.0, 0.0);
+            for i in 1..=10 {
+                stabilizer.push(i as f32 * 7.0, (i % 3) as f32 * 5.0);
+            }
+            let last = *stabilizer.finish().last().unwrap();
+            assert!(close(last, (70.0, 5.0)), "{mode:?} ended at {last:?}");
+            assert!(!stabilizer.is_active());
+        }
+    }
+
+    #[test]
+    fn finish_without_catch_up_adds_nothing() {
+        let mut stabilizer = Stabilizer { catch_up: false, ..stabilizer(StabilizerMode::PulledString) };
+        stabilizer.begin(0.0, 0.0);
+        stabilizer.push(50.0, 0.0);
+        assert!(stabilizer.finish().is_empty());
+    }
+
+    #[test]
+    fn spline_passes_through_its_control_points() {
+        let inputs = [(0.0, 0.0), (10.0, 0.0), (20.0, 10.0), (30.0, 10.0), (40.0, 0.0)];
+        let mut stabilizer = stabilizer(StabilizerMode::CatmullRom);
+        let mut points = stabilizer.begin(inputs[0].0, inputs[0].1);
+        for &(x, y) in &inputs[1..] {
+            points.extend(stabilizer.push(x, y));
+        }
+        points.extend(stabilizer.finish());
+
+        for input in inputs {
+            assert!(points.iter().any(|&point| close(point, input)), "{input:?} missing from {points:?}");
+        }
+    }
+
+    #[test]
+    fn spline_skips_inputs_closer_than_the_minimum_distance() {
+        let mut stabilizer = stabilizer(StabilizerMode::CatmullRom);
+        stabilizer.begin(0.0, 0.0);
+        assert!(stabilizer.push(1.0, 0.0).is_empty());
+        assert_eq!(stabilizer.samples.len(), 1);
+    }
+}
@@ -0,0 +1,267 @@
+use egui::Color32;
+use serde::{Serialize, Deserialize};
+use std::fs;
+use std::path::Path;
+
+use crate::localization::{Language, get_text};
+
+const GBR_MAGIC: &[u8; 4] = b"GIMP";
+const MAX_TIP_SIZE: u32 = 1024;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "StoredTip")]
+pub struct BrushTip {
+    pub name: String,
+    pub width: usize,
+    pub height: usize,
+    pub data: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+struct StoredTip {
+    name: String,
+    width: usize,
+    height: usize,
+    data: Vec<u8>,
+}
+
+impl TryFrom<StoredTip> for BrushTip {
+    type Error = String;
+
+    fn try_from(stored: StoredTip) -> Result<Self, String> {
+        let max = MAX_TIP_SIZE as usize;
+        if stored.width == 0 || stored.height == 0 || stored.width > max || stored.height > max {
+            return Err(format!("brush tip size {}x{}", stored.width, stored.height));
+        }
+        if stored.data.len() != stored.width * stored.height {
+            return Err(format!("brush tip has {} values for {}x{}", stored.data.len(), stored.width, stored.height));
+        }
+
+        Ok(Self {
+            name: stored.name,
+            width: stored.width,
+            height: stored.height,
+            data: stored.data,
+        })
+    }
+}
+
+impl BrushTip {
+    pub fn from_png(path: &Path, language: Language) -> Result<Self, String> {
+        let img = image::open(path)
+            .map_err(|e| format!("{}: {}", get_text("unable_to_open_image", language), e))?;
+        let rgba = img.to_rgba8();
+        let (width, height) = (rgba.width(), rgba.height());
+
+        if width == 0 || height == 0 || width > MAX_TIP_SIZE || height > MAX_TIP_SIZE {
+            return Err(format!("{}: {}x{}", get_text("invalid_brush_tip_size", language), width, height));
+        }
+
+        let data = rgba
+            .pixels()
+            .map(|p| coverage_from_rgba(p[0], p[1], p[2], p[3]))
+            .collect();
+
+        Ok(Self {
+            name: tip_name_from_path(path),
+            width: width as usize,
+            height: height as usize,
+            data,
+        })
+    }
+
+    pub fn from_gbr(path: &Path, language: Language) -> Result<Self, String> {
+        let bytes = fs::read(path)
+            .map_err(|e| format!("{}: {}", get_text("error_reading_file", language), e))?;
+        Self::parse_gbr(&bytes, &tip_name_from_path(path))
+            .ok_or_else(|| get_text("invalid_gbr_file", language))
+    }
+
+    fn parse_gbr(bytes: &[u8], fallback_name: &str) -> Option<Self> {
+        let read_u32 = |offset: usize| -> Option<u32> {
+            bytes.get(offset..offset + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        };
+
+        let header_size = read_u32(0)? as usize;
+        let version = read_u32(4)?;
+        let width = read_u32(8)?;
+        let height = read_u32(12)?;
+        let depth = read_u32(16)? as usize;
+
+        let name_start = match version {
+            1 => 20,
+            2 | 3 => {
+                if bytes.get(20..24)? != GBR_MAGIC {
+                    return None;
+                }
+                28
+            },
+            _ => return None,
+        };
+
+        if width == 0 || height == 0 || width > MAX_TIP_SIZE || height > MAX_TIP_SIZE || header_size < name_start {
+            return None;
+        }
+
+        let name = bytes
+            .get(name_start..header_size)
+            .map(|raw| String::from_utf8_lossy(raw).trim_end_matches('\0').to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| fallback_name.to_string());
+
+        let pixel_count = (width * height) as usize;
+        let pixels = bytes.get(header_size..header_size + pixel_count * depth)?;
+
+        let data = match depth {
+            1 => pixels.to_vec(),
+            4 => pixels.chunks_exact(4).map(|p| p[3]).collect(),
+            _ => return None,
+        };
+
+        Some(Self {
+            name,
+            width: width as usize,
+            height: height as usize,
+            data,
+        })
+    }
+
+    pub fn from_pixels(name: String, width: usize, height: usize, pixels: &[(Option<Color32>, u8)]) -> Option<Self> {
+        if width == 0 || height == 0 || pixels.len() != width * height {
+            return None;
+        }
+
+        let data = pixels
+            .iter()
+            .map(|(color, selected)| {
+                let coverage = color
+                    .map(|c| coverage_from_rgba(c.r(), c.g(), c.b(), c.a()))
+                    .unwrap_or(0);
+                ((coverage as u16 * *selected as u16) / 255) as u8
+            })
+            .collect();
+
+        Some(Self {
+            name,
+            width,
+            height,
+            data,
+        })
+    }
+
+    pub fn sample(&self, u: f32, v: f32) -> f32 {
+        let x = u * self.width as f32 - 0.5;
+        let y = v * self.height as f32 - 0.5;
+
+        let x0 = x.floor() as i32;
+        let y0 = y.floor() as i32;
+        let fx = x - x0 as f32;
+        let fy = y - y0 as f32;
+
+        let texel = |tx: i32, ty: i32| -> f32 {
+            if tx < 0 || ty < 0 || tx >= self.width as i32 || ty >= self.height as i32 {
+                0.0
+            } else {
+                self.data[ty as usize * self.width + tx as usize] as f32 / 255.0
+            }
+        };
+
+        let top = texel(x0, y0) * (1.0 - fx) + texel(x0 + 1, y0) * fx;
+        let bottom = texel(x0, y0 + 1) * (1.0 - fx) + texel(x0 + 1, y0 + 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+
+    pub fn preview_image(&self) -> egui::ColorImage {
+        let pixels = self.data
+            .iter()
+            .map(|&value| Color32::from_gray(240 - (value as u16 * 240 / 255) as u8))
+            .collect();
+
+        egui::ColorImage {
+            size: [self.width, self.height],
+            pixels,
+        }
+    }
+}
+
+fn coverage_from_rgba(r: u8, g: u8, b: u8, a: u8) -> u8 {
+    let luminance = (r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000;
+    ((255 - luminance) * a as u32 / 255) as u8
+}
+
+fn tip_name_from_path(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("Tip")
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gbr(version: u32, width: u32, height: u32, depth: u32, name: &str, pixels: &[u8]) -> Vec<u8> {
+        let mut name = name.as_bytes().to_vec();
+        name.push(0);
+        let name_start = if version == 1 { 20 } else { 28 };
+        let mut bytes = Vec::new();
+        for value in [(name_start + name.len()) as u32, version, width, height, depth] {
+            bytes.extend(value.to_be_bytes());
+        }
+        if version != 1 {
+            bytes.extend(GBR_MAGIC);
+            bytes.extend(25u32.to_be_bytes());
+        }
+        bytes.extend(name);
+        bytes.extend(pixels);
+        bytes
+    }
+
+    #[test]
+    fn parses_version_1_grayscale() {
+        let tip = BrushTip::parse_gbr(&gbr(1, 2, 2, 1, "Round", &[0, 64, 128, 255]), "fallback").unwrap();
+        assert_eq!((tip.name.as_str(), tip.width, tip.height), ("Round", 2, 2));
+        assert_eq!(tip.data, vec![0, 64, 128, 255]);
+    }
+
+    #[test]
+    fn parses_version_2_rgba_alpha() {
+        let pixels = [10, 20, 30, 40, 50, 60, 70, 80];
+        let tip = BrushTip::parse_gbr(&gbr(2, 2, 1, 4, "Color", &pixels), "fallback").unwrap();
+        assert_eq!((tip.name.as_str(), tip.width, tip.height), ("Color", 2, 1));
+        assert_eq!(tip.data, vec![40, 80]);
+    }
+
+    #[test]
+    fn parses_version_3_and_falls_back_to_file_name() {
+        let tip = BrushTip::parse_gbr(&gbr(3, 1, 3, 1, "", &[1, 2, 3]), "fallback").unwrap();
+        assert_eq!(tip.name, "fallback");
+        assert_eq!(tip.data, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn rejects_bad_gbr_input() {
+        let valid = gbr(2, 2, 2, 1, "Tip", &[1, 2, 3, 4]);
+        assert!(BrushTip::parse_gbr(&valid[..valid.len() - 1], "tip").is_none());
+        assert!(BrushTip::parse_gbr(&valid[..12], "tip").is_none());
+        assert!(BrushTip::parse_gbr(&[], "tip").is_none());
+        assert!(BrushTip::parse_gbr(&gbr(2, 0, 2, 1, "Tip", &[]), "tip").is_none());
+        assert!(BrushTip::parse_gbr(&gbr(1, 2, 2, 3, "Tip", &[0; 12]), "tip").is_none());
+        assert!(BrushTip::parse_gbr(&gbr(4, 1, 1, 1, "Tip", &[0]), "tip").is_none());
+
+        let mut wrong_magic = valid;
+        wrong_magic[20..24].copy_from_slice(b"ABCD");
+        assert!(BrushTip::parse_gbr(&wrong_magic, "tip").is_none());
+    }
+
+    #[test]
+    fn deserialization_checks_data_length_and_size() {
+        let tip: BrushTip = serde_json::from_str(r#"{"name": "Dot", "width": 2, "height": 1, "data": [0, 255]}"#).unwrap();
+        assert_eq!(tip.sample(0.75, 0.5), 1.0);
+
+        assert!(serde_json::from_str::<BrushTip>(r#"{"name": "Dot", "width": 2, "height": 2, "data": [0, 255]}"#).is_err());
+        assert!(serde_json::from_str::<BrushTip>(r#"{"name": "Dot", "width": 0, "height": 1, "data": []}"#).is_err());
+        let huge = format!(r#"{{"name": "Dot", "width": 2000, "height": 1, "data": [{}]}}"#, vec!["0"; 2000].join(","));
+        assert!(serde_json::from_str::<BrushTip>(&huge).is_err());
+    }
+}
@@ -0,0 +1,304 @@
+use eframe::egui;
+use egui::{Color32, Mesh, Pos2, Rect, Sense, Shape, Stroke, Vec2};
+use std::collections::VecDeque;
+use std::f32::consts::{FRAC_1_SQRT_2, TAU};
+
+use crate::color_space::{hsl_to_rgb, hsv_to_rgb, lab_to_rgb, parse_color, rgb_to_hsl, rgb_to_hsv, rgb_to_lab, to_hex};
+use crate::localization::{Language, get_text};
+
+const WHEEL_SIZE: f32 = 180.0;
+const RING_WIDTH: f32 = 0.18;
+const RING_SEGMENTS: usize = 96;
+const SQUARE_STEPS: usize = 16;
+const MAX_RECENT_COLORS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SliderMode {
+    Hsl,
+    Rgb,
+    Lab,
+}
+
+impl SliderMode {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SliderMode::Hsl => "HSL",
+            SliderMode::Rgb => "RGB",
+            SliderMode::Lab => "Lab",
+        }
+    }
+
+    pub fn all_modes() -> Vec<SliderMode> {
+        vec![SliderMode::Hsl, SliderMode::Rgb, SliderMode::Lab]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WheelPart {
+    Ring,
+    Square,
+}
+
+pub struct ColorPanel {
+    pub slider_mode: SliderMode,
+    pub recent: VecDeque<Color32>,
+    hsv: [f32; 3],
+    last_color: Option<Color32>,
+    hex_input: String,
+    dragging: Option<WheelPart>,
+}
+
+impl Default for ColorPanel {
+    fn default() -> Self {
+        Self {
+            slider_mode: SliderMode::Hsl,
+            recent: VecDeque::new(),
+            hsv: [0.0, 0.0, 0.0],
+            last_color: None,
+            hex_input: String::new(),
+            dragging: None,
+        }
+    }
+}
+
+fn to_unit(rgb: [u8; 4]) -> [f32; 3] {
+    [rgb[0] as f32 / 255.0, rgb[1] as f32 / 255.0, rgb[2] as f32 / 255.0]
+}
+
+fn from_unit(rgb: [f32; 3], alpha: u8) -> Color32 {
+    let channel = |v: f32| (v * 255.0).round().clamp(0.0, 255.0) as u8;
+    Color32::from_rgba_unmultiplied(channel(rgb[0]), channel(rgb[1]), channel(rgb[2]), alpha)
+}
+
+impl ColorPanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push_recent(&mut self, color: Color32) {
+        self.recent.retain(|&recent| recent != color);
+        self.recent.push_front(color);
+        self.recent.truncate(MAX_RECENT_COLORS);
+    }
+
+    fn sync(&mut self, color: Color32) {
+        if self.last_color == Some(color) {
+            return;
+        }
+
+        let [r, g, b, a] = color.to_srgba_unmultiplied();
+        let (hue, saturation, value) = rgb_to_hsv(Color32::from_rgb(r, g, b));
+        let keeps_hue = saturation <= f32::EPSILON || value <= f32::EPSILON;
+        self.hsv = [if keeps_hue { self.hsv[0] } else { hue }, if value <= f32::EPSILON { self.hsv[1] } else { saturation }, value];
+        self.hex_input = to_hex([r, g, b, a]);
+        self.last_color = Some(color);
+    }
+
+    fn commit(&mut self, color: &mut Color32, new_color: Color32) {
+        *color = new_color;
+        self.last_color = Some(new_color);
+        self.hex_input = to_hex(new_color.to_srgba_unmultiplied());
+    }
+
+    fn wheel(&mut self, ui: &mut egui::Ui) -> bool {
+        let size = ui.available_width().min(WHEEL_SIZE);
+        let (rect, response) = ui.allocate_exact_size(Vec2::splat(size), Sense::click_and_drag());
+        let center = rect.center();
+        let outer = size * 0.5;
+        let inner = outer * (1.0 - RING_WIDTH);
+        let square = Rect::from_center_size(center, Vec2::splat(inner * FRAC_1_SQRT_2 * 1.9));
+
+        let ring_point = |angle: f32, radius: f32| center + Vec2::new(angle.cos(), -angle.sin()) * radius;
+
+        let mut mesh = Mesh::default();
+        for i in 0..RING_SEGMENTS {
+            let (t0, t1) = (i as f32 / RING_SEGMENTS as f32, (i + 1) as f32 / RING_SEGMENTS as f32);
+            let (c0, c1) = (hsv_to_rgb(t0, 1.0, 1.0, 255), hsv_to_rgb(t1, 1.0, 1.0, 255));
+            let base = mesh.vertices.len() as u32;
+            mesh.colored_vertex(ring_point(t0 * TAU, outer), c0);
+            mesh.colored_vertex(ring_point(t0 * TAU, inner), c0);
+            mesh.colored_vertex(ring_point(t1 * TAU, outer), c1);
+            mesh.colored_vertex(ring_point(t1 * TAU, inner), c1);
+            mesh.add_triangle(base, base + 1, base + 2);
+            mesh.add_triangle(base + 1, base + 3, base + 2);
+        }
+
+        let steps = SQUARE_STEPS as u32 + 1;
+        let base = mesh.vertices.len() as u32;
+        for row in 0..=SQUARE_STEPS {
+            for column in 0..=SQUARE_STEPS {
+                let saturation = column as f32 / SQUARE_STEPS as f32;
+                let value = 1.0 - row as f32 / SQUARE_STEPS as f32;
+                let pos = Pos2::new(square.left() + saturation * square.width(), square.top() + (1.0 - value) * square.height());
+                mesh.colored_vertex(pos, hsv_to_rgb(self.hsv[0], saturation, value, 255));
+            }
+        }
+        for row in 0..SQUARE_STEPS as u32 {
+            for column in 0..SQUARE_STEPS as u32 {
+                let top_left = base + row * steps + column;
+                mesh.add_triangle(top_left, top_left + 1, top_left + steps);
+                mesh.add_triangle(top_left + 1, top_left + steps + 1, top_left + steps);
+            }
+        }
+
+        let painter = ui.painter_at(rect);
+        painter.add(Shape::mesh(mesh));
+
+        let hue_marker = ring_point(self.hsv[0] * TAU, (outer + inner) * 0.5);
+        painter.circle_stroke(hue_marker, (outer - inner) * 0.5, Stroke::new(2.0, Color32::WHITE));
+        let sv_marker = Pos2::new(square.left() + self.hsv[1] * square.width(), square.top() + (1.0 - self.hsv[2]) * square.height());
+        let marker_color = if self.hsv[2] > 0.5 { Color32::BLACK } else { Color32::WHITE };
+        painter.circle_stroke(sv_marker, 5.0, Stroke::new(1.5, marker_color));
+
+        if response.drag_started() || response.clicked() {
+            self.dragging = response.interact_pointer_pos().and_then(|pos| {
+                let distance = pos.distance(center);
+                if square.contains(pos) {
+                    Some(WheelPart::Square)
+                } else if distance >= inner && distance <= outer {
+                    Some(WheelPart::Ring)
+                } else {
+                    None
+                }
+            });
+        }
+
+        let mut changed = false;
+        if let (Some(part), Some(pos)) = (self.dragging, response.interact_pointer_pos()) {
+            match part {
+                WheelPart::Ring => {
+                    let offset = pos - center;
+                    self.hsv[0] = (-offset.y).atan2(offset.x).rem_euclid(TAU) / TAU;
+                },
+                WheelPart::Square => {
+                    self.hsv[1] = ((pos.x - square.left()) / square.width()).clamp(0.0, 1.0);
+                    self.hsv[2] = 1.0 - ((pos.y - square.top()) / square.height()).clamp(0.0, 1.0);
+                },
+            }
+            changed = true;
+        }
+
+        if response.drag_released() || response.clicked() {
+            self.dragging = None;
+        }
+        changed
+    }
+
+    fn sliders(&mut self, ui: &mut egui::Ui, rgba: [u8; 4], language: Language) -> Option<[f32; 3]> {
+        let rgb = to_unit(rgba);
+        let mut changed = false;
+
+        ui.horizontal(|ui| {
+            for mode in SliderMode::all_modes() {
+                ui.selectable_value(&mut self.slider_mode, mode, mode.get_name());
+            }
+        });
+
+        let mut slider = |ui: &mut egui::Ui, value: &mut f32, range: std::ops::RangeInclusive<f32>, key: &str| {
+            changed |= ui.add(egui::Slider::new(value, range).text(get_text(key, language))).changed();
+        };
+
+        let result = match self.slider_mode {
+            SliderMode::Hsl => {
+                let (h, s, l) = rgb_to_hsl(rgb[0], rgb[1], rgb[2]);
+                let hue = if s <= f32::EPSILON { self.hsv[0] } else { h };
+                let (mut h, mut s, mut l) = (hue * 360.0, s * 100.0, l * 100.0);
+                slider(ui, &mut h, 0.0..=360.0, "hue");
+                slider(ui, &mut s, 0.0..=100.0, "saturation");
+                slider(ui, &mut l, 0.0..=100.0, "lightness");
+                hsl_to_rgb(h / 360.0, s / 100.0, l / 100.0)
+            },
+            SliderMode::Rgb => {
+                let mut channels = rgb.map(|v| v * 255.0);
+                for (value, key) in channels.iter_mut().zip(["red", "green", "blue"]) {
+                    slider(ui, value, 0.0..=255.0, key);
+                }
+                channels.map(|v| v.round() / 255.0)
+            },
+            SliderMode::Lab => {
+                let mut lab = rgb_to_lab(rgb);
+                slider(ui, &mut lab[0], 0.0..=100.0, "lab_lightness");
+                slider(ui, &mut lab[1], -128.0..=127.0, "lab_a");
+                slider(ui, &mut lab[2], -128.0..=127.0, "lab_b");
+                lab_to_rgb(lab)
+            },
+        };
+
+        changed.then_some(result)
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, color: &mut Color32, language: Language) -> bool {
+        self.sync(*color);
+        let mut changed = false;
+        let alpha = color.a();
+
+        if self.wheel(ui) {
+            let [r, g, b, _] = hsv_to_rgb(self.hsv[0], self.hsv[1], self.hsv[2], 255).to_srgba_unmultiplied();
+            self.commit(color, Color32::from_rgba_unmultiplied(r, g, b, alpha));
+            changed = true;
+        }
+
+        let rgba = color.to_srgba_unmultiplied();
+        if let Some(rgb) = self.sliders(ui, rgba, language) {
+            let new_color = from_unit(rgb, rgba[3]);
+            let [r, g, b, _] = new_color.to_srgba_unmultiplied();
+            let (hue, saturation, value) = rgb_to_hsv(Color32::from_rgb(r, g, b));
+            if saturation > f32::EPSILON {
+                self.hsv[0] = hue;
+            }
+            self.hsv[1] = saturation;
+            self.hsv[2] = value;
+            self.commit(color, new_color);
+            changed = true;
+        }
+
+        let mut alpha = color.to_srgba_unmultiplied()[3] as f32;
+        if ui.add(egui::Slider::new(&mut alpha, 0.0..=255.0).text(get_text("alpha", language))).changed() {
+            let [r, g, b, _] = color.to_srgba_unmultiplied();
+            self.commit(color, Color32::from_rgba_unmultiplied(r, g, b, alpha.round() as u8));
+            changed = true;
+        }
+
+        ui.horizontal(|ui| {
+            ui.label(get_text("hex_color", language));
+            let input = ui.add(egui::TextEdit::singleline(&mut self.hex_input).desired_width(110.0));
+            let submitted = input.lost_focus() || (input.has_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)));
+            if submitted {
+                match parse_color(&self.hex_input) {
+                    Some([r, g, b, a]) => {
+                        self.last_color = None;
+                        *color = Color32::from_rgba_unmultiplied(r, g, b, a);
+                        self.sync(*color);
+                        changed = true;
+                    },
+                    None => self.hex_input = to_hex(color.to_srgba_unmultiplied()),
+                }
+            }
+            input.on_hover_text(get_text("hex_color_hint", language));
+        });
+
+        changed
+    }
+
+    pub fn recent_strip(&self, ui: &mut egui::Ui) -> Option<(Color32, bool)> {
+        let mut picked = None;
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing = Vec2::splat(2.0);
+            for &color in &self.recent {
+                let swatch = ui.add(
+                    egui::Button::new("")
+                        .fill(color)
+                        .stroke(Stroke::new(1.0, Color32::from_gray(90)))
+                        .min_size(Vec2::splat(16.0))
+                );
+                if swatch.clicked() {
+                    picked = Some((color, false));
+                }
+                if swatch.secondary_clicked() {
+                    picked = Some((color, true));
+                }
+            }
+        });
+        picked
+    }
+}
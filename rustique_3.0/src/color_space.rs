@@ -0,0 +1,274 @@
+use egui::Color32;
+
+pub fn rgb_to_hsv(color: Color32) -> (f32, f32, f32) {
+    let r = color.r() as f32 / 255.0;
+    let g = color.g() as f32 / 255.0;
+    let b = color.b() as f32 / 255.0;
+
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+
+    let hue = if delta <= f32::EPSILON {
+        0.0
+    } else if max == r {
+        ((g - b) / delta).rem_euclid(6.0) / 6.0
+    } else if max == g {
+        ((b - r) / delta + 2.0) / 6.0
+    } else {
+        ((r - g) / delta + 4.0) / 6.0
+    };
+
+    let saturation = if max <= f32::EPSILON { 0.0 } else { delta / max };
+
+    (hue, saturation, max)
+}
+
+pub fn hsv_to_rgb(hue: f32, saturation: f32, value: f32, alpha: u8) -> Color32 {
+    let h = hue.rem_euclid(1.0) * 6.0;
+    let s = saturation.clamp(0.0, 1.0);
+    let v = value.clamp(0.0, 1.0);
+
+    let c = v * s;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let m = v - c;
+
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+
+    Color32::from_rgba_unmultiplied(
+        ((r + m) * 255.0).round() as u8,
+        ((g + m) * 255.0).round() as u8,
+        ((b + m) * 255.0).round() as u8,
+        alpha,
+    )
+}
+
+pub fn rgb_to_hsl(r: f32, g: f32, b: f32) -> (f32, f32, f32) {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let lightness = (max + min) * 0.5;
+
+    if delta <= f32::EPSILON {
+        return (0.0, 0.0, lightness);
+    }
+
+    let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs()).max(f32::EPSILON);
+    let hue = if max == r {
+        ((g - b) / delta).rem_euclid(6.0) / 6.0
+    } else if max == g {
+        ((b - r) / delta + 2.0) / 6.0
+    } else {
+        ((r - g) / delta + 4.0) / 6.0
+    };
+
+    (hue, saturation.min(1.0), lightness)
+}
+
+pub fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> [f32; 3] {
+    let h = hue.rem_euclid(1.0) * 6.0;
+    let s = saturation.clamp(0.0, 1.0);
+    let l = lightness.clamp(0.0, 1.0);
+
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let m = l - c * 0.5;
+
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+
+    [r + m, g + m, b + m]
+}
+
+pub fn luminance(rgb: [f32; 3]) -> f32 {
+    0.299 * rgb[0] + 0.587 * rgb[1] + 0.114 * rgb[2]
+}
+
+fn srgb_to_linear(channel: f32) -> f32 {
+    if channel <= 0.04045 {
+        channel / 12.92
+    } else {
+        ((channel + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(channel: f32) -> f32 {
+    if channel <= 0.0031308 {
+        channel * 12.92
+    } else {
+        1.055 * channel.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+const WHITE_POINT: [f32; 3] = [0.95047, 1.0, 1.08883];
+
+pub fn rgb_to_lab(rgb: [f32; 3]) -> [f32; 3] {
+    let [r, g, b] = rgb.map(srgb_to_linear);
+    let xyz = [
+        0.4124 * r + 0.3576 * g + 0.1805 * b,
+        0.2126 * r + 0.7152 * g + 0.0722 * b,
+        0.0193 * r + 0.1192 * g + 0.9505 * b,
+    ];
+
+    let f = |t: f32| if t > 0.008856 { t.cbrt() } else { 7.787 * t + 16.0 / 116.0 };
+    let [fx, fy, fz] = [f(xyz[0] / WHITE_POINT[0]), f(xyz[1] / WHITE_POINT[1]), f(xyz[2] / WHITE_POINT[2])];
+
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+pub fn lab_to_rgb(lab: [f32; 3]) -> [f32; 3] {
+    let fy = (lab[0] + 16.0) / 116.0;
+    let fx = fy + lab[1] / 500.0;
+    let fz = fy - lab[2] / 200.0;
+
+    let inverse = |t: f32| if t.powi(3) > 0.008856 { t.powi(3) } else { (t - 16.0 / 116.0) / 7.787 };
+    let [x, y, z] = [inverse(fx) * WHITE_POINT[0], inverse(fy) * WHITE_POINT[1], inverse(fz) * WHITE_POINT[2]];
+
+    let linear = [
+        3.2406 * x - 1.5372 * y - 0.4986 * z,
+        -0.9689 * x + 1.8758 * y + 0.0415 * z,
+        0.0557 * x - 0.2040 * y + 1.0570 * z,
+    ];
+    linear.map(|channel| linear_to_srgb(channel.max(0.0)).clamp(0.0, 1.0))
+}
+
+pub fn to_hex(color: [u8; 4]) -> String {
+    if color[3] == 255 {
+        format!("#{:02X}{:02X}{:02X}", color[0], color[1], color[2])
+    } else {
+        format!("#{:02X}{:02X}{:02X}{:02X}", color[0], color[1], color[2], color[3])
+    }
+}
+
+fn parse_hex(digits: &str) -> Option<[u8; 4]> {
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let nibble = |i: usize| u8::from_str_radix(&digits[i..i + 1], 16).ok().map(|v| v * 17);
+    let byte = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).ok();
+
+    match digits.len() {
+        3 => Some([nibble(0)?, nibble(1)?, nibble(2)?, 255]),
+        4 => Some([nibble(0)?, nibble(1)?, nibble(2)?, nibble(3)?]),
+        6 => Some([byte(0)?, byte(2)?, byte(4)?, 255]),
+        8 => Some([byte(0)?, byte(2)?, byte(4)?, byte(6)?]),
+        _ => None,
+    }
+}
+
+fn parse_css_function(name: &str, arguments: &str) -> Option<[u8; 4]> {
+    let values: Vec<&str> = arguments
+        .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .collect();
+    if values.len() != 3 && values.len() != 4 {
+        return None;
+    }
+
+    let number = |text: &str, scale: f32| -> Option<f32> {
+        match text.strip_suffix('%') {
+            Some(percent) => percent.parse::<f32>().ok().map(|v| v / 100.0 * scale),
+            None => text.trim_end_matches("deg").parse::<f32>().ok(),
+        }
+    };
+    let alpha = match values.get(3) {
+        Some(text) => number(text, 1.0)?.clamp(0.0, 1.0),
+        None => 1.0,
+    };
+    let alpha = (alpha * 255.0).round() as u8;
+
+    match name {
+        "rgb" | "rgba" => {
+            let channel = |text: &str| number(text, 255.0).map(|v| v.round().clamp(0.0, 255.0) as u8);
+            Some([channel(values[0])?, channel(values[1])?, channel(values[2])?, alpha])
+        },
+        "hsl" | "hsla" => {
+            let hue = number(values[0], 360.0)? / 360.0;
+            let saturation = number(values[1], 1.0)?;
+            let lightness = number(values[2], 1.0)?;
+            let rgb = hsl_to_rgb(hue, saturation, lightness);
+            let channel = |v: f32| (v * 255.0).round().clamp(0.0, 255.0) as u8;
+            Some([channel(rgb[0]), channel(rgb[1]), channel(rgb[2]), alpha])
+        },
+        _ => None,
+    }
+}
+
+pub fn parse_color(text: &str) -> Option<[u8; 4]> {
+    let text = text.trim().to_ascii_lowercase();
+
+    if let Some((name, rest)) = text.split_once('(') {
+        let arguments = rest.strip_suffix(')')?;
+        return parse_css_function(name.trim(), arguments);
+    }
+
+    parse_hex(text.strip_prefix('#').unwrap_or(&text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_forms_are_parsed() {
+        assert_eq!(parse_color("#FF8000"), Some([255, 128, 0, 255]));
+        assert_eq!(parse_color("ff8000"), Some([255, 128, 0, 255]));
+        assert_eq!(parse_color("#f80"), Some([255, 136, 0, 255]));
+        assert_eq!(parse_color("#f808"), Some([255, 136, 0, 136]));
+        assert_eq!(parse_color("  #11223344 "), Some([17, 34, 51, 68]));
+    }
+
+    #[test]
+    fn invalid_hex_is_rejected() {
+        assert_eq!(parse_color(""), None);
+        assert_eq!(parse_color("#12345"), None);
+        assert_eq!(parse_color("#ggg"), None);
+        assert_eq!(parse_color("#é12"), None);
+    }
+
+    #[test]
+    fn css_rgb_is_parsed() {
+        assert_eq!(parse_color("rgb(255, 0, 128)"), Some([255, 0, 128, 255]));
+        assert_eq!(parse_color("RGBA(10 20 30 / 50%)"), Some([10, 20, 30, 128]));
+        assert_eq!(parse_color("rgba(100%, 0%, 50%, 0.25)"), Some([255, 0, 128, 64]));
+        assert_eq!(parse_color("rgb(300, -5, 0)"), Some([255, 0, 0, 255]));
+    }
+
+    #[test]
+    fn css_hsl_is_parsed() {
+        assert_eq!(parse_color("hsl(120, 100%, 50%)"), Some([0, 255, 0, 255]));
+        assert_eq!(parse_color("hsla(0deg 100% 50% / 0.5)"), Some([255, 0, 0, 128]));
+        assert_eq!(parse_color("hsl(0, 0%, 100%)"), Some([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn malformed_css_is_rejected() {
+        assert_eq!(parse_color("rgb(1, 2)"), None);
+        assert_eq!(parse_color("rgb(1, 2, 3"), None);
+        assert_eq!(parse_color("cmyk(1, 2, 3, 4)"), None);
+        assert_eq!(parse_color("rgb(a, b, c)"), None);
+    }
+
+    #[test]
+    fn hex_round_trips() {
+        for color in [[0, 0, 0, 255], [18, 52, 86, 255], [255, 255, 255, 0], [1, 2, 3, 4]] {
+            assert_eq!(parse_color(&to_hex(color)), Some(color));
+        }
+        assert_eq!(to_hex([255, 128, 0, 255]), "#FF8000");
+        assert_eq!(to_hex([255, 128, 0, 64]), "#FF800040");
+    }
+}
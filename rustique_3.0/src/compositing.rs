@@ -0,0 +1,39 @@
+use eframe::egui::Color32;
+
+pub(crate) fn to_premultiplied(color: Option<Color32>) -> [f32; 4] {
+    color
+        .map(|c| [c.r() as f32, c.g() as f32, c.b() as f32, c.a() as f32])
+        .unwrap_or([0.0; 4])
+}
+
+pub(crate) fn from_premultiplied(color: [f32; 4]) -> Option<Color32> {
+    if color[3] < 1.0 {
+        return None;
+    }
+    let channel = |value: f32| value.round().clamp(0.0, color[3].round()) as u8;
+    Some(Color32::from_rgba_premultiplied(
+        channel(color[0]),
+        channel(color[1]),
+        channel(color[2]),
+        color[3].round().clamp(0.0, 255.0) as u8,
+    ))
+}
+
+pub(crate) fn mix_premultiplied(from: [f32; 4], to: [f32; 4], amount: f32) -> [f32; 4] {
+    [
+        from[0] + (to[0] - from[0]) * amount,
+        from[1] + (to[1] - from[1]) * amount,
+        from[2] + (to[2] - from[2]) * amount,
+        from[3] + (to[3] - from[3]) * amount,
+    ]
+}
+
+pub(crate) fn blend_over(destination: [f32; 4], source: [f32; 4]) -> [f32; 4] {
+    let inverse_alpha = 1.0 - source[3] / 255.0;
+    [
+        source[0] + destination[0] * inverse_alpha,
+        source[1] + destination[1] * inverse_alpha,
+        source[2] + destination[2] * inverse_alpha,
+        source[3] + destination[3] * inverse_alpha,
+    ]
+}
@@ -0,0 +1,650 @@
+use eframe::egui;
+use egui::{Color32, TextureHandle, TextureOptions};
+use image::ImageFormat;
+use image::codecs::jpeg::{JpegEncoder, PixelDensity, PixelDensityUnit};
+use image::codecs::webp::WebPEncoder;
+use serde::{Deserializer, Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::indexed_color::MAX_INDEXED_COLORS;
+use crate::localization::{Language, get_text};
+use crate::quantize::{Dithering, Quantizer, build_palette, map_to_palette};
+
+const EXPORT_SETTINGS_PATH: &str = "export_settings.json";
+const SOFTWARE_NAME: &str = "Rustique";
+const PREVIEW_SIZE: usize = 128;
+const PREVIEW_ZOOM: f32 = 2.0;
+const PREVIEW_IDLE: Duration = Duration::from_millis(300);
+const METERS_PER_INCH: f32 = 0.0254;
+const MIN_PALETTE_SIZE: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PngCompression {
+    Fast,
+    Default,
+    Best,
+}
+
+impl PngCompression {
+    pub fn get_name(&self, language: Language) -> String {
+        match self {
+            PngCompression::Fast => get_text("compression_fast", language),
+            PngCompression::Default => get_text("compression_default", language),
+            PngCompression::Best => get_text("compression_best", language),
+        }
+    }
+
+    pub fn all_levels() -> Vec<PngCompression> {
+        vec![PngCompression::Fast, PngCompression::Default, PngCompression::Best]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TiffCompression {
+    None,
+    Lzw,
+    Deflate,
+    PackBits,
+}
+
+impl TiffCompression {
+    pub fn get_name(&self, language: Language) -> String {
+        match self {
+            TiffCompression::None => get_text("compression_none", language),
+            TiffCompression::Lzw => "LZW".to_string(),
+            TiffCompression::Deflate => "Deflate".to_string(),
+            TiffCompression::PackBits => "PackBits".to_string(),
+        }
+    }
+
+    pub fn all_compressions() -> Vec<TiffCompression> {
+        vec![TiffCompression::None, TiffCompression::Lzw, TiffCompression::Deflate, TiffCompression::PackBits]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum WebpMode {
+    Lossless,
+    #[serde(alias = "NearLossless")]
+    Lossy,
+}
+
+impl WebpMode {
+    pub fn get_name(&self, language: Language) -> String {
+        match self {
+            WebpMode::Lossless => get_text("webp_lossless", language),
+            WebpMode::Lossy => get_text("webp_lossy", language),
+        }
+    }
+
+    pub fn all_modes() -> Vec<WebpMode> {
+        vec![WebpMode::Lossless, WebpMode::Lossy]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub reduce_colors: bool,
+    #[serde(deserialize_with = "clamped_palette_size")]
+    pub palette_size: usize,
+    pub quantizer: Quantizer,
+    pub dithering: Dithering,
+    pub matte: [u8; 3],
+    pub jpeg_quality: u8,
+    pub png_compression: PngCompression,
+    pub webp_mode: WebpMode,
+    pub webp_quality: u8,
+    pub tiff_compression: TiffCompression,
+    pub embed_dpi: bool,
+    pub dpi: u16,
+    pub embed_metadata: bool,
+    pub author: String,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            reduce_colors: false,
+            palette_size: MAX_INDEXED_COLORS,
+            quantizer: Quantizer::Octree,
+            dithering: Dithering::FloydSteinberg,
+            matte: [255, 255, 255],
+            jpeg_quality: 90,
+            png_compression: PngCompression::Default,
+            webp_mode: WebpMode::Lossless,
+            webp_quality: 80,
+            tiff_compression: TiffCompression::Lzw,
+            embed_dpi: false,
+            dpi: 72,
+            embed_metadata: true,
+            author: String::new(),
+        }
+    }
+}
+
+fn clamped_palette_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+    usize::deserialize(deserializer).map(|size| size.clamp(MIN_PALETTE_SIZE, MAX_INDEXED_COLORS))
+}
+
+fn format_key(format: ImageFormat) -> String {
+    format.extensions_str().first().copied().unwrap_or_default().to_string()
+}
+
+impl ExportOptions {
+    pub fn supports_alpha(format: ImageFormat) -> bool {
+        !matches!(format, ImageFormat::Jpeg)
+    }
+
+    pub fn quantizes(&self, format: ImageFormat) -> bool {
+        format == ImageFormat::Gif || self.reduce_colors
+    }
+
+    fn supports_dpi(format: ImageFormat) -> bool {
+        matches!(format, ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Tiff)
+    }
+
+    fn supports_metadata(format: ImageFormat) -> bool {
+        matches!(format, ImageFormat::Png | ImageFormat::Tiff)
+    }
+
+    pub fn apply_matte(&self, pixel: [u8; 4], keep_transparency: bool) -> [u8; 4] {
+        if keep_transparency && pixel[3] < 128 {
+            return [0, 0, 0, 0];
+        }
+        let alpha = pixel[3] as f32 / 255.0;
+        let blend = |value: u8, matte: u8| (value as f32 * alpha + matte as f32 * (1.0 - alpha)).round() as u8;
+        [blend(pixel[0], self.matte[0]), blend(pixel[1], self.matte[1]), blend(pixel[2], self.matte[2]), 255]
+    }
+
+    fn metadata(&self) -> Vec<(&'static str, String)> {
+        if !self.embed_metadata {
+            return Vec::new();
+        }
+        let mut fields = vec![("Software", SOFTWARE_NAME.to_string())];
+        if !self.author.trim().is_empty() {
+            fields.push(("Author", self.author.trim().to_string()));
+        }
+        fields
+    }
+
+    pub fn encode(
+        &self,
+        format: ImageFormat,
+        width: usize,
+        height: usize,
+        mut pixels: Vec<[u8; 4]>,
+        document_palette: Option<&[[u8; 3]]>,
+        language: Language,
+    ) -> Result<Vec<u8>, String> {
+        let error = |e: &dyn std::fmt::Display| format!("{}: {}", get_text("error_saving_image", language), e);
+        let document_palette = document_palette.filter(|_| matches!(format, ImageFormat::Png | ImageFormat::Gif));
+        let quantize = document_palette.is_some() || self.quantizes(format);
+
+        if quantize || !Self::supports_alpha(format) {
+            let keep_transparency = Self::supports_alpha(format);
+            for pixel in pixels.iter_mut() {
+                *pixel = self.apply_matte(*pixel, keep_transparency);
+            }
+        }
+
+        if quantize {
+            let (palette, dithering) = match document_palette {
+                Some(palette) => (palette.to_vec(), Dithering::None),
+                None => (build_palette(&pixels, self.palette_size, self.quantizer), self.dithering),
+            };
+            let indices = map_to_palette(&pixels, width, &palette, dithering);
+
+            if matches!(format, ImageFormat::Png | ImageFormat::Gif) {
+                let transparent = palette.len() as u8;
+                let indices: Vec<u8> = indices.iter().map(|index| index.unwrap_or(transparent)).collect();
+                let colors: Vec<[u8; 4]> = palette
+                    .iter()
+                    .map(|&[r, g, b]| [r, g, b, 255])
+                    .chain(std::iter::once([0, 0, 0, 0]))
+                    .collect();
+                let mut bytes = Vec::new();
+                if format == ImageFormat::Png {
+                    self.write_png(&mut bytes, width, height, &indices, Some(&colors)).map_err(|e| error(&e))?;
+                } else {
+                    write_indexed_gif(&mut bytes, width, height, &indices, &colors, language)?;
+                }
+                return Ok(bytes);
+            }
+
+            pixels = indices
+                .iter()
+                .map(|index| index.map_or([0, 0, 0, 0], |index| {
+                    let [r, g, b] = palette[index as usize];
+                    [r, g, b, 255]
+                }))
+                .collect();
+        }
+
+        let (w, h) = (width as u32, height as u32);
+        let rgba: Vec<u8> = pixels.iter().flatten().copied().collect();
+        let mut bytes = Vec::new();
+        match format {
+            ImageFormat::Png => {
+                self.write_png(&mut bytes, width, height, &rgba, None).map_err(|e| error(&e))?;
+            },
+            ImageFormat::Jpeg => {
+                let rgb: Vec<u8> = pixels.iter().flat_map(|pixel| [pixel[0], pixel[1], pixel[2]]).collect();
+                let mut encoder = JpegEncoder::new_with_quality(&mut bytes, self.jpeg_quality.clamp(1, 100));
+                if self.embed_dpi {
+                    encoder.set_pixel_density(PixelDensity {
+                        density: (self.dpi, self.dpi),
+                        unit: PixelDensityUnit::Inches,
+                    });
+                }
+                encoder.encode(&rgb, w, h, image::ColorType::Rgb8).map_err(|e| error(&e))?;
+            },
+            ImageFormat::WebP => match self.webp_mode {
+                WebpMode::Lossless => {
+                    WebPEncoder::new_lossless(&mut bytes)
+                        .encode(&rgba, w, h, image::ColorType::Rgba8)
+                        .map_err(|e| error(&e))?;
+                },
+                WebpMode::Lossy => {
+                    let encoded = webp::Encoder::from_rgba(&rgba, w, h)
+                        .encode_simple(false, self.webp_quality.min(100) as f32)
+                        .map_err(|e| error(&format!("{:?}", e)))?;
+                    bytes = encoded.to_vec();
+                },
+            },
+            ImageFormat::Tiff => {
+                let mut cursor = Cursor::new(Vec::new());
+                self.write_tiff(&mut cursor, w, h, &rgba).map_err(|e| error(&e))?;
+                bytes = cursor.into_inner();
+            },
+            _ => {
+                let buffer = image::RgbaImage::from_raw(w, h, rgba)
+                    .ok_or_else(|| get_text("error_saving_image", language))?;
+                buffer.write_to(&mut Cursor::new(&mut bytes), format).map_err(|e| error(&e))?;
+            },
+        }
+        Ok(bytes)
+    }
+
+    pub fn png_encoder<W: Write>(&self, writer: W, width: usize, height: usize) -> Result<png::Encoder<'static, W>, png::EncodingError> {
+        let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_compression(match self.png_compression {
+            PngCompression::Fast => png::Compression::Fast,
+            PngCompression::Default => png::Compression::Default,
+            PngCompression::Best => png::Compression::Best,
+        });
+
+        if self.embed_dpi {
+            let pixels_per_meter = (self.dpi as f32 / METERS_PER_INCH).round() as u32;
+            encoder.set_pixel_dims(Some(png::PixelDimensions {
+                xppu: pixels_per_meter,
+                yppu: pixels_per_meter,
+                unit: png::Unit::Meter,
+            }));
+        }
+        for (keyword, text) in self.metadata() {
+            encoder.add_text_chunk(keyword.to_string(), text)?;
+        }
+        Ok(encoder)
+    }
+
+    fn write_png(&self, writer: impl Write, width: usize, height: usize, data: &[u8], palette: Option<&[[u8; 4]]>) -> Result<(), png::EncodingError> {
+        let mut encoder = self.png_encoder(writer, width, height)?;
+        if let Some(palette) = palette {
+            encoder.set_color(png::ColorType::Indexed);
+            encoder.set_palette(palette.iter().flat_map(|color| [color[0], color[1], color[2]]).collect::<Vec<u8>>());
+            if palette.iter().any(|color| color[3] < 255) {
+                encoder.set_trns(palette.iter().map(|color| color[3]).collect::<Vec<u8>>());
+            }
+        }
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(data)
+    }
+
+    fn write_tiff(&self, writer: &mut Cursor<Vec<u8>>, width: u32, height: u32, rgba: &[u8]) -> Result<(), tiff::TiffError> {
+        use tiff::encoder::{Rational, TiffEncoder, colortype::RGBA8, compression};
+        use tiff::tags::{ResolutionUnit, Tag};
+
+        let mut encoder = TiffEncoder::new(writer)?;
+        macro_rules! write_image {
+            ($compression:expr) => {{
+                let mut image = encoder.new_image_with_compression::<RGBA8, _>(width, height, $compression)?;
+                if self.embed_dpi {
+                    image.resolution(ResolutionUnit::Inch, Rational { n: self.dpi as u32, d: 1 });
+                }
+                for (keyword, text) in self.metadata() {
+                    let tag = if keyword == "Author" { Tag::Artist } else { Tag::Software };
+                    image.encoder().write_tag(tag, text.as_str())?;
+                }
+                image.write_data(rgba)
+            }};
+        }
+
+        match self.tiff_compression {
+            TiffCompression::None => write_image!(compression::Uncompressed),
+            TiffCompression::Lzw => write_image!(compression::Lzw),
+            TiffCompression::Deflate => write_image!(compression::Deflate::default()),
+            TiffCompression::PackBits => write_image!(compression::Packbits),
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, format: ImageFormat, indexed_document: bool, language: Language) {
+        match format {
+            ImageFormat::Jpeg => {
+                ui.add(egui::Slider::new(&mut self.jpeg_quality, 1..=100).text(get_text("jpeg_quality", language)));
+            },
+            ImageFormat::Png => {
+                egui::ComboBox::from_label(get_text("png_compression", language))
+                    .selected_text(self.png_compression.get_name(language))
+                    .show_ui(ui, |ui| {
+                        for level in PngCompression::all_levels() {
+                            ui.selectable_value(&mut self.png_compression, level, level.get_name(language));
+                        }
+                    });
+            },
+            ImageFormat::WebP => {
+                egui::ComboBox::from_label(get_text("webp_mode", language))
+                    .selected_text(self.webp_mode.get_name(language))
+                    .show_ui(ui, |ui| {
+                        for mode in WebpMode::all_modes() {
+                            ui.selectable_value(&mut self.webp_mode, mode, mode.get_name(language));
+                        }
+                    });
+                if self.webp_mode == WebpMode::Lossy {
+                    ui.add(egui::Slider::new(&mut self.webp_quality, 0..=100).text(get_text("webp_quality", language)));
+                }
+            },
+            ImageFormat::Tiff => {
+                egui::ComboBox::from_label(get_text("tiff_compression", language))
+                    .selected_text(self.tiff_compression.get_name(language))
+                    .show_ui(ui, |ui| {
+                        for compression in TiffCompression::all_compressions() {
+                            ui.selectable_value(&mut self.tiff_compression, compression, compression.get_name(language));
+                        }
+                    });
+            },
+            _ => {},
+        }
+
+        if indexed_document && matches!(format, ImageFormat::Png | ImageFormat::Gif) {
+            ui.label(get_text("export_document_palette", language));
+        } else {
+            if format != ImageFormat::Gif {
+                ui.checkbox(&mut self.reduce_colors, get_text("reduce_colors", language));
+            }
+
+            ui.add_enabled_ui(self.quantizes(format), |ui| {
+                ui.add(egui::Slider::new(&mut self.palette_size, MIN_PALETTE_SIZE..=MAX_INDEXED_COLORS).text(get_text("palette_size", language)));
+                egui::ComboBox::from_label(get_text("quantizer", language))
+                    .selected_text(self.quantizer.get_name(language))
+                    .show_ui(ui, |ui| {
+                        for quantizer in Quantizer::all_quantizers() {
+                            ui.selectable_value(&mut self.quantizer, quantizer, quantizer.get_name(language));
+                        }
+                    });
+                egui::ComboBox::from_label(get_text("dithering", language))
+                    .selected_text(self.dithering.get_name(language))
+                    .show_ui(ui, |ui| {
+                        for dithering in Dithering::all_ditherings() {
+                            ui.selectable_value(&mut self.dithering, dithering, dithering.get_name(language));
+                        }
+                    });
+            });
+        }
+
+        if !Self::supports_alpha(format) || self.quantizes(format) || indexed_document {
+            ui.horizontal(|ui| {
+                let [r, g, b] = self.matte;
+                let mut matte = Color32::from_rgb(r, g, b);
+                if ui.color_edit_button_srgba(&mut matte).changed() {
+                    self.matte = [matte.r(), matte.g(), matte.b()];
+                }
+                ui.label(get_text("matte_color", language));
+            });
+        }
+
+        if Self::supports_dpi(format) {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.embed_dpi, get_text("embed_dpi", language));
+                ui.add_enabled(self.embed_dpi, egui::DragValue::new(&mut self.dpi).clamp_range(1..=2400).suffix(" dpi"));
+            });
+        }
+        if Self::supports_metadata(format) {
+            ui.checkbox(&mut self.embed_metadata, get_text("embed_metadata", language));
+            if self.embed_metadata {
+                ui.add(egui::TextEdit::singleline(&mut self.author).hint_text(get_text("metadata_author", language)));
+            }
+        }
+    }
+}
+
+fn write_indexed_gif(writer: impl Write, width: usize, height: usize, indices: &[u8], palette: &[[u8; 4]], language: Language) -> Result<(), String> {
+    let error = |e: &dyn std::fmt::Display| format!("{}: {}", get_text("error_saving_image", language), e);
+    let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+        return Err(get_text("image_too_large_for_gif", language));
+    };
+
+    let rgb: Vec<u8> = palette.iter().flat_map(|color| [color[0], color[1], color[2]]).collect();
+    let transparent = palette.iter().position(|color| color[3] < 128).map(|index| index as u8);
+    let mut encoder = gif::Encoder::new(writer, width, height, &rgb).map_err(|e| error(&e))?;
+    let frame = gif::Frame::from_indexed_pixels(width, height, indices, transparent);
+    encoder.write_frame(&frame).map_err(|e| error(&e))
+}
+
+pub struct ExportSettings {
+    formats: HashMap<String, ExportOptions>,
+    settings_path: PathBuf,
+}
+
+impl ExportSettings {
+    pub fn load_default() -> Self {
+        let settings_path = PathBuf::from(EXPORT_SETTINGS_PATH);
+        let formats = fs::read_to_string(&settings_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            formats,
+            settings_path,
+        }
+    }
+
+    pub fn save(&self, language: Language) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&self.formats)
+            .map_err(|e| format!("{}: {}", get_text("error_saving_export_settings", language), e))?;
+        fs::write(&self.settings_path, json)
+            .map_err(|e| format!("{}: {}", get_text("error_saving_export_settings", language), e))
+    }
+
+    pub fn options(&self, format: ImageFormat) -> ExportOptions {
+        self.formats.get(&format_key(format)).cloned().unwrap_or_default()
+    }
+
+    pub fn options_mut(&mut self, format: ImageFormat) -> &mut ExportOptions {
+        self.formats.entry(format_key(format)).or_default()
+    }
+}
+
+struct RenderedPreview {
+    format: ImageFormat,
+    options: ExportOptions,
+    size: Result<usize, String>,
+    texture: Option<TextureHandle>,
+}
+
+/// Estimated size and cropped preview of an export. The document is composited once
+/// when the options window opens; re-encoding waits until option edits settle.
+pub struct ExportPreview {
+    width: usize,
+    height: usize,
+    pixels: Vec<[u8; 4]>,
+    palette: Option<Vec<[u8; 3]>>,
+    rendered: Option<RenderedPreview>,
+    changed_at: Option<Instant>,
+}
+
+impl ExportPreview {
+    pub fn new(width: usize, height: usize, pixels: Vec<[u8; 4]>, palette: Option<Vec<[u8; 3]>>) -> Self {
+        Self {
+            width,
+            height,
+            pixels,
+            palette,
+            rendered: None,
+            changed_at: None,
+        }
+    }
+
+    /// Re-encodes the preview when the options changed, once no drag is in progress and
+    /// the options stayed untouched for a moment. Returns how long to wait before the
+    /// next check when a rebuild is still pending.
+    pub fn update(&mut self, ctx: &egui::Context, format: ImageFormat, options: &ExportOptions, language: Language) -> Option<Duration> {
+        if self.rendered.as_ref().is_some_and(|rendered| rendered.format == format && rendered.options == *options) {
+            self.changed_at = None;
+            return None;
+        }
+
+        let now = Instant::now();
+        if self.rendered.is_some() {
+            let (dragging, released) = ctx.input(|i| (i.pointer.any_down(), i.pointer.any_released()));
+            if dragging {
+                self.changed_at = Some(now);
+                return Some(PREVIEW_IDLE);
+            }
+            let idle = now - *self.changed_at.get_or_insert(now);
+            if !released && idle < PREVIEW_IDLE {
+                return Some(PREVIEW_IDLE - idle);
+            }
+        }
+
+        let encoded = options.encode(format, self.width, self.height, self.pixels.clone(), self.palette.as_deref(), language);
+        self.rendered = Some(RenderedPreview::new(ctx, format, options, encoded));
+        self.changed_at = None;
+        None
+    }
+
+    pub fn ui(&self, ui: &mut egui::Ui, language: Language) {
+        if let Some(rendered) = &self.rendered {
+            rendered.ui(ui, language);
+        }
+    }
+}
+
+impl RenderedPreview {
+    fn new(
+        ctx: &egui::Context,
+        format: ImageFormat,
+        options: &ExportOptions,
+        encoded: Result<Vec<u8>, String>,
+    ) -> Self {
+        let texture = encoded.as_ref().ok().and_then(|bytes| {
+            let decoded = image::load_from_memory_with_format(bytes, format).ok()?.to_rgba8();
+            let (width, height) = (decoded.width() as usize, decoded.height() as usize);
+            let (crop_width, crop_height) = (width.min(PREVIEW_SIZE), height.min(PREVIEW_SIZE));
+            let (x0, y0) = ((width - crop_width) / 2, (height - crop_height) / 2);
+
+            let checker = |x: usize, y: usize| if (x / 8 + y / 8).is_multiple_of(2) { 200.0 } else { 160.0 };
+            let pixels = (0..crop_width * crop_height)
+                .map(|i| {
+                    let (x, y) = (i % crop_width, i / crop_width);
+                    let [r, g, b, a] = decoded.get_pixel((x0 + x) as u32, (y0 + y) as u32).0;
+                    let alpha = a as f32 / 255.0;
+                    let channel = |value: u8| (value as f32 * alpha + checker(x, y) * (1.0 - alpha)).round() as u8;
+                    Color32::from_rgb(channel(r), channel(g), channel(b))
+                })
+                .collect();
+            let image = egui::ColorImage {
+                size: [crop_width, crop_height],
+                pixels,
+            };
+            Some(ctx.load_texture("export_preview", image, TextureOptions::NEAREST))
+        });
+
+        Self {
+            format,
+            options: options.clone(),
+            size: encoded.map(|bytes| bytes.len()),
+            texture,
+        }
+    }
+
+    fn ui(&self, ui: &mut egui::Ui, language: Language) {
+        match &self.size {
+            Ok(size) => {
+                ui.label(format!("{}: {:.1} KB", get_text("estimated_file_size", language), *size as f32 / 1024.0));
+            },
+            Err(e) => {
+                ui.colored_label(Color32::from_rgb(220, 80, 80), e);
+            },
+        }
+        if let Some(texture) = &self.texture {
+            ui.label(get_text("export_preview", language));
+            ui.image(texture, texture.size_vec2() * PREVIEW_ZOOM);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixels() -> Vec<[u8; 4]> {
+        (0..64).map(|i| [i as u8 * 4, 255 - i as u8, 37, (i * 3) as u8]).collect()
+    }
+
+    #[test]
+    fn webp_round_trips_exactly() {
+        let bytes = ExportOptions::default().encode(ImageFormat::WebP, 8, 8, pixels(), None, Language::English).unwrap();
+        let decoded = image::load_from_memory_with_format(&bytes, ImageFormat::WebP).unwrap().to_rgba8();
+        let decoded: Vec<[u8; 4]> = decoded.pixels().map(|pixel| pixel.0).collect();
+        assert_eq!(decoded, pixels());
+    }
+
+    #[test]
+    fn lossy_webp_shrinks_with_quality_and_keeps_alpha() {
+        let noisy: Vec<[u8; 4]> = (0..64 * 64u32)
+            .map(|i| {
+                let v = i.wrapping_mul(2_654_435_761) >> 24;
+                [v as u8, (v * 3) as u8, (i % 64 * 4) as u8, if i % 64 < 32 { 255 } else { 0 }]
+            })
+            .collect();
+        let encode = |quality| {
+            let options = ExportOptions { webp_mode: WebpMode::Lossy, webp_quality: quality, ..ExportOptions::default() };
+            options.encode(ImageFormat::WebP, 64, 64, noisy.clone(), None, Language::English).unwrap()
+        };
+
+        let (low, high) = (encode(10), encode(95));
+        assert!(low.len() < high.len());
+        let decoded = image::load_from_memory_with_format(&low, ImageFormat::WebP).unwrap().to_rgba8();
+        assert_eq!((decoded.width(), decoded.height()), (64, 64));
+        assert_eq!(decoded.get_pixel(0, 0).0[3], 255);
+        assert_eq!(decoded.get_pixel(63, 0).0[3], 0);
+    }
+
+    #[test]
+    fn old_near_lossless_settings_load_as_lossy() {
+        let json = r#"{"webp": {"webp_mode": "NearLossless", "webp_quality": 40, "jpeg_quality": 70}}"#;
+        let formats: HashMap<String, ExportOptions> = serde_json::from_str(json).unwrap();
+        assert_eq!(formats["webp"].webp_mode, WebpMode::Lossy);
+        assert_eq!(formats["webp"].webp_quality, 40);
+        assert_eq!(formats["webp"].jpeg_quality, 70);
+    }
+
+    #[test]
+    fn loaded_palette_size_is_clamped() {
+        let formats: HashMap<String, ExportOptions> = serde_json::from_str(r#"{"gif": {"palette_size": 4000}, "png": {"palette_size": 0}}"#).unwrap();
+        assert_eq!(formats["gif"].palette_size, MAX_INDEXED_COLORS);
+        assert_eq!(formats["png"].palette_size, MIN_PALETTE_SIZE);
+
+        let bytes = formats["gif"].encode(ImageFormat::Gif, 8, 8, pixels(), None, Language::English).unwrap();
+        assert!(image::load_from_memory_with_format(&bytes, ImageFormat::Gif).is_ok());
+    }
+}
@@ -0,0 +1,351 @@
+use eframe::egui;
+use egui::{Color32, Pos2};
+use serde::{Serialize, Deserialize};
+use std::f32::consts::PI;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::localization::{Language, get_text};
+
+const GRADIENT_LIBRARY_PATH: &str = "gradients.json";
+const PREVIEW_WIDTH: usize = 160;
+const PREVIEW_HEIGHT: usize = 16;
+pub const BAYER_4X4: [f32; 16] = [
+    0.0, 8.0, 2.0, 10.0,
+    12.0, 4.0, 14.0, 6.0,
+    3.0, 11.0, 1.0, 9.0,
+    15.0, 7.0, 13.0, 5.0,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientShape {
+    Linear,
+    Radial,
+    Conic,
+    Diamond,
+    Reflected,
+}
+
+impl GradientShape {
+    pub fn get_name(&self, language: Language) -> String {
+        match self {
+            GradientShape::Linear => get_text("gradient_linear", language),
+            GradientShape::Radial => get_text("gradient_radial", language),
+            GradientShape::Conic => get_text("gradient_conic", language),
+            GradientShape::Diamond => get_text("gradient_diamond", language),
+            GradientShape::Reflected => get_text("gradient_reflected", language),
+        }
+    }
+
+    pub fn all_shapes() -> Vec<GradientShape> {
+        vec![
+            GradientShape::Linear,
+            GradientShape::Radial,
+            GradientShape::Conic,
+            GradientShape::Diamond,
+            GradientShape::Reflected,
+        ]
+    }
+
+    pub fn position(&self, start: Pos2, end: Pos2, x: f32, y: f32) -> f32 {
+        let axis = end - start;
+        let length = axis.length().max(f32::EPSILON);
+        let dx = x - start.x;
+        let dy = y - start.y;
+
+        let t = match self {
+            GradientShape::Linear => (dx * axis.x + dy * axis.y) / (length * length),
+            GradientShape::Radial => (dx * dx + dy * dy).sqrt() / length,
+            GradientShape::Conic => {
+                let angle = dy.atan2(dx) - axis.y.atan2(axis.x);
+                angle.rem_euclid(2.0 * PI) / (2.0 * PI)
+            },
+            GradientShape::Diamond => {
+                let (ux, uy) = (axis.x / length, axis.y / length);
+                let along = (dx * ux + dy * uy).abs();
+                let across = (-dx * uy + dy * ux).abs();
+                (along + across) / length
+            },
+            GradientShape::Reflected => ((dx * axis.x + dy * axis.y) / (length * length)).abs(),
+        };
+
+        t.clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GradientStop {
+    pub position: f32,
+    pub color: [u8; 4],
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Gradient {
+    pub name: String,
+    pub stops: Vec<GradientStop>,
+}
+
+impl Gradient {
+    pub fn two_colors(name: String, from: Color32, to: Color32) -> Self {
+        Self {
+            name,
+            stops: vec![
+                GradientStop { position: 0.0, color: from.to_srgba_unmultiplied() },
+                GradientStop { position: 1.0, color: to.to_srgba_unmultiplied() },
+            ],
+        }
+    }
+
+    fn sorted_stops(&self) -> Vec<GradientStop> {
+        let mut stops = self.stops.clone();
+        stops.sort_by(|a, b| a.position.partial_cmp(&b.position).unwrap_or(std::cmp::Ordering::Equal));
+        stops
+    }
+
+    pub fn sample(&self, t: f32) -> [f32; 4] {
+        sample_stops(&self.sorted_stops(), t)
+    }
+
+    pub fn render(&self, width: usize, height: usize, dither: bool, color_at: impl Fn(usize, usize) -> f32) -> Vec<[f32; 4]> {
+        let stops = self.sorted_stops();
+        let mut pixels = Vec::with_capacity(width * height);
+
+        for y in 0..height {
+            for x in 0..width {
+                let mut color = sample_stops(&stops, color_at(x, y));
+                if dither {
+                    let threshold = (BAYER_4X4[(y % 4) * 4 + x % 4] + 0.5) / 16.0 - 0.5;
+                    for channel in color.iter_mut() {
+                        *channel = (*channel + threshold).clamp(0.0, 255.0);
+                    }
+                }
+                pixels.push(color);
+            }
+        }
+
+        pixels
+    }
+}
+
+fn sample_stops(stops: &[GradientStop], t: f32) -> [f32; 4] {
+    let premultiplied = |stop: &GradientStop| {
+        let alpha = stop.color[3] as f32 / 255.0;
+        [
+            stop.color[0] as f32 * alpha,
+            stop.color[1] as f32 * alpha,
+            stop.color[2] as f32 * alpha,
+            stop.color[3] as f32,
+        ]
+    };
+
+    let (first, last) = match (stops.first(), stops.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return [0.0; 4],
+    };
+
+    if t <= first.position {
+        return premultiplied(first);
+    }
+    if t >= last.position {
+        return premultiplied(last);
+    }
+
+    let segment = stops
+        .windows(2)
+        .find(|w| t >= w[0].position && t <= w[1].position);
+
+    match segment {
+        Some(w) => {
+            let span = (w[1].position - w[0].position).max(f32::EPSILON);
+            let local = (t - w[0].position) / span;
+            let a = premultiplied(&w[0]);
+            let b = premultiplied(&w[1]);
+            [
+                a[0] + (b[0] - a[0]) * local,
+                a[1] + (b[1] - a[1]) * local,
+                a[2] + (b[2] - a[2]) * local,
+                a[3] + (b[3] - a[3]) * local,
+            ]
+        },
+        None => premultiplied(last),
+    }
+}
+
+pub struct GradientLibrary {
+    pub gradients: Vec<Gradient>,
+    library_path: PathBuf,
+}
+
+impl GradientLibrary {
+    pub fn load_default() -> Self {
+        let library_path = PathBuf::from(GRADIENT_LIBRARY_PATH);
+        let gradients = fs::read_to_string(&library_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            gradients,
+            library_path,
+        }
+    }
+
+    pub fn save(&self, language: Language) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&self.gradients)
+            .map_err(|e| format!("{}: {}", get_text("error_saving_gradients", language), e))?;
+        fs::write(&self.library_path, json)
+            .map_err(|e| format!("{}: {}", get_text("error_saving_gradients", language), e))
+    }
+
+    pub fn add(&mut self, gradient: Gradient) {
+        if let Some(existing) = self.gradients.iter_mut().find(|g| g.name == gradient.name) {
+            *existing = gradient;
+        } else {
+            self.gradients.push(gradient);
+        }
+    }
+}
+
+pub struct GradientTool {
+    pub shape: GradientShape,
+    pub gradient: Gradient,
+    pub dither: bool,
+    pub library: GradientLibrary,
+    pub drag_start: Option<Pos2>,
+    pub drag_end: Option<Pos2>,
+    new_name: String,
+    preview: Option<(Gradient, egui::TextureHandle)>,
+}
+
+impl Default for GradientTool {
+    fn default() -> Self {
+        Self {
+            shape: GradientShape::Linear,
+            gradient: Gradient::two_colors(String::new(), Color32::BLACK, Color32::WHITE),
+            dither: true,
+            library: GradientLibrary::load_default(),
+            drag_start: None,
+            drag_end: None,
+            new_name: String::new(),
+            preview: None,
+        }
+    }
+}
+
+impl GradientTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn preview_texture(&mut self, ctx: &egui::Context) -> egui::TextureHandle {
+        if let Some((_, texture)) = self.preview.as_ref().filter(|(gradient, _)| *gradient == self.gradient) {
+            return texture.clone();
+        }
+
+        let checker = |x: usize, y: usize| if (x / 4 + y / 4).is_multiple_of(2) { 200.0 } else { 140.0 };
+        let colors = self.gradient.render(PREVIEW_WIDTH, PREVIEW_HEIGHT, false, |x, _| {
+            x as f32 / (PREVIEW_WIDTH - 1) as f32
+        });
+        let pixels = colors
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let background = checker(i % PREVIEW_WIDTH, i / PREVIEW_WIDTH);
+                let inverse_alpha = 1.0 - c[3] / 255.0;
+                let channel = |value: f32| (value + background * inverse_alpha).clamp(0.0, 255.0) as u8;
+                Color32::from_rgb(channel(c[0]), channel(c[1]), channel(c[2]))
+            })
+            .collect();
+
+        let image = egui::ColorImage {
+            size: [PREVIEW_WIDTH, PREVIEW_HEIGHT],
+            pixels,
+        };
+        let texture = ctx.load_texture("gradient_preview", image, egui::TextureOptions::NEAREST);
+        self.preview = Some((self.gradient.clone(), texture.clone()));
+        texture
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut egui::Ui, ctx: &egui::Context, primary: Color32, secondary: Color32, language: Language) -> Option<String> {
+        let mut error = None;
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{}:", get_text("gradient_shape", language)));
+            egui::ComboBox::from_id_source("gradient_shape")
+                .selected_text(self.shape.get_name(language))
+                .show_ui(ui, |ui| {
+                    for shape in GradientShape::all_shapes() {
+                        ui.selectable_value(&mut self.shape, shape, shape.get_name(language));
+                    }
+                });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label(format!("{}:", get_text("gradient_preset", language)));
+            egui::ComboBox::from_id_source("gradient_preset")
+                .selected_text(get_text("curve_choose", language))
+                .show_ui(ui, |ui| {
+                    if ui.selectable_label(false, get_text("gradient_primary_secondary", language)).clicked() {
+                        self.gradient = Gradient::two_colors(String::new(), primary, secondary);
+                    }
+                    if ui.selectable_label(false, get_text("gradient_primary_transparent", language)).clicked() {
+                        self.gradient = Gradient::two_colors(String::new(), primary, Color32::TRANSPARENT);
+                    }
+                    for saved in &self.library.gradients {
+                        if ui.selectable_label(false, &saved.name).clicked() {
+                            self.gradient = saved.clone();
+                        }
+                    }
+                });
+        });
+
+        let texture = self.preview_texture(ctx);
+        ui.image(&texture, egui::Vec2::new(PREVIEW_WIDTH as f32, PREVIEW_HEIGHT as f32));
+
+        let mut remove = None;
+        let stop_count = self.gradient.stops.len();
+        for (index, stop) in self.gradient.stops.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                let mut color = Color32::from_rgba_unmultiplied(stop.color[0], stop.color[1], stop.color[2], stop.color[3]);
+                if ui.color_edit_button_srgba(&mut color).changed() {
+                    stop.color = color.to_srgba_unmultiplied();
+                }
+                ui.add(egui::Slider::new(&mut stop.position, 0.0..=1.0).show_value(false));
+                if stop_count > 2 && ui.small_button("✖").clicked() {
+                    remove = Some(index);
+                }
+            });
+        }
+        if let Some(index) = remove {
+            self.gradient.stops.remove(index);
+        }
+
+        if ui.button(get_text("add_gradient_stop", language)).clicked() {
+            let color = self.gradient.sample(0.5);
+            let alpha = color[3] / 255.0;
+            let unpremultiply = |value: f32| if alpha > 0.0 { (value / alpha).clamp(0.0, 255.0) as u8 } else { 0 };
+            self.gradient.stops.push(GradientStop {
+                position: 0.5,
+                color: [unpremultiply(color[0]), unpremultiply(color[1]), unpremultiply(color[2]), color[3] as u8],
+            });
+        }
+
+        ui.checkbox(&mut self.dither, get_text("gradient_dither", language));
+
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.new_name)
+                .hint_text(get_text("gradient_name", language))
+                .desired_width(100.0));
+            let can_save = !self.new_name.trim().is_empty();
+            if ui.add_enabled(can_save, egui::Button::new(get_text("save_gradient", language))).clicked() {
+                let mut gradient = self.gradient.clone();
+                gradient.name = self.new_name.trim().to_string();
+                self.library.add(gradient);
+                self.new_name.clear();
+                error = self.library.save(language).err();
+            }
+        });
+
+        error
+    }
+}
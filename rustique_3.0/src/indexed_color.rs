@@ -0,0 +1,256 @@
+use eframe::egui;
+use egui::Color32;
+use serde::{Serialize, Deserialize};
+
+use crate::localization::{Language, get_text};
+
+pub const MAX_INDEXED_COLORS: usize = 255;
+const ALPHA_THRESHOLD: u8 = 128;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredPalette")]
+pub struct IndexedPalette {
+    pub colors: Vec<[u8; 3]>,
+}
+
+#[derive(Deserialize)]
+struct StoredPalette {
+    colors: Vec<[u8; 3]>,
+}
+
+impl From<StoredPalette> for IndexedPalette {
+    fn from(mut stored: StoredPalette) -> Self {
+        stored.colors.truncate(MAX_INDEXED_COLORS);
+        Self { colors: stored.colors }
+    }
+}
+
+impl IndexedPalette {
+    pub fn from_colors(colors: impl IntoIterator<Item = [u8; 4]>) -> Self {
+        let mut unique: Vec<[u8; 3]> = Vec::new();
+        for [r, g, b, _] in colors {
+            if !unique.contains(&[r, g, b]) && unique.len() < MAX_INDEXED_COLORS {
+                unique.push([r, g, b]);
+            }
+        }
+        Self { colors: unique }
+    }
+
+    pub fn color(&self, index: usize) -> Color32 {
+        let [r, g, b] = self.colors[index];
+        Color32::from_rgb(r, g, b)
+    }
+
+    pub fn nearest(&self, color: Color32) -> Option<usize> {
+        let [r, g, b, _] = color.to_srgba_unmultiplied();
+        self.colors
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, entry)| {
+                let dr = entry[0] as i32 - r as i32;
+                let dg = entry[1] as i32 - g as i32;
+                let db = entry[2] as i32 - b as i32;
+                2 * dr * dr + 4 * dg * dg + 3 * db * db
+            })
+            .map(|(index, _)| index)
+    }
+
+    pub fn index_of(&self, color: Option<Color32>) -> Option<usize> {
+        color.filter(|color| color.a() >= ALPHA_THRESHOLD).and_then(|color| self.nearest(color))
+    }
+
+    pub fn snap(&self, color: Option<Color32>) -> Option<Color32> {
+        self.index_of(color).map(|index| self.color(index))
+    }
+
+    pub fn resolve(&self, indices: &[Option<u8>], pixels: &mut [Option<Color32>]) {
+        for (pixel, index) in pixels.iter_mut().zip(indices) {
+            *pixel = index.map(|index| self.color(index as usize));
+        }
+    }
+
+    pub fn recolor(&self, index: usize, indices: &[Option<u8>], pixels: &mut [Option<Color32>]) {
+        let color = Some(self.color(index));
+        for (pixel, _) in pixels.iter_mut().zip(indices).filter(|(_, entry)| **entry == Some(index as u8)) {
+            *pixel = color;
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub enum ColorMode {
+    #[default]
+    Rgba,
+    Indexed(IndexedPalette),
+}
+
+impl ColorMode {
+    pub fn palette(&self) -> Option<&IndexedPalette> {
+        match self {
+            ColorMode::Rgba => None,
+            ColorMode::Indexed(palette) => Some(palette),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndexedSource {
+    ActivePalette,
+    Extracted,
+}
+
+impl IndexedSource {
+    pub fn get_name(&self, language: Language) -> String {
+        match self {
+            IndexedSource::ActivePalette => get_text("indexed_source_palette", language),
+            IndexedSource::Extracted => get_text("indexed_source_extracted", language),
+        }
+    }
+
+    pub fn all_sources() -> Vec<IndexedSource> {
+        vec![IndexedSource::ActivePalette, IndexedSource::Extracted]
+    }
+}
+
+pub enum IndexedAction {
+    None,
+    ConvertToIndexed(IndexedSource, usize),
+    ConvertToRgba,
+    EditEntry(usize, [u8; 3], bool),
+    SelectEntry(usize, bool),
+}
+
+pub struct IndexedPanel {
+    pub source: IndexedSource,
+    pub color_count: usize,
+    editing: bool,
+}
+
+impl Default for IndexedPanel {
+    fn default() -> Self {
+        Self {
+            source: IndexedSource::Extracted,
+            color_count: 16,
+            editing: false,
+        }
+    }
+}
+
+impl IndexedPanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, mode: &ColorMode, language: Language) -> IndexedAction {
+        let mut action = IndexedAction::None;
+
+        match mode {
+            ColorMode::Rgba => {
+                egui::ComboBox::from_id_source("indexed_source")
+                    .selected_text(self.source.get_name(language))
+                    .show_ui(ui, |ui| {
+                        for source in IndexedSource::all_sources() {
+                            ui.selectable_value(&mut self.source, source, source.get_name(language));
+                        }
+                    });
+                if self.source == IndexedSource::Extracted {
+                    ui.add(egui::Slider::new(&mut self.color_count, 2..=MAX_INDEXED_COLORS).text(get_text("palette_size", language)));
+                }
+                if ui.button(get_text("convert_to_indexed", language)).clicked() {
+                    action = IndexedAction::ConvertToIndexed(self.source, self.color_count);
+                }
+            },
+            ColorMode::Indexed(palette) => {
+                ui.label(format!("{}: {}", get_text("indexed_colors", language), palette.colors.len()));
+                ui.horizontal_wrapped(|ui| {
+                    ui.spacing_mut().item_spacing = egui::Vec2::splat(2.0);
+                    for (index, &[r, g, b]) in palette.colors.iter().enumerate() {
+                        let mut color = Color32::from_rgb(r, g, b);
+                        let response = ui.color_edit_button_srgba(&mut color);
+                        if response.changed() {
+                            let [r, g, b, _] = color.to_srgba_unmultiplied();
+                            action = IndexedAction::EditEntry(index, [r, g, b], !self.editing);
+                            self.editing = true;
+                        }
+                        if response.secondary_clicked() {
+                            action = IndexedAction::SelectEntry(index, false);
+                        }
+                        if response.middle_clicked() {
+                            action = IndexedAction::SelectEntry(index, true);
+                        }
+                        response.on_hover_text(format!("#{}", index));
+                    }
+                });
+                ui.label(get_text("indexed_entry_hint", language));
+
+                if !ui.memory(|memory| memory.any_popup_open()) {
+                    self.editing = false;
+                }
+
+                if ui.button(get_text("convert_to_rgba", language)).clicked() {
+                    action = IndexedAction::ConvertToRgba;
+                }
+            },
+        }
+
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn palette() -> IndexedPalette {
+        IndexedPalette { colors: vec![[0, 0, 0], [255, 0, 0], [255, 0, 0]] }
+    }
+
+    #[test]
+    fn resolve_maps_indices_to_colors() {
+        let indices = [Some(0), None, Some(2)];
+        let mut pixels = [None; 3];
+        palette().resolve(&indices, &mut pixels);
+        assert_eq!(pixels, [Some(Color32::BLACK), None, Some(Color32::from_rgb(255, 0, 0))]);
+    }
+
+    #[test]
+    fn recolor_only_touches_the_edited_entry() {
+        let mut palette = palette();
+        let indices = [Some(1), Some(2), Some(0), None];
+        let mut pixels = [None; 4];
+        palette.resolve(&indices, &mut pixels);
+
+        palette.colors[2] = [0, 0, 255];
+        palette.recolor(2, &indices, &mut pixels);
+        assert_eq!(pixels, [
+            Some(Color32::from_rgb(255, 0, 0)),
+            Some(Color32::from_rgb(0, 0, 255)),
+            Some(Color32::BLACK),
+            None,
+        ]);
+    }
+
+    #[test]
+    fn index_of_drops_transparent_pixels() {
+        let palette = palette();
+        assert_eq!(palette.index_of(None), None);
+        assert_eq!(palette.index_of(Some(Color32::from_rgba_unmultiplied(250, 0, 0, 10))), None);
+        assert_eq!(palette.index_of(Some(Color32::from_rgb(250, 10, 0))), Some(1));
+    }
+
+    #[test]
+    fn loaded_palettes_are_capped() {
+        let colors: Vec<[u8; 3]> = (0..300).map(|i| [(i % 256) as u8, (i / 256) as u8, 0]).collect();
+        let json = serde_json::to_string(&IndexedPalette { colors: colors.clone() }).unwrap();
+        let palette: IndexedPalette = serde_json::from_str(&json).unwrap();
+        assert_eq!(palette.colors, colors[..MAX_INDEXED_COLORS]);
+    }
+
+    #[test]
+    fn from_colors_dedups_and_caps_entries() {
+        let colors = (0..=255u8).flat_map(|value| [[value, 0, 0, 255], [value, 0, 0, 0]]);
+        let palette = IndexedPalette::from_colors(colors);
+        assert_eq!(palette.colors.len(), MAX_INDEXED_COLORS);
+        assert_eq!(palette.colors[1], [1, 0, 0]);
+    }
+}
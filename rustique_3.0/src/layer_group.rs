@@ -0,0 +1,134 @@
+use serde::{Serialize, Deserialize};
+
+use crate::localization::{Language, get_text};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum GroupBlend {
+    PassThrough,
+    Isolated,
+}
+
+impl GroupBlend {
+    pub fn get_name(&self, language: Language) -> String {
+        match self {
+            GroupBlend::PassThrough => get_text("group_pass_through", language),
+            GroupBlend::Isolated => get_text("group_isolated", language),
+        }
+    }
+
+    pub fn all_modes() -> Vec<GroupBlend> {
+        vec![GroupBlend::PassThrough, GroupBlend::Isolated]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerGroup {
+    pub id: u32,
+    pub name: String,
+    pub parent: Option<u32>,
+    pub visible: bool,
+    pub opacity: f32,
+    pub collapsed: bool,
+    pub blend: GroupBlend,
+}
+
+impl LayerGroup {
+    pub fn new(id: u32, name: String, parent: Option<u32>) -> Self {
+        Self {
+            id,
+            name,
+            parent,
+            visible: true,
+            opacity: 1.0,
+            collapsed: false,
+            blend: GroupBlend::PassThrough,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LayerNode {
+    Layer(usize),
+    Group(u32, Vec<LayerNode>),
+}
+
+pub fn find_group(groups: &[LayerGroup], id: u32) -> Option<&LayerGroup> {
+    groups.iter().find(|group| group.id == id)
+}
+
+pub fn group_chain(groups: &[LayerGroup], group: Option<u32>) -> Vec<u32> {
+    let mut chain = Vec::new();
+    let mut current = group.and_then(|id| find_group(groups, id));
+
+    while let Some(found) = current {
+        if chain.contains(&found.id) {
+            break;
+        }
+        chain.push(found.id);
+        current = found.parent.and_then(|id| find_group(groups, id));
+    }
+
+    chain.reverse();
+    chain
+}
+
+pub fn build_tree(memberships: impl Iterator<Item = Option<u32>>, groups: &[LayerGroup]) -> Vec<LayerNode> {
+    fn push_node(stack: &mut [(u32, Vec<LayerNode>)], root: &mut Vec<LayerNode>, node: LayerNode) {
+        match stack.last_mut() {
+            Some((_, children)) => children.push(node),
+            None => root.push(node),
+        }
+    }
+
+    let mut root = Vec::new();
+    let mut stack: Vec<(u32, Vec<LayerNode>)> = Vec::new();
+
+    for (index, membership) in memberships.enumerate() {
+        let chain = group_chain(groups, membership);
+        let common = stack
+            .iter()
+            .zip(&chain)
+            .take_while(|((open, _), id)| open == *id)
+            .count();
+
+        while stack.len() > common {
+            if let Some((id, children)) = stack.pop() {
+                push_node(&mut stack, &mut root, LayerNode::Group(id, children));
+            }
+        }
+        for &id in &chain[common..] {
+            stack.push((id, Vec::new()));
+        }
+        push_node(&mut stack, &mut root, LayerNode::Layer(index));
+    }
+
+    while let Some((id, children)) = stack.pop() {
+        push_node(&mut stack, &mut root, LayerNode::Group(id, children));
+    }
+
+    root
+}
+
+pub fn is_descendant(groups: &[LayerGroup], group: Option<u32>, ancestor: u32) -> bool {
+    group_chain(groups, group).contains(&ancestor)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LayerRow {
+    Group(u32),
+    Layer(usize),
+}
+
+pub fn display_rows(nodes: &[LayerNode], groups: &[LayerGroup], depth: usize, rows: &mut Vec<(LayerRow, usize)>) {
+    for node in nodes.iter().rev() {
+        match node {
+            LayerNode::Layer(index) => rows.push((LayerRow::Layer(*index), depth)),
+            LayerNode::Group(id, children) => {
+                rows.push((LayerRow::Group(*id), depth));
+                if !find_group(groups, *id).is_some_and(|group| group.collapsed) {
+                    display_rows(children, groups, depth + 1, rows);
+                }
+            },
+        }
+    }
+}
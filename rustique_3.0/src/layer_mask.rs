@@ -0,0 +1,44 @@
+use eframe::egui::Color32;
+use serde::{Serialize, Deserialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayerMask {
+    pub data: Vec<u8>,
+    pub enabled: bool,
+    pub inverted: bool,
+}
+
+impl LayerMask {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            data: vec![255; width * height],
+            enabled: true,
+            inverted: false,
+        }
+    }
+
+    pub fn factor(&self, idx: usize) -> f32 {
+        if !self.enabled {
+            return 1.0;
+        }
+        let value = self.data[idx] as f32 / 255.0;
+        if self.inverted { 1.0 - value } else { value }
+    }
+
+    pub fn as_color(&self, idx: usize) -> Color32 {
+        Color32::from_gray(self.data[idx])
+    }
+
+    pub fn paint(&mut self, idx: usize, color: Option<Color32>) {
+        let Some(color) = color else {
+            self.data[idx] = 255;
+            return;
+        };
+
+        let [r, g, b, a] = color.to_srgba_unmultiplied();
+        let luminance = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+        let alpha = a as f32 / 255.0;
+        let current = self.data[idx] as f32;
+        self.data[idx] = (current + (luminance - current) * alpha).round().clamp(0.0, 255.0) as u8;
+    }
+}
@@ -0,0 +1,474 @@
+use eframe::egui;
+use egui::Color32;
+use serde::{Serialize, Deserialize};
+use std::cell::{Ref, RefCell};
+
+use crate::compositing::blend_over;
+use crate::gradient::Gradient;
+use crate::localization::{Language, get_text};
+
+const FAR: f64 = 1e12;
+const BLUR_PASSES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum StrokePosition {
+    Inside,
+    Center,
+    Outside,
+}
+
+impl StrokePosition {
+    pub fn get_name(&self, language: Language) -> String {
+        match self {
+            StrokePosition::Inside => get_text("stroke_inside", language),
+            StrokePosition::Center => get_text("stroke_center", language),
+            StrokePosition::Outside => get_text("stroke_outside", language),
+        }
+    }
+
+    pub fn all_positions() -> Vec<StrokePosition> {
+        vec![StrokePosition::Inside, StrokePosition::Center, StrokePosition::Outside]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ShadowEffect {
+    pub enabled: bool,
+    pub color: [u8; 4],
+    pub opacity: f32,
+    pub angle: f32,
+    pub distance: f32,
+    pub size: f32,
+}
+
+impl Default for ShadowEffect {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            color: [0, 0, 0, 255],
+            opacity: 0.75,
+            angle: 120.0,
+            distance: 5.0,
+            size: 5.0,
+        }
+    }
+}
+
+impl ShadowEffect {
+    fn offset(&self) -> (i32, i32) {
+        let (sin, cos) = self.angle.to_radians().sin_cos();
+        ((-cos * self.distance).round() as i32, (sin * self.distance).round() as i32)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GlowEffect {
+    pub enabled: bool,
+    pub color: [u8; 4],
+    pub opacity: f32,
+    pub size: f32,
+}
+
+impl Default for GlowEffect {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            color: [255, 255, 190, 255],
+            opacity: 0.75,
+            size: 8.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StrokeEffect {
+    pub enabled: bool,
+    pub color: [u8; 4],
+    pub opacity: f32,
+    pub size: f32,
+    pub position: StrokePosition,
+}
+
+impl Default for StrokeEffect {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            color: [0, 0, 0, 255],
+            opacity: 1.0,
+            size: 3.0,
+            position: StrokePosition::Outside,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ColorOverlay {
+    pub enabled: bool,
+    pub color: [u8; 4],
+    pub opacity: f32,
+}
+
+impl Default for ColorOverlay {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            color: [255, 0, 0, 255],
+            opacity: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GradientOverlay {
+    pub enabled: bool,
+    pub gradient: Gradient,
+    pub opacity: f32,
+    pub angle: f32,
+}
+
+impl Default for GradientOverlay {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            gradient: Gradient::two_colors(String::new(), Color32::BLACK, Color32::WHITE),
+            opacity: 1.0,
+            angle: 90.0,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct StyleCache(RefCell<Option<Vec<[f32; 4]>>>);
+
+impl Clone for StyleCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl PartialEq for StyleCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LayerStyle {
+    pub drop_shadow: ShadowEffect,
+    pub inner_shadow: ShadowEffect,
+    pub outer_glow: GlowEffect,
+    pub stroke: StrokeEffect,
+    pub color_overlay: ColorOverlay,
+    pub gradient_overlay: GradientOverlay,
+    #[serde(skip)]
+    cache: StyleCache,
+}
+
+fn effect_color(color: [u8; 4], opacity: f32, coverage: f32) -> [f32; 4] {
+    let alpha = color[3] as f32 / 255.0 * opacity.clamp(0.0, 1.0) * coverage.clamp(0.0, 1.0);
+    [color[0] as f32 * alpha, color[1] as f32 * alpha, color[2] as f32 * alpha, alpha * 255.0]
+}
+
+fn shifted(values: &[f32], width: usize, height: usize, offset: (i32, i32), outside: f32) -> Vec<f32> {
+    let mut result = vec![outside; values.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let source_x = x as i32 - offset.0;
+            let source_y = y as i32 - offset.1;
+            if source_x >= 0 && source_y >= 0 && (source_x as usize) < width && (source_y as usize) < height {
+                result[y * width + x] = values[source_y as usize * width + source_x as usize];
+            }
+        }
+    }
+    result
+}
+
+fn box_blur_line(line: &mut [f32], scratch: &mut Vec<f32>, radius: usize) {
+    scratch.clear();
+    scratch.extend_from_slice(line);
+    let window = (2 * radius + 1) as f32;
+    let mut sum: f32 = scratch.iter().take(radius + 1).sum();
+
+    for i in 0..line.len() {
+        line[i] = sum / window;
+        if let Some(&entering) = scratch.get(i + radius + 1) {
+            sum += entering;
+        }
+        if i >= radius {
+            sum -= scratch[i - radius];
+        }
+    }
+}
+
+fn blur(values: &[f32], width: usize, height: usize, size: f32) -> Vec<f32> {
+    let radius = (size / BLUR_PASSES as f32).round() as usize;
+    let mut result = values.to_vec();
+    if radius == 0 {
+        return result;
+    }
+
+    let mut scratch = Vec::new();
+    let mut column = vec![0.0; height];
+    for _ in 0..BLUR_PASSES {
+        for row in result.chunks_mut(width) {
+            box_blur_line(row, &mut scratch, radius);
+        }
+        for x in 0..width {
+            for y in 0..height {
+                column[y] = result[y * width + x];
+            }
+            box_blur_line(&mut column, &mut scratch, radius);
+            for y in 0..height {
+                result[y * width + x] = column[y];
+            }
+        }
+    }
+    result
+}
+
+fn squared_distance_line(f: &[f64], output: &mut [f64]) {
+    let n = f.len();
+    let mut v = vec![0usize; n];
+    let mut z = vec![0.0_f64; n + 1];
+    let mut k = 0;
+    z[0] = f64::NEG_INFINITY;
+    z[1] = f64::INFINITY;
+
+    let intersection = |q: usize, p: usize| {
+        let (qf, pf) = (q as f64, p as f64);
+        ((f[q] + qf * qf) - (f[p] + pf * pf)) / (2.0 * (qf - pf))
+    };
+
+    for q in 1..n {
+        let mut s = intersection(q, v[k]);
+        while s <= z[k] {
+            k -= 1;
+            s = intersection(q, v[k]);
+        }
+        k += 1;
+        v[k] = q;
+        z[k] = s;
+        z[k + 1] = f64::INFINITY;
+    }
+
+    k = 0;
+    for (q, out) in output.iter_mut().enumerate() {
+        while z[k + 1] < q as f64 {
+            k += 1;
+        }
+        let d = q as f64 - v[k] as f64;
+        *out = d * d + f[v[k]];
+    }
+}
+
+fn distance_field(targets: impl Fn(usize) -> bool, width: usize, height: usize) -> Vec<f32> {
+    let mut grid: Vec<f64> = (0..width * height).map(|i| if targets(i) { 0.0 } else { FAR }).collect();
+
+    let mut line = vec![0.0; height];
+    let mut output = vec![0.0; height.max(width)];
+    for x in 0..width {
+        for y in 0..height {
+            line[y] = grid[y * width + x];
+        }
+        squared_distance_line(&line, &mut output[..height]);
+        for y in 0..height {
+            grid[y * width + x] = output[y];
+        }
+    }
+    for row in grid.chunks_mut(width) {
+        line.clear();
+        line.extend_from_slice(row);
+        squared_distance_line(&line, &mut output[..width]);
+        row.copy_from_slice(&output[..width]);
+    }
+
+    grid.into_iter().map(|d| d.sqrt() as f32).collect()
+}
+
+impl LayerStyle {
+    pub fn is_active(&self) -> bool {
+        self.drop_shadow.enabled
+            || self.inner_shadow.enabled
+            || self.outer_glow.enabled
+            || self.stroke.enabled
+            || self.color_overlay.enabled
+            || self.gradient_overlay.enabled
+    }
+
+    pub fn invalidate(&self) {
+        self.cache.0.borrow_mut().take();
+    }
+
+    pub fn rendered(&self, width: usize, height: usize, content: impl Fn(usize) -> [f32; 4]) -> Ref<'_, [[f32; 4]]> {
+        if self.cache.0.borrow().is_none() {
+            let pixels: Vec<[f32; 4]> = (0..width * height).map(content).collect();
+            *self.cache.0.borrow_mut() = Some(self.render(pixels, width, height));
+        }
+        Ref::map(self.cache.0.borrow(), |cache| cache.as_deref().unwrap_or(&[]))
+    }
+
+    fn render(&self, content: Vec<[f32; 4]>, width: usize, height: usize) -> Vec<[f32; 4]> {
+        let alpha: Vec<f32> = content.iter().map(|pixel| pixel[3] / 255.0).collect();
+        let mut below = vec![[0.0; 4]; content.len()];
+        let mut above = vec![[0.0; 4]; content.len()];
+
+        if self.drop_shadow.enabled {
+            let shadow = &self.drop_shadow;
+            let coverage = blur(&shifted(&alpha, width, height, shadow.offset(), 0.0), width, height, shadow.size);
+            for (pixel, c) in below.iter_mut().zip(coverage) {
+                *pixel = blend_over(*pixel, effect_color(shadow.color, shadow.opacity, c));
+            }
+        }
+
+        if self.outer_glow.enabled {
+            let glow = &self.outer_glow;
+            let coverage = blur(&alpha, width, height, glow.size);
+            for (pixel, c) in below.iter_mut().zip(coverage) {
+                *pixel = blend_over(*pixel, effect_color(glow.color, glow.opacity, c * 2.0));
+            }
+        }
+
+        let mut styled = content;
+        if self.gradient_overlay.enabled {
+            let overlay = &self.gradient_overlay;
+            let (sin, cos) = overlay.angle.to_radians().sin_cos();
+            let extent = (width as f32 * cos.abs() + height as f32 * sin.abs()).max(1.0);
+            for (i, pixel) in styled.iter_mut().enumerate() {
+                let dx = (i % width) as f32 - width as f32 * 0.5;
+                let dy = height as f32 * 0.5 - (i / width) as f32;
+                let t = (dx * cos + dy * sin) / extent + 0.5;
+                let sample = overlay.gradient.sample(t);
+                if sample[3] <= 0.0 {
+                    continue;
+                }
+                let amount = sample[3] / 255.0 * overlay.opacity.clamp(0.0, 1.0);
+                for (channel, value) in pixel.iter_mut().zip(sample).take(3) {
+                    let target = value / sample[3] * 255.0 * alpha[i];
+                    *channel += (target - *channel) * amount;
+                }
+            }
+        }
+
+        if self.color_overlay.enabled {
+            let overlay = &self.color_overlay;
+            let amount = overlay.color[3] as f32 / 255.0 * overlay.opacity.clamp(0.0, 1.0);
+            for (pixel, a) in styled.iter_mut().zip(&alpha) {
+                for (channel, value) in pixel.iter_mut().zip(overlay.color).take(3) {
+                    *channel += (value as f32 * a - *channel) * amount;
+                }
+            }
+        }
+
+        if self.inner_shadow.enabled {
+            let shadow = &self.inner_shadow;
+            let outside: Vec<f32> = alpha.iter().map(|a| 1.0 - a).collect();
+            let coverage = blur(&shifted(&outside, width, height, shadow.offset(), 1.0), width, height, shadow.size);
+            for ((pixel, c), a) in above.iter_mut().zip(coverage).zip(&alpha) {
+                *pixel = blend_over(*pixel, effect_color(shadow.color, shadow.opacity, c * a));
+            }
+        }
+
+        if self.stroke.enabled {
+            let stroke = &self.stroke;
+            let (inner_size, outer_size) = match stroke.position {
+                StrokePosition::Inside => (stroke.size, 0.0),
+                StrokePosition::Center => (stroke.size * 0.5, stroke.size * 0.5),
+                StrokePosition::Outside => (0.0, stroke.size),
+            };
+
+            if outer_size > 0.0 {
+                let distance = distance_field(|i| alpha[i] >= 0.5, width, height);
+                for (pixel, d) in below.iter_mut().zip(distance) {
+                    let coverage = (outer_size + 0.5 - d).clamp(0.0, 1.0);
+                    *pixel = blend_over(*pixel, effect_color(stroke.color, stroke.opacity, coverage));
+                }
+            }
+            if inner_size > 0.0 {
+                let distance = distance_field(|i| alpha[i] < 0.5, width, height);
+                for ((pixel, d), a) in above.iter_mut().zip(distance).zip(&alpha) {
+                    let coverage = (inner_size + 0.5 - d).clamp(0.0, 1.0) * a;
+                    *pixel = blend_over(*pixel, effect_color(stroke.color, stroke.opacity, coverage));
+                }
+            }
+        }
+
+        below
+            .into_iter()
+            .zip(styled)
+            .zip(above)
+            .map(|((bottom, middle), top)| blend_over(blend_over(bottom, middle), top))
+            .collect()
+    }
+
+    pub fn settings_ui(&mut self, ui: &mut egui::Ui, id: &str, language: Language) -> bool {
+        let mut changed = false;
+
+        let mut shadow_ui = |ui: &mut egui::Ui, shadow: &mut ShadowEffect, key: &str| {
+            changed |= ui.checkbox(&mut shadow.enabled, get_text(key, language)).changed();
+            if shadow.enabled {
+                ui.horizontal(|ui| {
+                    changed |= ui.color_edit_button_srgba_unmultiplied(&mut shadow.color).changed();
+                    changed |= ui.add(egui::Slider::new(&mut shadow.opacity, 0.0..=1.0).text(get_text("style_opacity", language))).changed();
+                });
+                changed |= ui.add(egui::Slider::new(&mut shadow.angle, -180.0..=180.0).text(get_text("style_angle", language))).changed();
+                changed |= ui.add(egui::Slider::new(&mut shadow.distance, 0.0..=50.0).text(get_text("style_distance", language))).changed();
+                changed |= ui.add(egui::Slider::new(&mut shadow.size, 0.0..=50.0).text(get_text("style_size", language))).changed();
+            }
+        };
+        shadow_ui(ui, &mut self.drop_shadow, "drop_shadow");
+        shadow_ui(ui, &mut self.inner_shadow, "inner_shadow");
+
+        let glow = &mut self.outer_glow;
+        changed |= ui.checkbox(&mut glow.enabled, get_text("outer_glow", language)).changed();
+        if glow.enabled {
+            ui.horizontal(|ui| {
+                changed |= ui.color_edit_button_srgba_unmultiplied(&mut glow.color).changed();
+                changed |= ui.add(egui::Slider::new(&mut glow.opacity, 0.0..=1.0).text(get_text("style_opacity", language))).changed();
+            });
+            changed |= ui.add(egui::Slider::new(&mut glow.size, 0.0..=50.0).text(get_text("style_size", language))).changed();
+        }
+
+        let stroke = &mut self.stroke;
+        changed |= ui.checkbox(&mut stroke.enabled, get_text("style_stroke", language)).changed();
+        if stroke.enabled {
+            ui.horizontal(|ui| {
+                changed |= ui.color_edit_button_srgba_unmultiplied(&mut stroke.color).changed();
+                changed |= ui.add(egui::Slider::new(&mut stroke.opacity, 0.0..=1.0).text(get_text("style_opacity", language))).changed();
+            });
+            changed |= ui.add(egui::Slider::new(&mut stroke.size, 1.0..=30.0).text(get_text("style_size", language))).changed();
+            egui::ComboBox::from_id_source(format!("{}_stroke_position", id))
+                .selected_text(stroke.position.get_name(language))
+                .show_ui(ui, |ui| {
+                    for position in StrokePosition::all_positions() {
+                        changed |= ui.selectable_value(&mut stroke.position, position, position.get_name(language)).changed();
+                    }
+                });
+        }
+
+        let overlay = &mut self.color_overlay;
+        changed |= ui.checkbox(&mut overlay.enabled, get_text("color_overlay", language)).changed();
+        if overlay.enabled {
+            ui.horizontal(|ui| {
+                changed |= ui.color_edit_button_srgba_unmultiplied(&mut overlay.color).changed();
+                changed |= ui.add(egui::Slider::new(&mut overlay.opacity, 0.0..=1.0).text(get_text("style_opacity", language))).changed();
+            });
+        }
+
+        let overlay = &mut self.gradient_overlay;
+        changed |= ui.checkbox(&mut overlay.enabled, get_text("gradient_overlay", language)).changed();
+        if overlay.enabled {
+            ui.horizontal(|ui| {
+                for stop in overlay.gradient.stops.iter_mut() {
+                    changed |= ui.color_edit_button_srgba_unmultiplied(&mut stop.color).changed();
+                }
+                changed |= ui.add(egui::Slider::new(&mut overlay.opacity, 0.0..=1.0).text(get_text("style_opacity", language))).changed();
+            });
+            changed |= ui.add(egui::Slider::new(&mut overlay.angle, -180.0..=180.0).text(get_text("style_angle", language))).changed();
+        }
+
+        if changed {
+            self.invalidate();
+        }
+        changed
+    }
+}
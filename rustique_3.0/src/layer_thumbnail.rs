@@ -0,0 +1,117 @@
+use eframe::egui;
+use egui::{Color32, TextureHandle, TextureId, TextureOptions, Vec2};
+
+pub const THUMBNAIL_SIZE: usize = 28;
+const CHECKER_SIZE: usize = 4;
+const MAX_SAMPLES_PER_AXIS: usize = 4;
+
+#[derive(Default)]
+pub struct LayerThumbnail {
+    texture: Option<TextureHandle>,
+    pixels: Vec<Color32>,
+    size: [usize; 2],
+    dirty: Option<(usize, usize, usize, usize)>,
+}
+
+impl Clone for LayerThumbnail {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl PartialEq for LayerThumbnail {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl LayerThumbnail {
+    pub fn mark_dirty(&mut self, x: usize, y: usize) {
+        self.dirty = Some(match self.dirty {
+            Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x + 1), y1.max(y + 1)),
+            None => (x, y, x + 1, y + 1),
+        });
+    }
+
+    pub fn mark_all_dirty(&mut self) {
+        self.texture = None;
+    }
+
+    pub fn texture(
+        &mut self,
+        ctx: &egui::Context,
+        name: &str,
+        width: usize,
+        height: usize,
+        pixel: impl Fn(usize) -> Option<Color32>,
+    ) -> (TextureId, Vec2) {
+        let scale = width.max(height).max(1) as f32 / THUMBNAIL_SIZE as f32;
+        let size = [
+            ((width as f32 / scale).round() as usize).max(1),
+            ((height as f32 / scale).round() as usize).max(1),
+        ];
+        let display_size = Vec2::new(size[0] as f32, size[1] as f32);
+
+        let cells = match (&self.texture, self.dirty) {
+            (Some(_), _) if self.size != size => None,
+            (Some(texture), None) => return (texture.id(), display_size),
+            (Some(_), Some((x0, y0, x1, y1))) => Some((
+                (x0 as f32 / scale).floor() as usize,
+                (y0 as f32 / scale).floor() as usize,
+                ((x1 as f32 / scale).ceil() as usize).min(size[0]),
+                ((y1 as f32 / scale).ceil() as usize).min(size[1]),
+            )),
+            (None, _) => None,
+        };
+
+        if cells.is_none() {
+            self.pixels = vec![Color32::TRANSPARENT; size[0] * size[1]];
+            self.size = size;
+        }
+        let (cx0, cy0, cx1, cy1) = cells.unwrap_or((0, 0, size[0], size[1]));
+
+        for ty in cy0..cy1 {
+            for tx in cx0..cx1 {
+                let source_x0 = ((tx as f32 * scale) as usize).min(width - 1);
+                let source_y0 = ((ty as f32 * scale) as usize).min(height - 1);
+                let source_x1 = (((tx + 1) as f32 * scale) as usize).clamp(source_x0 + 1, width);
+                let source_y1 = (((ty + 1) as f32 * scale) as usize).clamp(source_y0 + 1, height);
+                let step_x = ((source_x1 - source_x0) / MAX_SAMPLES_PER_AXIS).max(1);
+                let step_y = ((source_y1 - source_y0) / MAX_SAMPLES_PER_AXIS).max(1);
+
+                let mut sum = [0.0_f32; 4];
+                let mut count = 0.0;
+                for y in (source_y0..source_y1).step_by(step_y) {
+                    for x in (source_x0..source_x1).step_by(step_x) {
+                        if let Some(color) = pixel(y * width + x) {
+                            sum[0] += color.r() as f32;
+                            sum[1] += color.g() as f32;
+                            sum[2] += color.b() as f32;
+                            sum[3] += color.a() as f32;
+                        }
+                        count += 1.0;
+                    }
+                }
+
+                let checker = if (tx / CHECKER_SIZE + ty / CHECKER_SIZE).is_multiple_of(2) { 200.0 } else { 160.0 };
+                let inverse_alpha = 1.0 - sum[3] / count / 255.0;
+                let channel = |value: f32| (value / count + checker * inverse_alpha).round().clamp(0.0, 255.0) as u8;
+                self.pixels[ty * size[0] + tx] = Color32::from_rgb(channel(sum[0]), channel(sum[1]), channel(sum[2]));
+            }
+        }
+        self.dirty = None;
+
+        let image = egui::ColorImage {
+            size,
+            pixels: self.pixels.clone(),
+        };
+        let texture = match self.texture.as_mut() {
+            Some(texture) => {
+                texture.set(image, TextureOptions::LINEAR);
+                texture
+            },
+            None => self.texture.insert(ctx.load_texture(name, image, TextureOptions::LINEAR)),
+        };
+        (texture.id(), display_size)
+    }
+}
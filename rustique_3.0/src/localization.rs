@@ -101,6 +101,295 @@ fn get_french_text(key: &str) -> String {
         ("select_brush_texture", "Sélectionner une texture de pinceau"),
         ("custom_brushes", "Pinceaux personnalisés"),
         ("delete", "Supprimer"),
+        ("brush_presets", "Préréglages de pinceau"),
+        ("preset_name", "Nom :"),
+        ("preset_category", "Catégorie :"),
+        ("save_as_preset", "Enregistrer le pinceau"),
+        ("import_presets", "Importer"),
+        ("export_presets", "Tout exporter"),
+        ("export_preset", "Exporter"),
+        ("no_presets", "Aucun préréglage enregistré"),
+        ("default_preset_category", "Général"),
+        ("error_reading_brush_file", "Erreur lors de la lecture du fichier de pinceaux"),
+        ("error_writing_brush_file", "Erreur lors de l'écriture du fichier de pinceaux"),
+        ("unsupported_brush_file_version", "Version de fichier de pinceaux non prise en charge"),
+        ("brush_tip", "Pointe du pinceau"),
+        ("procedural_tip", "Pointe procédurale"),
+        ("load_tip_image", "Charger PNG"),
+        ("import_gbr", "Importer .gbr"),
+        ("tip_from_selection", "Depuis la sélection"),
+        ("clear_tip", "Retirer"),
+        ("invalid_brush_tip_size", "Taille de pointe invalide"),
+        ("invalid_gbr_file", "Fichier de pinceau GIMP invalide"),
+        ("no_selection", "Aucune sélection active"),
+        ("selection", "Sélection"),
+        ("select_tool", "Sélection rectangulaire"),
+        ("stabilizer", "Stabilisateur"),
+        ("mode", "Mode"),
+        ("stabilizer_none", "Aucun"),
+        ("stabilizer_average", "Moyenne pondérée"),
+        ("stabilizer_string", "Fil tiré"),
+        ("stabilizer_spline", "Spline Catmull-Rom"),
+        ("stabilizer_window", "Échantillons"),
+        ("stabilizer_radius", "Rayon"),
+        ("catch_up_stroke_end", "Rattraper en fin de trait"),
+        ("brush_dynamics", "Dynamique du pinceau"),
+        ("dynamics_source_random", "Aléatoire"),
+        ("dynamics_source_pressure", "Pression"),
+        ("dynamics_source_velocity", "Vitesse"),
+        ("size_jitter", "Variation de taille"),
+        ("angle_jitter", "Variation d'angle"),
+        ("opacity_jitter", "Variation d'opacité"),
+        ("scatter", "Dispersion"),
+        ("hue_jitter", "Variation de teinte"),
+        ("saturation_jitter", "Variation de saturation"),
+        ("value_jitter", "Variation de luminosité"),
+        ("dab_count", "Nombre d'empreintes"),
+        ("paper_texture", "Grain du papier"),
+        ("texture_scale", "Échelle du grain"),
+        ("dynamics_seed", "Graine"),
+        ("curve_linear", "Linéaire"),
+        ("curve_soft", "Douce"),
+        ("curve_hard", "Dure"),
+        ("curve_s_curve", "Courbe en S"),
+        ("curve_preset", "Préréglage"),
+        ("curve_choose", "Choisir..."),
+        ("size_curve", "Courbe de taille"),
+        ("opacity_curve", "Courbe d'opacité"),
+        ("flow_curve", "Courbe de flux"),
+        ("affects_flow", "Affecte le flux"),
+        ("pressure_source", "Source de pression"),
+        ("pressure_backend_velocity", "Vitesse du curseur"),
+        ("pressure_backend_tablet", "Tablette (evdev)"),
+        ("pressure_backend_replay", "Relecture de fichier"),
+        ("choose_replay_file", "Choisir un enregistrement..."),
+        ("pressure_replay_files", "Enregistrements de pression"),
+        ("no_replay_file_selected", "Aucun fichier d'enregistrement sélectionné"),
+        ("invalid_replay_line", "Ligne d'enregistrement invalide :"),
+        ("empty_replay_file", "Le fichier d'enregistrement est vide"),
+        ("error_writing_replay_file", "Erreur lors de l'écriture de l'enregistrement"),
+        ("no_tablet_found", "Aucune tablette avec capteur de pression trouvée"),
+        ("tablet_permission_denied", "Accès refusé à /dev/input (ajoutez votre utilisateur au groupe input)"),
+        ("tablet_backend_unsupported", "La tablette evdev n'est disponible que sous Linux"),
+        ("tilt", "Inclinaison"),
+        ("record_pressure", "Enregistrer la pression..."),
+        ("stop_pressure_recording", "Arrêter l'enregistrement"),
+        ("smudge_tool", "Estompe"),
+        ("smudge_strength", "Force"),
+        ("finger_painting", "Peinture au doigt"),
+        ("sample", "Échantillonner"),
+        ("sample_active_layer", "Calque actif"),
+        ("sample_all_layers", "Tous les calques"),
+        ("clone_stamp_tool", "Tampon de duplication (Alt+clic : source)"),
+        ("heal_tool", "Correcteur (Alt+clic : source)"),
+        ("clone_aligned", "Aligné"),
+        ("clone_source", "Source"),
+        ("clone_source_hint", "Alt+clic pour définir la source"),
+        ("gradient_tool", "Dégradé"),
+        ("gradient_linear", "Linéaire"),
+        ("gradient_radial", "Radial"),
+        ("gradient_conic", "Conique"),
+        ("gradient_diamond", "Losange"),
+        ("gradient_reflected", "Réfléchi"),
+        ("gradient_shape", "Forme"),
+        ("gradient_preset", "Dégradé"),
+        ("gradient_primary_secondary", "Primaire → secondaire"),
+        ("gradient_primary_transparent", "Primaire → transparent"),
+        ("add_gradient_stop", "Ajouter un point"),
+        ("gradient_dither", "Tramage (évite les bandes)"),
+        ("gradient_name", "Nom du dégradé"),
+        ("save_gradient", "Enregistrer"),
+        ("error_saving_gradients", "Erreur lors de l'enregistrement des dégradés"),
+        ("pen_tool", "Plume (tracés de Bézier)"),
+        ("path", "Tracé"),
+        ("stroke_path", "Contour"),
+        ("fill_path", "Remplir"),
+        ("path_to_selection", "Convertir en sélection"),
+        ("pressure_taper", "Effilement (pression)"),
+        ("pen_tool_hint", "Glisser : courbe, Alt : casser la poignée, Entrée : terminer, Retour : annuler le point"),
+        ("symmetry", "Symétrie"),
+        ("symmetry_off", "Désactivée"),
+        ("symmetry_vertical", "Axe vertical"),
+        ("symmetry_horizontal", "Axe horizontal"),
+        ("symmetry_both", "Deux axes"),
+        ("symmetry_radial", "Radiale"),
+        ("symmetry_kaleidoscope", "Kaléidoscope"),
+        ("symmetry_segments", "Segments"),
+        ("symmetry_show_guides", "Afficher les guides"),
+        ("symmetry_reset_center", "Recentrer"),
+        ("symmetry_center_hint", "Faites glisser le point central sur le canevas pour déplacer le centre"),
+        ("pencil_tool", "Crayon (pixel art)"),
+        ("shade_tool", "Ombrage de palette"),
+        ("pixel_grid", "Grille de pixels"),
+        ("pixel_grid_threshold", "Taille minimale d'un pixel à l'écran pour afficher la grille"),
+        ("tile_grid", "Grille de tuiles"),
+        ("pencil_size", "Taille du crayon"),
+        ("pixel_perfect", "Pixel parfait"),
+        ("shade_dither", "Tramage"),
+        ("shade_tool_hint", "Clic gauche : couleur suivante de la palette, clic droit : précédente"),
+        ("wrap_mode", "Mode raccord"),
+        ("wrap_mode_hint", "Les traits qui dépassent un bord continuent du côté opposé"),
+        ("tiled_view", "Vue en mosaïque 3×3"),
+        ("group", "Groupe"),
+        ("new_group", "Grouper le calque actif"),
+        ("ungroup", "Dissocier le groupe"),
+        ("opacity", "Opacité"),
+        ("group_pass_through", "Transfert"),
+        ("group_isolated", "Isolé"),
+        ("add_mask", "Ajouter un masque"),
+        ("edit_mask", "Masque"),
+        ("mask_enabled", "Activer le masque"),
+        ("invert_mask", "Inverser le masque"),
+        ("apply_mask", "Appliquer le masque"),
+        ("delete_mask", "Supprimer le masque"),
+        ("clipping_mask", "Écrêter au calque inférieur"),
+        ("layer_copy_suffix", "copie"),
+        ("no_layer_below", "Aucun calque en dessous à fusionner"),
+        ("merge_across_groups", "Impossible de fusionner des calques de groupes différents"),
+        ("layer_locked", "Le calque est verrouillé"),
+        ("not_enough_visible_layers", "Il faut au moins deux calques visibles à fusionner"),
+        ("merged_layer", "Fusionné"),
+        ("background_color_layer", "Couleur d'arrière-plan"),
+        ("duplicate_layer", "Dupliquer le calque"),
+        ("merge_down", "Fusionner vers le bas"),
+        ("merge_visible", "Fusionner les calques visibles"),
+        ("flatten_image", "Aplatir l'image"),
+        ("layer_from_background", "Nouveau calque de couleur d'arrière-plan"),
+        ("lock", "Verrou :"),
+        ("lock_pixels", "Verrouiller les pixels"),
+        ("lock_alpha", "Préserver la transparence"),
+        ("lock_position", "Verrouiller la position"),
+        ("adjustment_levels", "Niveaux"),
+        ("adjustment_curves", "Courbes"),
+        ("adjustment_hue_saturation", "Teinte/Saturation"),
+        ("adjustment_color_balance", "Balance des couleurs"),
+        ("adjustment_invert", "Négatif"),
+        ("adjustment_threshold", "Seuil"),
+        ("adjustment_gradient_map", "Courbe de transfert de dégradé"),
+        ("new_adjustment_layer", "Nouveau calque de réglage"),
+        ("merge_into_adjustment", "Impossible de fusionner dans un calque de réglage"),
+        ("levels_input_black", "Noir (entrée)"),
+        ("levels_input_white", "Blanc (entrée)"),
+        ("levels_gamma", "Gamma"),
+        ("levels_output_black", "Noir (sortie)"),
+        ("levels_output_white", "Blanc (sortie)"),
+        ("hue", "Teinte"),
+        ("saturation", "Saturation"),
+        ("lightness", "Luminosité"),
+        ("balance_shadows", "Tons foncés"),
+        ("balance_midtones", "Tons moyens"),
+        ("balance_highlights", "Tons clairs"),
+        ("balance_cyan_red", "Cyan / Rouge"),
+        ("balance_magenta_green", "Magenta / Vert"),
+        ("balance_yellow_blue", "Jaune / Bleu"),
+        ("preserve_luminosity", "Préserver la luminosité"),
+        ("threshold_level", "Niveau"),
+        ("reverse_gradient", "Inverser le dégradé"),
+        ("layer_style", "Style de calque"),
+        ("drop_shadow", "Ombre portée"),
+        ("inner_shadow", "Ombre interne"),
+        ("outer_glow", "Lueur externe"),
+        ("style_stroke", "Contour"),
+        ("color_overlay", "Incrustation de couleur"),
+        ("gradient_overlay", "Incrustation de dégradé"),
+        ("style_opacity", "Opacité"),
+        ("style_angle", "Angle"),
+        ("style_distance", "Distance"),
+        ("style_size", "Taille"),
+        ("stroke_inside", "Intérieur"),
+        ("stroke_center", "Centre"),
+        ("stroke_outside", "Extérieur"),
+        ("swap_colors", "Échanger les couleurs (X)"),
+        ("recent_colors", "Couleurs récentes"),
+        ("red", "Rouge"),
+        ("green", "Vert"),
+        ("blue", "Bleu"),
+        ("lab_lightness", "L*"),
+        ("lab_a", "a*"),
+        ("lab_b", "b*"),
+        ("alpha", "Alpha"),
+        ("hex_color", "Hex"),
+        ("hex_color_hint", "#RRGGBB, #RRGGBBAA, rgb(), rgba(), hsl() ou hsla()"),
+        ("palettes", "Palettes"),
+        ("palette", "Palette"),
+        ("new_palette", "Nouvelle palette"),
+        ("delete_palette", "Supprimer la palette"),
+        ("palette_name", "Nom de la palette"),
+        ("add_color_to_palette", "Ajouter la couleur active"),
+        ("import_palette", "Importer…"),
+        ("export_palette", "Exporter…"),
+        ("extract_palette", "Extraire une palette"),
+        ("palette_size", "Couleurs"),
+        ("extraction_median_cut", "Coupe médiane"),
+        ("extraction_kmeans", "K-moyennes"),
+        ("extract_from_image", "Extraire de l'image"),
+        ("extracted_palette", "Palette extraite"),
+        ("nothing_to_extract", "L'image ne contient aucun pixel opaque"),
+        ("palette_format_not_supported", "Format de palette non pris en charge"),
+        ("error_loading_palette", "Erreur lors du chargement de la palette"),
+        ("error_saving_palette", "Erreur lors de l'enregistrement de la palette"),
+        ("invalid_palette_file", "Fichier de palette invalide"),
+        ("color_mode", "Mode de couleur"),
+        ("indexed_source_palette", "Palette active"),
+        ("indexed_source_extracted", "Extraite de l'image"),
+        ("convert_to_indexed", "Convertir en couleurs indexées"),
+        ("convert_to_rgba", "Convertir en RVBA"),
+        ("indexed_colors", "Couleurs indexées"),
+        ("indexed_entry_hint", "Clic : modifier | Droit : principale | Milieu : secondaire"),
+        ("no_active_palette", "Aucune palette active"),
+        ("empty_indexed_palette", "La palette ne contient aucune couleur"),
+        ("image_too_large_for_gif", "L'image est trop grande pour le format GIF"),
+        ("quantizer_octree", "Octree"),
+        ("quantizer_neuquant", "NeuQuant"),
+        ("dithering_none", "Aucun"),
+        ("dithering_floyd_steinberg", "Floyd-Steinberg"),
+        ("dithering_atkinson", "Atkinson"),
+        ("dithering_bayer", "Bayer ordonné"),
+        ("export_document_palette", "La palette du document sera utilisée telle quelle"),
+        ("reduce_colors", "Réduire les couleurs"),
+        ("quantizer", "Quantificateur"),
+        ("dithering", "Tramage"),
+        ("matte_color", "Couleur de fond (matte)"),
+        ("export_options", "Options d'exportation"),
+        ("export", "Exporter"),
+        ("compression_fast", "Rapide"),
+        ("compression_default", "Normale"),
+        ("compression_best", "Maximale"),
+        ("compression_none", "Aucune"),
+        ("webp_mode", "Mode WebP"),
+        ("webp_lossless", "Sans perte"),
+        ("webp_lossy", "Avec perte"),
+        ("webp_quality", "Qualité"),
+        ("jpeg_quality", "Qualité JPEG"),
+        ("png_compression", "Compression PNG"),
+        ("tiff_compression", "Compression TIFF"),
+        ("embed_dpi", "Inclure la résolution"),
+        ("embed_metadata", "Inclure les métadonnées"),
+        ("metadata_author", "Auteur"),
+        ("error_saving_export_settings", "Erreur lors de l'enregistrement des options d'exportation"),
+        ("estimated_file_size", "Taille estimée"),
+        ("export_preview", "Aperçu (centre, 200 %)"),
+        ("animated_gif", "GIF animé"),
+        ("animated_png", "PNG animé (APNG)"),
+        ("png_sequence", "Séquence PNG"),
+        ("play_animation", "Lire l'animation"),
+        ("pause_animation", "Mettre en pause"),
+        ("previous_frame", "Image précédente"),
+        ("next_frame", "Image suivante"),
+        ("add_frame", "Nouvelle image"),
+        ("duplicate_frame", "Dupliquer l'image"),
+        ("delete_frame", "Supprimer l'image"),
+        ("move_frame_left", "Déplacer l'image vers la gauche"),
+        ("move_frame_right", "Déplacer l'image vers la droite"),
+        ("frame_duration", "Durée de l'image"),
+        ("onion_skin", "Pelure d'oignon"),
+        ("onion_previous_frames", "Images précédentes affichées"),
+        ("onion_next_frames", "Images suivantes affichées"),
+        ("export_animation", "Exporter l'animation"),
+        ("reset_brush", "Réinitialiser le pinceau"),
+        ("merge_hidden_layer", "Impossible de fusionner un calque masqué"),
+        ("background_layer", "Arrière-plan"),
+        ("palette_too_large_for_aco", "Trop de couleurs pour le format ACO (65535 au maximum)"),
     ].iter().cloned().collect();
     
     translations.get(key).unwrap_or(&key).to_string()
@@ -193,6 +482,295 @@ fn get_english_text(key: &str) -> String {
         ("select_brush_texture", "Select brush texture"),
         ("custom_brushes", "Custom Brushes"),
         ("delete", "Delete"),
+        ("brush_presets", "Brush Presets"),
+        ("preset_name", "Name:"),
+        ("preset_category", "Category:"),
+        ("save_as_preset", "Save Current Brush"),
+        ("import_presets", "Import"),
+        ("export_presets", "Export All"),
+        ("export_preset", "Export"),
+        ("no_presets", "No saved presets"),
+        ("default_preset_category", "General"),
+        ("error_reading_brush_file", "Error reading brush file"),
+        ("error_writing_brush_file", "Error writing brush file"),
+        ("unsupported_brush_file_version", "Unsupported brush file version"),
+        ("brush_tip", "Brush Tip"),
+        ("procedural_tip", "Procedural tip"),
+        ("load_tip_image", "Load PNG"),
+        ("import_gbr", "Import .gbr"),
+        ("tip_from_selection", "From Selection"),
+        ("clear_tip", "Clear"),
+        ("invalid_brush_tip_size", "Invalid brush tip size"),
+        ("invalid_gbr_file", "Invalid GIMP brush file"),
+        ("no_selection", "No active selection"),
+        ("selection", "Selection"),
+        ("select_tool", "Rectangle Select"),
+        ("stabilizer", "Stabilizer"),
+        ("mode", "Mode"),
+        ("stabilizer_none", "None"),
+        ("stabilizer_average", "Moving Average"),
+        ("stabilizer_string", "Pulled String"),
+        ("stabilizer_spline", "Catmull-Rom Spline"),
+        ("stabilizer_window", "Samples"),
+        ("stabilizer_radius", "Radius"),
+        ("catch_up_stroke_end", "Catch up at stroke end"),
+        ("brush_dynamics", "Brush dynamics"),
+        ("dynamics_source_random", "Random"),
+        ("dynamics_source_pressure", "Pressure"),
+        ("dynamics_source_velocity", "Velocity"),
+        ("size_jitter", "Size jitter"),
+        ("angle_jitter", "Angle jitter"),
+        ("opacity_jitter", "Opacity jitter"),
+        ("scatter", "Scatter"),
+        ("hue_jitter", "Hue jitter"),
+        ("saturation_jitter", "Saturation jitter"),
+        ("value_jitter", "Value jitter"),
+        ("dab_count", "Dab count"),
+        ("paper_texture", "Paper grain"),
+        ("texture_scale", "Grain scale"),
+        ("dynamics_seed", "Seed"),
+        ("curve_linear", "Linear"),
+        ("curve_soft", "Soft"),
+        ("curve_hard", "Hard"),
+        ("curve_s_curve", "S-curve"),
+        ("curve_preset", "Preset"),
+        ("curve_choose", "Choose..."),
+        ("size_curve", "Size curve"),
+        ("opacity_curve", "Opacity curve"),
+        ("flow_curve", "Flow curve"),
+        ("affects_flow", "Affects flow"),
+        ("pressure_source", "Pressure source"),
+        ("pressure_backend_velocity", "Cursor velocity"),
+        ("pressure_backend_tablet", "Tablet (evdev)"),
+        ("pressure_backend_replay", "File replay"),
+        ("choose_replay_file", "Choose recording..."),
+        ("pressure_replay_files", "Pressure recordings"),
+        ("no_replay_file_selected", "No recording file selected"),
+        ("invalid_replay_line", "Invalid recording line:"),
+        ("empty_replay_file", "The recording file is empty"),
+        ("error_writing_replay_file", "Error writing recording"),
+        ("no_tablet_found", "No tablet with a pressure sensor found"),
+        ("tablet_permission_denied", "Access to /dev/input denied (add your user to the input group)"),
+        ("tablet_backend_unsupported", "The evdev tablet backend is only available on Linux"),
+        ("tilt", "Tilt"),
+        ("record_pressure", "Record pressure..."),
+        ("stop_pressure_recording", "Stop recording"),
+        ("smudge_tool", "Smudge"),
+        ("smudge_strength", "Strength"),
+        ("finger_painting", "Finger painting"),
+        ("sample", "Sample"),
+        ("sample_active_layer", "Active layer"),
+        ("sample_all_layers", "All layers"),
+        ("clone_stamp_tool", "Clone stamp (Alt+click: source)"),
+        ("heal_tool", "Healing brush (Alt+click: source)"),
+        ("clone_aligned", "Aligned"),
+        ("clone_source", "Source"),
+        ("clone_source_hint", "Alt+click to set the source"),
+        ("gradient_tool", "Gradient"),
+        ("gradient_linear", "Linear"),
+        ("gradient_radial", "Radial"),
+        ("gradient_conic", "Conic"),
+        ("gradient_diamond", "Diamond"),
+        ("gradient_reflected", "Reflected"),
+        ("gradient_shape", "Shape"),
+        ("gradient_preset", "Gradient"),
+        ("gradient_primary_secondary", "Primary → secondary"),
+        ("gradient_primary_transparent", "Primary → transparent"),
+        ("add_gradient_stop", "Add stop"),
+        ("gradient_dither", "Dither (avoids banding)"),
+        ("gradient_name", "Gradient name"),
+        ("save_gradient", "Save"),
+        ("error_saving_gradients", "Error saving gradients"),
+        ("pen_tool", "Pen (Bezier paths)"),
+        ("path", "Path"),
+        ("stroke_path", "Stroke"),
+        ("fill_path", "Fill"),
+        ("path_to_selection", "Make selection"),
+        ("pressure_taper", "Pressure taper"),
+        ("pen_tool_hint", "Drag: curve, Alt: break handle, Enter: finish, Backspace: undo point"),
+        ("symmetry", "Symmetry"),
+        ("symmetry_off", "Off"),
+        ("symmetry_vertical", "Vertical axis"),
+        ("symmetry_horizontal", "Horizontal axis"),
+        ("symmetry_both", "Both axes"),
+        ("symmetry_radial", "Radial"),
+        ("symmetry_kaleidoscope", "Kaleidoscope"),
+        ("symmetry_segments", "Segments"),
+        ("symmetry_show_guides", "Show guides"),
+        ("symmetry_reset_center", "Reset center"),
+        ("symmetry_center_hint", "Drag the center point on the canvas to move it"),
+        ("pencil_tool", "Pencil (pixel art)"),
+        ("shade_tool", "Palette shading"),
+        ("pixel_grid", "Pixel grid"),
+        ("pixel_grid_threshold", "Minimum on-screen pixel size before the grid appears"),
+        ("tile_grid", "Tile grid"),
+        ("pencil_size", "Pencil size"),
+        ("pixel_perfect", "Pixel-perfect"),
+        ("shade_dither", "Dither"),
+        ("shade_tool_hint", "Left click: next palette color, right click: previous"),
+        ("wrap_mode", "Wrap mode"),
+        ("wrap_mode_hint", "Strokes that cross an edge continue on the opposite side"),
+        ("tiled_view", "3×3 tiled view"),
+        ("group", "Group"),
+        ("new_group", "Group active layer"),
+        ("ungroup", "Ungroup"),
+        ("opacity", "Opacity"),
+        ("group_pass_through", "Pass through"),
+        ("group_isolated", "Isolated"),
+        ("add_mask", "Add mask"),
+        ("edit_mask", "Mask"),
+        ("mask_enabled", "Enable mask"),
+        ("invert_mask", "Invert mask"),
+        ("apply_mask", "Apply mask"),
+        ("delete_mask", "Delete mask"),
+        ("clipping_mask", "Clip to layer below"),
+        ("layer_copy_suffix", "copy"),
+        ("no_layer_below", "There is no layer below to merge into"),
+        ("merge_across_groups", "Cannot merge layers from different groups"),
+        ("layer_locked", "The layer is locked"),
+        ("not_enough_visible_layers", "At least two visible layers are needed to merge"),
+        ("merged_layer", "Merged"),
+        ("background_color_layer", "Background color"),
+        ("duplicate_layer", "Duplicate layer"),
+        ("merge_down", "Merge down"),
+        ("merge_visible", "Merge visible"),
+        ("flatten_image", "Flatten image"),
+        ("layer_from_background", "Layer from background color"),
+        ("lock", "Lock:"),
+        ("lock_pixels", "Lock pixels"),
+        ("lock_alpha", "Preserve transparency"),
+        ("lock_position", "Lock position"),
+        ("adjustment_levels", "Levels"),
+        ("adjustment_curves", "Curves"),
+        ("adjustment_hue_saturation", "Hue/Saturation"),
+        ("adjustment_color_balance", "Color balance"),
+        ("adjustment_invert", "Invert"),
+        ("adjustment_threshold", "Threshold"),
+        ("adjustment_gradient_map", "Gradient map"),
+        ("new_adjustment_layer", "New adjustment layer"),
+        ("merge_into_adjustment", "Cannot merge into an adjustment layer"),
+        ("levels_input_black", "Input black"),
+        ("levels_input_white", "Input white"),
+        ("levels_gamma", "Gamma"),
+        ("levels_output_black", "Output black"),
+        ("levels_output_white", "Output white"),
+        ("hue", "Hue"),
+        ("saturation", "Saturation"),
+        ("lightness", "Lightness"),
+        ("balance_shadows", "Shadows"),
+        ("balance_midtones", "Midtones"),
+        ("balance_highlights", "Highlights"),
+        ("balance_cyan_red", "Cyan / Red"),
+        ("balance_magenta_green", "Magenta / Green"),
+        ("balance_yellow_blue", "Yellow / Blue"),
+        ("preserve_luminosity", "Preserve luminosity"),
+        ("threshold_level", "Level"),
+        ("reverse_gradient", "Reverse gradient"),
+        ("layer_style", "Layer style"),
+        ("drop_shadow", "Drop shadow"),
+        ("inner_shadow", "Inner shadow"),
+        ("outer_glow", "Outer glow"),
+        ("style_stroke", "Stroke"),
+        ("color_overlay", "Color overlay"),
+        ("gradient_overlay", "Gradient overlay"),
+        ("style_opacity", "Opacity"),
+        ("style_angle", "Angle"),
+        ("style_distance", "Distance"),
+        ("style_size", "Size"),
+        ("stroke_inside", "Inside"),
+        ("stroke_center", "Center"),
+        ("stroke_outside", "Outside"),
+        ("swap_colors", "Swap colors (X)"),
+        ("recent_colors", "Recent colors"),
+        ("red", "Red"),
+        ("green", "Green"),
+        ("blue", "Blue"),
+        ("lab_lightness", "L*"),
+        ("lab_a", "a*"),
+        ("lab_b", "b*"),
+        ("alpha", "Alpha"),
+        ("hex_color", "Hex"),
+        ("hex_color_hint", "#RRGGBB, #RRGGBBAA, rgb(), rgba(), hsl() or hsla()"),
+        ("palettes", "Palettes"),
+        ("palette", "Palette"),
+        ("new_palette", "New palette"),
+        ("delete_palette", "Delete palette"),
+        ("palette_name", "Palette name"),
+        ("add_color_to_palette", "Add active color"),
+        ("import_palette", "Import…"),
+        ("export_palette", "Export…"),
+        ("extract_palette", "Extract palette"),
+        ("palette_size", "Colors"),
+        ("extraction_median_cut", "Median cut"),
+        ("extraction_kmeans", "K-means"),
+        ("extract_from_image", "Extract from image"),
+        ("extracted_palette", "Extracted palette"),
+        ("nothing_to_extract", "The image has no opaque pixels"),
+        ("palette_format_not_supported", "Palette format not supported"),
+        ("error_loading_palette", "Error loading palette"),
+        ("error_saving_palette", "Error saving palette"),
+        ("invalid_palette_file", "Invalid palette file"),
+        ("color_mode", "Color mode"),
+        ("indexed_source_palette", "Active palette"),
+        ("indexed_source_extracted", "Extracted from image"),
+        ("convert_to_indexed", "Convert to indexed"),
+        ("convert_to_rgba", "Convert to RGBA"),
+        ("indexed_colors", "Indexed colors"),
+        ("indexed_entry_hint", "Click: edit | Right: primary | Middle: secondary"),
+        ("no_active_palette", "No active palette"),
+        ("empty_indexed_palette", "The palette has no colors"),
+        ("image_too_large_for_gif", "The image is too large for GIF"),
+        ("quantizer_octree", "Octree"),
+        ("quantizer_neuquant", "NeuQuant"),
+        ("dithering_none", "None"),
+        ("dithering_floyd_steinberg", "Floyd-Steinberg"),
+        ("dithering_atkinson", "Atkinson"),
+        ("dithering_bayer", "Ordered Bayer"),
+        ("export_document_palette", "The document palette is written as is"),
+        ("reduce_colors", "Reduce colors"),
+        ("quantizer", "Quantizer"),
+        ("dithering", "Dithering"),
+        ("matte_color", "Matte color"),
+        ("export_options", "Export options"),
+        ("export", "Export"),
+        ("compression_fast", "Fast"),
+        ("compression_default", "Default"),
+        ("compression_best", "Best"),
+        ("compression_none", "None"),
+        ("webp_mode", "WebP mode"),
+        ("webp_lossless", "Lossless"),
+        ("webp_lossy", "Lossy"),
+        ("webp_quality", "Quality"),
+        ("jpeg_quality", "JPEG quality"),
+        ("png_compression", "PNG compression"),
+        ("tiff_compression", "TIFF compression"),
+        ("embed_dpi", "Embed resolution"),
+        ("embed_metadata", "Embed metadata"),
+        ("metadata_author", "Author"),
+        ("error_saving_export_settings", "Error saving export settings"),
+        ("estimated_file_size", "Estimated size"),
+        ("export_preview", "Preview (center, 200%)"),
+        ("animated_gif", "Animated GIF"),
+        ("animated_png", "Animated PNG (APNG)"),
+        ("png_sequence", "PNG sequence"),
+        ("play_animation", "Play animation"),
+        ("pause_animation", "Pause animation"),
+        ("previous_frame", "Previous frame"),
+        ("next_frame", "Next frame"),
+        ("add_frame", "New frame"),
+        ("duplicate_frame", "Duplicate frame"),
+        ("delete_frame", "Delete frame"),
+        ("move_frame_left", "Move frame left"),
+        ("move_frame_right", "Move frame right"),
+        ("frame_duration", "Frame duration"),
+        ("onion_skin", "Onion skin"),
+        ("onion_previous_frames", "Previous frames shown"),
+        ("onion_next_frames", "Next frames shown"),
+        ("export_animation", "Export animation"),
+        ("reset_brush", "Reset brush"),
+        ("merge_hidden_layer", "Cannot merge a hidden layer"),
+        ("background_layer", "Background"),
+        ("palette_too_large_for_aco", "Too many colors for the ACO format (65535 maximum)"),
     ].iter().cloned().collect();
     
     translations.get(key).unwrap_or(&key).to_string()
@@ -3,14 +3,33 @@ mod localization;
 mod brush_system;
 mod ui_theme;
 mod ui_icons;
+mod selection;
+mod color_space;
+mod pressure_input;
+mod gradient;
+mod vector_path;
+mod symmetry;
+mod pixel_art;
+mod layer_group;
+mod layer_mask;
+mod adjustment;
+mod layer_style;
+mod layer_thumbnail;
+mod color_panel;
+mod palette;
+mod indexed_color;
+mod quantize;
+mod export_options;
+mod animation;
+mod compositing;
 
 use eframe::egui;
 use egui::{Color32, TextureHandle, TextureOptions, Rect, Pos2, Vec2, Stroke, RichText};
-use image::{ImageBuffer, Rgba, ImageFormat};
-use std::collections::VecDeque;
+use image::ImageFormat;
+use std::collections::{HashMap, VecDeque};
 use rfd::FileDialog;
 use std::time::Instant;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
 use std::io::Write;
 use serde::{Serialize, Deserialize};
@@ -18,8 +37,28 @@ use serde::{Serialize, Deserialize};
 use main_menu::MainMenu;
 use localization::{Language, get_text};
 use brush_system::BrushManager;
+use brush_system::stabilizer::{Stabilizer, StabilizerMode};
+use brush_system::curves::curve_editor;
+use pressure_input::{PressureInput, PressureBackend};
+use gradient::GradientTool;
+use vector_path::{PenTool, VectorPath};
+use symmetry::{Symmetry, SymmetryMode};
+use pixel_art::{PixelArt, bresenham};
+use layer_group::{GroupBlend, LayerGroup, LayerNode, LayerRow, build_tree};
+use layer_mask::LayerMask;
+use adjustment::Adjustment;
+use layer_style::LayerStyle;
+use layer_thumbnail::LayerThumbnail;
+use color_panel::ColorPanel;
+use palette::{PaletteAction, PalettePanel, ExtractionMethod, extract_palette};
+use indexed_color::{ColorMode, IndexedAction, IndexedPalette, IndexedPanel, IndexedSource};
+use export_options::{ExportPreview, ExportSettings};
+use compositing::{blend_over, from_premultiplied, mix_premultiplied, to_premultiplied};
+use animation::{AnimationExport, AnimationPanel, TimelineAction, DEFAULT_FRAME_DURATION, MAX_FRAME_DURATION, MIN_FRAME_DURATION};
 use ui_theme::RustiqueTheme;
 use ui_icons::ToolIcons;
+use selection::Selection;
+use brush_system::tip::BrushTip;
 
 const MAX_UNDO_STEPS: usize = 20;
 const CHECKERBOARD_SIZE: usize = 8;
@@ -34,6 +73,33 @@ enum Tool {
     PaintBucket,
     ColorPicker,
     Line,
+    Select,
+    Smudge,
+    CloneStamp,
+    Heal,
+    Gradient,
+    Pen,
+    Pencil,
+    Shade,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SampleSource {
+    ActiveLayer,
+    AllLayers,
+}
+
+impl SampleSource {
+    fn get_name(&self, language: Language) -> String {
+        match self {
+            SampleSource::ActiveLayer => get_text("sample_active_layer", language),
+            SampleSource::AllLayers => get_text("sample_all_layers", language),
+        }
+    }
+    
+    fn all_sources() -> Vec<SampleSource> {
+        vec![SampleSource::ActiveLayer, SampleSource::AllLayers]
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -75,6 +141,40 @@ impl FileFormat {
     }
 }
 
+fn mean_rgb(colors: impl Iterator<Item = [f32; 4]>) -> [f32; 3] {
+    let (sum, count) = colors.fold(([0.0; 3], 0), |(sum, count), color| {
+        ([sum[0] + color[0], sum[1] + color[1], sum[2] + color[2]], count + 1)
+    });
+    let count = count.max(1) as f32;
+    [sum[0] / count, sum[1] / count, sum[2] / count]
+}
+
+fn boundary_ring(mask: &[f32], size: usize) -> Vec<bool> {
+    let covered = |x: i32, y: i32| {
+        x >= 0 && y >= 0 && (x as usize) < size && (y as usize) < size && mask[y as usize * size + x as usize] > 0.0
+    };
+    (0..mask.len())
+        .map(|i| {
+            let (x, y) = ((i % size) as i32, (i / size) as i32);
+            covered(x, y) && !(covered(x - 1, y) && covered(x + 1, y) && covered(x, y - 1) && covered(x, y + 1))
+        })
+        .collect()
+}
+
+fn resize_smudge_buffer(buffer: Vec<[f32; 4]>, size: usize) -> Vec<[f32; 4]> {
+    let old_size = (buffer.len() as f32).sqrt() as usize;
+    if old_size == size || old_size == 0 {
+        return buffer;
+    }
+    (0..size * size)
+        .map(|i| {
+            let sx = (i % size) * old_size / size;
+            let sy = (i / size) * old_size / size;
+            buffer[sy * old_size + sx]
+        })
+        .collect()
+}
+
 enum AppState {
     MainMenu(MainMenu),
     Canvas(PaintApp),
@@ -84,14 +184,107 @@ enum AppState {
 struct Layer {
     name: String,
     data: Vec<Option<Color32>>,
+    indices: Option<Vec<Option<u8>>>,
     visible: bool,
+    group: Option<u32>,
+    mask: Option<LayerMask>,
+    clipped: bool,
+    lock_pixels: bool,
+    lock_alpha: bool,
+    lock_position: bool,
+    adjustment: Option<Adjustment>,
+    style: LayerStyle,
+    opacity: f32,
+    thumbnail: LayerThumbnail,
 }
 
 #[derive(Serialize, Deserialize)]
 struct LayerData {
     name: String,
     data: Vec<Option<[u8; 4]>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    indices: Option<Vec<Option<u8>>>,
     visible: bool,
+    #[serde(default)]
+    group: Option<u32>,
+    #[serde(default)]
+    mask: Option<LayerMask>,
+    #[serde(default)]
+    clipped: bool,
+    #[serde(default)]
+    lock_pixels: bool,
+    #[serde(default)]
+    lock_alpha: bool,
+    #[serde(default)]
+    lock_position: bool,
+    #[serde(default)]
+    adjustment: Option<Adjustment>,
+    #[serde(default)]
+    style: LayerStyle,
+    #[serde(default = "full_opacity")]
+    opacity: f32,
+}
+
+fn full_opacity() -> f32 {
+    1.0
+}
+
+impl From<LayerData> for Layer {
+    fn from(layer_data: LayerData) -> Self {
+        Layer {
+            name: layer_data.name,
+            data: layer_data.data
+                .into_iter()
+                .map(|pixel| pixel.map(|rgba| Color32::from_rgba_unmultiplied(rgba[0], rgba[1], rgba[2], rgba[3])))
+                .collect(),
+            indices: layer_data.indices,
+            visible: layer_data.visible,
+            group: layer_data.group,
+            mask: layer_data.mask,
+            clipped: layer_data.clipped,
+            lock_pixels: layer_data.lock_pixels,
+            lock_alpha: layer_data.lock_alpha,
+            lock_position: layer_data.lock_position,
+            adjustment: layer_data.adjustment,
+            style: layer_data.style,
+            opacity: layer_data.opacity,
+            thumbnail: LayerThumbnail::default(),
+        }
+    }
+}
+
+impl From<&Layer> for LayerData {
+    fn from(layer: &Layer) -> Self {
+        LayerData {
+            name: layer.name.clone(),
+            data: layer.data
+                .iter()
+                .map(|pixel| pixel.map(|color| [color.r(), color.g(), color.b(), color.a()]))
+                .collect(),
+            indices: layer.indices.clone(),
+            visible: layer.visible,
+            group: layer.group,
+            mask: layer.mask.clone(),
+            clipped: layer.clipped,
+            lock_pixels: layer.lock_pixels,
+            lock_alpha: layer.lock_alpha,
+            lock_position: layer.lock_position,
+            adjustment: layer.adjustment.clone(),
+            style: layer.style.clone(),
+            opacity: layer.opacity,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct FrameData {
+    #[serde(default)]
+    layers: Vec<LayerData>,
+    #[serde(default)]
+    groups: Vec<LayerGroup>,
+    #[serde(default)]
+    active_layer_index: usize,
+    duration_ms: u32,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -105,6 +298,35 @@ struct RustiqueFile {
     saved_colors: Vec<[u8; 4]>,
     brush_size: i32,
     eraser_size: i32,
+    #[serde(default)]
+    paths: Vec<VectorPath>,
+    #[serde(default)]
+    groups: Vec<LayerGroup>,
+    #[serde(default)]
+    color_mode: ColorMode,
+    #[serde(default)]
+    frames: Vec<FrameData>,
+    #[serde(default)]
+    current_frame: usize,
+}
+
+#[derive(Clone)]
+struct AnimationFrame {
+    layers: Vec<Layer>,
+    groups: Vec<LayerGroup>,
+    active_layer_index: usize,
+    duration_ms: u32,
+}
+
+impl AnimationFrame {
+    fn new(duration_ms: u32) -> Self {
+        Self {
+            layers: Vec::new(),
+            groups: Vec::new(),
+            active_layer_index: 0,
+            duration_ms,
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -112,7 +334,12 @@ struct CanvasState {
     width: usize,
     height: usize,
     layers: Vec<Layer>,
+    groups: Vec<LayerGroup>,
     active_layer_index: usize,
+    editing_mask: bool,
+    color_mode: ColorMode,
+    frames: Vec<AnimationFrame>,
+    current_frame: usize,
 }
 
 impl CanvasState {
@@ -120,50 +347,504 @@ impl CanvasState {
         let default_layer = Layer {
             name: "Background".to_string(),
             data: vec![None; width * height],
+            indices: None,
             visible: true,
+            group: None,
+            mask: None,
+            clipped: false,
+            lock_pixels: false,
+            lock_alpha: false,
+            lock_position: false,
+            adjustment: None,
+            style: LayerStyle::default(),
+            opacity: 1.0,
+            thumbnail: LayerThumbnail::default(),
         };
         
         Self {
             width,
             height,
             layers: vec![default_layer],
+            groups: Vec::new(),
             active_layer_index: 0,
+            editing_mask: false,
+            color_mode: ColorMode::Rgba,
+            frames: vec![AnimationFrame::new(DEFAULT_FRAME_DURATION)],
+            current_frame: 0,
         }
     }
     
-    #[inline]
-    fn get(&self, x: usize, y: usize) -> Option<Color32> {
-        if x < self.width && y < self.height {
-            for layer_index in (0..self.layers.len()).rev() {
-                let layer = &self.layers[layer_index];
-                if layer.visible {
-                    let idx = y * self.width + x;
-                    if let Some(color) = layer.data[idx] {
-                        return Some(color);
+    fn layer_tree(&self) -> Vec<LayerNode> {
+        build_tree(self.layers.iter().map(|layer| layer.group), &self.groups)
+    }
+    
+    fn composite(&self) -> Vec<[f32; 4]> {
+        self.composite_region((0, 0, self.width, self.height))
+    }
+    
+    fn composite_rgba(&self) -> Vec<[u8; 4]> {
+        self.composite()
+            .into_iter()
+            .map(|pixel| from_premultiplied(pixel).unwrap_or(Color32::TRANSPARENT).to_srgba_unmultiplied())
+            .collect()
+    }
+    
+    fn composite_region(&self, region: (usize, usize, usize, usize)) -> Vec<[f32; 4]> {
+        let (x0, y0, x1, y1) = region;
+        let mut buffer = vec![[0.0; 4]; (x1 - x0) * (y1 - y0)];
+        self.composite_nodes(&self.layer_tree(), &mut buffer, region);
+        buffer
+    }
+    
+    fn composite_nodes(&self, nodes: &[LayerNode], buffer: &mut [[f32; 4]], region: (usize, usize, usize, usize)) {
+        let (x0, y0, x1, _) = region;
+        let region_width = x1 - x0;
+        
+        let mut clip_base: Option<Vec<f32>> = None;
+        
+        for node in nodes {
+            match node {
+                LayerNode::Layer(index) => {
+                    let layer = &self.layers[*index];
+                    let clip = match (layer.clipped, clip_base.as_deref()) {
+                        (false, _) => None,
+                        (true, Some(base)) => Some(base),
+                        (true, None) => continue,
+                    };
+                    
+                    if let Some(adjustment) = layer.adjustment.as_ref() {
+                        if !layer.visible {
+                            continue;
+                        }
+                        for (i, pixel) in buffer.iter_mut().enumerate() {
+                            let idx = (y0 + i / region_width) * self.width + x0 + i % region_width;
+                            let mut factor = layer.mask.as_ref().map_or(1.0, |mask| mask.factor(idx)) * layer.opacity;
+                            if let Some(base) = clip {
+                                factor *= base[i];
+                            }
+                            if factor > 0.0 {
+                                *pixel = mix_premultiplied(*pixel, adjustment.apply(*pixel), factor);
+                            }
+                        }
+                        continue;
                     }
-                }
+                    let mut coverage = Vec::with_capacity(if layer.clipped { 0 } else { buffer.len() });
+                    let mask_factor = |idx: usize| layer.mask.as_ref().map_or(1.0, |mask| mask.factor(idx));
+                    let styled = (layer.visible && layer.style.is_active()).then(|| {
+                        layer.style.rendered(self.width, self.height, |idx| {
+                            to_premultiplied(layer.data[idx]).map(|channel| channel * mask_factor(idx))
+                        })
+                    });
+                    
+                    for (i, pixel) in buffer.iter_mut().enumerate() {
+                        let idx = (y0 + i / region_width) * self.width + x0 + i % region_width;
+                        let mut color = to_premultiplied(layer.data[idx]);
+                        let mut factor = mask_factor(idx) * layer.opacity;
+                        if !layer.clipped {
+                            coverage.push(if layer.visible { color[3] / 255.0 * factor } else { 0.0 });
+                        }
+                        if let Some(rendered) = styled.as_deref() {
+                            color = rendered[idx];
+                            factor = layer.opacity;
+                        }
+                        if let Some(base) = clip {
+                            factor *= base[i];
+                        }
+                        if layer.visible && factor > 0.0 && color[3] > 0.0 {
+                            *pixel = blend_over(*pixel, color.map(|channel| channel * factor));
+                        }
+                    }
+                    
+                    if !layer.clipped {
+                        clip_base = Some(coverage);
+                    }
+                },
+                LayerNode::Group(id, children) => {
+                    clip_base = None;
+                    let Some(group) = layer_group::find_group(&self.groups, *id) else {
+                        self.composite_nodes(children, buffer, region);
+                        continue;
+                    };
+                    if !group.visible {
+                        continue;
+                    }
+                    let opacity = group.opacity.clamp(0.0, 1.0);
+                    
+                    match group.blend {
+                        GroupBlend::Isolated => {
+                            let mut isolated = vec![[0.0; 4]; buffer.len()];
+                            self.composite_nodes(children, &mut isolated, region);
+                            for (pixel, content) in buffer.iter_mut().zip(isolated) {
+                                let faded = content.map(|channel| channel * opacity);
+                                *pixel = blend_over(*pixel, faded);
+                            }
+                        },
+                        GroupBlend::PassThrough => {
+                            let before = buffer.to_vec();
+                            self.composite_nodes(children, buffer, region);
+                            if opacity < 1.0 {
+                                for (pixel, original) in buffer.iter_mut().zip(before) {
+                                    *pixel = mix_premultiplied(original, *pixel, opacity);
+                                }
+                            }
+                        },
+                    }
+                },
+            }
+        }
+    }
+    
+    fn next_group_id(&self) -> u32 {
+        self.groups.iter().map(|group| group.id + 1).max().unwrap_or(1)
+    }
+    
+    fn prune_empty_groups(&mut self) {
+        loop {
+            let used: Vec<u32> = self.layers
+                .iter()
+                .filter_map(|layer| layer.group)
+                .chain(self.groups.iter().filter_map(|group| group.parent))
+                .collect();
+            let before = self.groups.len();
+            self.groups.retain(|group| used.contains(&group.id));
+            if self.groups.len() == before {
+                break;
             }
         }
-        None
     }
     
     #[inline]
     fn get_from_active_layer(&self, x: usize, y: usize) -> Option<Color32> {
         if x < self.width && y < self.height && self.active_layer_index < self.layers.len() {
             let idx = y * self.width + x;
-            self.layers[self.active_layer_index].data[idx]
+            let layer = &self.layers[self.active_layer_index];
+            match layer.mask.as_ref().filter(|_| self.editing_mask) {
+                Some(mask) => Some(mask.as_color(idx)),
+                None => layer.data[idx],
+            }
         } else {
             None
         }
     }
     
+    fn get_index_from_active_layer(&self, x: usize, y: usize) -> Option<u8> {
+        if self.editing_mask || x >= self.width || y >= self.height {
+            return None;
+        }
+        let indices = self.layers.get(self.active_layer_index)?.indices.as_ref()?;
+        indices[y * self.width + x]
+    }
+    
+    fn is_editing_mask(&self) -> bool {
+        self.editing_mask && self.layers
+            .get(self.active_layer_index)
+            .is_some_and(|layer| layer.mask.is_some())
+    }
+    
+    fn stash_frame(&mut self) {
+        if let Some(frame) = self.frames.get_mut(self.current_frame) {
+            frame.layers = std::mem::take(&mut self.layers);
+            frame.groups = std::mem::take(&mut self.groups);
+            frame.active_layer_index = self.active_layer_index;
+        }
+    }
+    
+    fn unstash_frame(&mut self) {
+        if let Some(frame) = self.frames.get_mut(self.current_frame) {
+            self.layers = std::mem::take(&mut frame.layers);
+            self.groups = std::mem::take(&mut frame.groups);
+            self.active_layer_index = frame.active_layer_index;
+        }
+    }
+    
+    fn show_frame(&mut self, index: usize) {
+        if index != self.current_frame && index < self.frames.len() {
+            self.stash_frame();
+            self.current_frame = index;
+            self.unstash_frame();
+        }
+    }
+    
+    fn frame_durations(&self) -> Vec<u32> {
+        self.frames.iter().map(|frame| frame.duration_ms).collect()
+    }
+    
+    fn frame_composite(&mut self, index: usize) -> Vec<[f32; 4]> {
+        let current = self.current_frame;
+        self.show_frame(index);
+        let composite = self.composite();
+        self.show_frame(current);
+        composite
+    }
+    
+    fn frame_composites_rgba(&mut self) -> Vec<(Vec<[u8; 4]>, u32)> {
+        let current = self.current_frame;
+        let composites = (0..self.frames.len())
+            .map(|index| {
+                self.show_frame(index);
+                (self.composite_rgba(), self.frames[index].duration_ms)
+            })
+            .collect();
+        self.show_frame(current);
+        composites
+    }
+    
+    fn snapshot_frames(&self) -> FrameStack {
+        let mut frames = self.frames.clone();
+        if let Some(frame) = frames.get_mut(self.current_frame) {
+            frame.layers = self.layers.clone();
+            frame.groups = self.groups.clone();
+            frame.active_layer_index = self.active_layer_index;
+        }
+        FrameStack {
+            frames,
+            current_frame: self.current_frame,
+            color_mode: self.color_mode.clone(),
+        }
+    }
+    
+    fn swap_frames(&mut self, stack: FrameStack) -> FrameStack {
+        self.stash_frame();
+        let previous = FrameStack {
+            frames: std::mem::replace(&mut self.frames, stack.frames),
+            current_frame: std::mem::replace(&mut self.current_frame, stack.current_frame),
+            color_mode: std::mem::replace(&mut self.color_mode, stack.color_mode),
+        };
+        self.unstash_frame();
+        previous
+    }
+    
+    fn conform_layer(&mut self, index: usize) {
+        let Some(layer) = self.layers.get_mut(index) else {
+            return;
+        };
+        let Some(palette) = self.color_mode.palette().filter(|_| layer.adjustment.is_none()) else {
+            layer.indices = None;
+            return;
+        };
+        
+        let mut cache = HashMap::new();
+        let indices: Vec<Option<u8>> = layer.data
+            .iter()
+            .map(|pixel| *cache.entry(*pixel).or_insert_with(|| palette.index_of(*pixel).map(|index| index as u8)))
+            .collect();
+        palette.resolve(&indices, &mut layer.data);
+        layer.indices = Some(indices);
+        layer.style.invalidate();
+        layer.thumbnail.mark_all_dirty();
+    }
+    
+    /// Drops masks and resizes pixel data whose length does not match the canvas,
+    /// so a damaged file cannot index past the end of a layer.
+    fn repair_layers(&mut self) {
+        let size = self.width * self.height;
+        let current = self.current_frame;
+        for frame in 0..self.frames.len() {
+            self.show_frame(frame);
+            for layer in self.layers.iter_mut() {
+                layer.data.resize(size, None);
+                if layer.mask.as_ref().is_some_and(|mask| mask.data.len() != size) {
+                    layer.mask = None;
+                }
+            }
+        }
+        self.show_frame(current);
+    }
+    
+    /// Brings every layer of every frame in line with the color mode after loading:
+    /// stored indices are resolved through the palette, and layers without valid
+    /// indices are snapped to it.
+    fn sync_indices(&mut self) {
+        let current = self.current_frame;
+        for frame in 0..self.frames.len() {
+            self.show_frame(frame);
+            for index in 0..self.layers.len() {
+                let layer = &mut self.layers[index];
+                let valid = match (self.color_mode.palette(), layer.indices.as_ref()) {
+                    (Some(palette), Some(indices)) => indices.len() == layer.data.len()
+                        && indices.iter().flatten().all(|&entry| (entry as usize) < palette.colors.len()),
+                    _ => false,
+                };
+                match (valid, self.color_mode.palette(), layer.indices.as_ref()) {
+                    (true, Some(palette), Some(indices)) => palette.resolve(indices, &mut layer.data),
+                    _ => self.conform_layer(index),
+                }
+            }
+        }
+        self.show_frame(current);
+    }
+    
+    /// Rewrites one palette entry and every pixel that refers to it, in all frames.
+    /// Returns the previous entry color.
+    fn set_palette_entry(&mut self, entry: usize, color: [u8; 3]) -> Option<[u8; 3]> {
+        let ColorMode::Indexed(palette) = &mut self.color_mode else {
+            return None;
+        };
+        let previous = std::mem::replace(palette.colors.get_mut(entry)?, color);
+        
+        let current = self.current_frame;
+        for frame in 0..self.frames.len() {
+            self.show_frame(frame);
+            let Some(palette) = self.color_mode.palette() else {
+                continue;
+            };
+            for layer in self.layers.iter_mut() {
+                if let Some(indices) = layer.indices.as_ref() {
+                    palette.recolor(entry, indices, &mut layer.data);
+                    layer.style.invalidate();
+                    layer.thumbnail.mark_all_dirty();
+                }
+            }
+        }
+        self.show_frame(current);
+        Some(previous)
+    }
+    
+    fn is_layer_shown(&self, index: usize) -> bool {
+        self.layers.get(index).is_some_and(|layer| {
+            layer.visible && layer_group::group_chain(&self.groups, layer.group)
+                .iter()
+                .all(|&id| layer_group::find_group(&self.groups, id).is_some_and(|group| group.visible))
+        })
+    }
+    
+    #[inline]
+    fn sample(&self, x: usize, y: usize, source: SampleSource) -> Option<Color32> {
+        self.sample_pixels(&[(x, y)], source)[0]
+    }
+    
+    /// Reads `pixels` from the active layer or the composite image. The composite is
+    /// built once over the bounding box of the pixels rather than per pixel.
+    fn sample_pixels(&self, pixels: &[(usize, usize)], source: SampleSource) -> Vec<Option<Color32>> {
+        if source == SampleSource::ActiveLayer {
+            return pixels.iter().map(|&(x, y)| self.get_from_active_layer(x, y)).collect();
+        }
+        
+        let inside: Vec<(usize, usize)> = pixels.iter().copied().filter(|&(x, y)| x < self.width && y < self.height).collect();
+        let (Some(x0), Some(y0), Some(x1), Some(y1)) = (
+            inside.iter().map(|p| p.0).min(),
+            inside.iter().map(|p| p.1).min(),
+            inside.iter().map(|p| p.0 + 1).max(),
+            inside.iter().map(|p| p.1 + 1).max(),
+        ) else {
+            return vec![None; pixels.len()];
+        };
+        
+        let composite = self.composite_region((x0, y0, x1, y1));
+        pixels
+            .iter()
+            .map(|&(x, y)| {
+                let inside = x < self.width && y < self.height;
+                inside.then(|| from_premultiplied(composite[(y - y0) * (x1 - x0) + x - x0])).flatten()
+            })
+            .collect()
+    }
+    
     #[inline]
     fn set(&mut self, x: usize, y: usize, color: Option<Color32>) {
         if x < self.width && y < self.height && self.active_layer_index < self.layers.len() {
             let idx = y * self.width + x;
-            self.layers[self.active_layer_index].data[idx] = color;
+            let layer = &mut self.layers[self.active_layer_index];
+            layer.style.invalidate();
+            layer.thumbnail.mark_dirty(x, y);
+            match (layer.mask.as_mut().filter(|_| self.editing_mask), layer.indices.as_mut(), self.color_mode.palette()) {
+                (Some(mask), _, _) => mask.paint(idx, color),
+                (None, Some(indices), Some(palette)) => {
+                    let index = palette.index_of(color);
+                    indices[idx] = index.map(|index| index as u8);
+                    layer.data[idx] = index.map(|index| palette.color(index));
+                },
+                _ => layer.data[idx] = color,
+            }
+        }
+    }
+    
+    /// Replays a recorded change; on indexed layers the stored palette index wins
+    /// over the color, so entries sharing a color stay distinct.
+    fn restore(&mut self, x: usize, y: usize, color: Option<Color32>, index: Option<u8>) {
+        let indexed = !self.editing_mask && self.layers
+            .get(self.active_layer_index)
+            .is_some_and(|layer| layer.indices.is_some());
+        let Some(palette) = self.color_mode.palette().filter(|_| indexed && x < self.width && y < self.height) else {
+            self.set(x, y, color);
+            return;
+        };
+        
+        let idx = y * self.width + x;
+        let layer = &mut self.layers[self.active_layer_index];
+        layer.style.invalidate();
+        layer.thumbnail.mark_dirty(x, y);
+        if let Some(indices) = layer.indices.as_mut() {
+            indices[idx] = index;
+        }
+        layer.data[idx] = index.map(|index| palette.color(index as usize));
+    }
+}
+
+#[derive(Clone)]
+struct LayerStack {
+    layers: Vec<Layer>,
+    groups: Vec<LayerGroup>,
+    active_layer_index: usize,
+    color_mode: ColorMode,
+    frame: usize,
+}
+
+struct FrameStack {
+    frames: Vec<AnimationFrame>,
+    current_frame: usize,
+    color_mode: ColorMode,
+}
+
+#[derive(Clone, Copy)]
+struct LayerFlags {
+    visible: bool,
+    group: Option<u32>,
+    lock_pixels: bool,
+    lock_alpha: bool,
+    lock_position: bool,
+}
+
+impl LayerFlags {
+    fn of(layer: &Layer) -> Self {
+        Self {
+            visible: layer.visible,
+            group: layer.group,
+            lock_pixels: layer.lock_pixels,
+            lock_alpha: layer.lock_alpha,
+            lock_position: layer.lock_position,
         }
     }
+    
+    fn apply(self, layer: &mut Layer) {
+        layer.visible = self.visible;
+        layer.group = self.group;
+        layer.lock_pixels = self.lock_pixels;
+        layer.lock_alpha = self.lock_alpha;
+        layer.lock_position = self.lock_position;
+    }
+}
+
+struct LayerProperties {
+    flags: Vec<LayerFlags>,
+    groups: Vec<LayerGroup>,
+    frame: usize,
+}
+
+struct MaskSwap {
+    index: usize,
+    mask: Option<LayerMask>,
+    frame: usize,
+}
+
+enum UndoEntry {
+    Pixels(Vec<CanvasChange>),
+    Properties(Box<LayerProperties>),
+    Mask(Box<MaskSwap>),
+    PaletteEntry(usize, [u8; 3]),
+    FrameDuration(usize, u32),
+    Layers(Box<LayerStack>),
+    Frames(Box<FrameStack>),
 }
 
 #[derive(Clone)]
@@ -171,8 +852,12 @@ struct CanvasChange {
     x: usize,
     y: usize,
     layer_index: usize,
+    frame: usize,
+    mask: bool,
     old_color: Option<Color32>,
     new_color: Option<Color32>,
+    old_index: Option<u8>,
+    new_index: Option<u8>,
 }
 
 enum SaveDialog {
@@ -184,8 +869,8 @@ enum SaveDialog {
 
 struct PaintApp {
     current_state: CanvasState,
-    undo_stack: Vec<Vec<CanvasChange>>,
-    redo_stack: Vec<Vec<CanvasChange>>,
+    undo_stack: Vec<UndoEntry>,
+    redo_stack: Vec<UndoEntry>,
     current_changes: Vec<CanvasChange>,
     current_tool: Tool,
     primary_color: Color32,
@@ -217,6 +902,31 @@ struct PaintApp {
     last_cursor_time: Option<f64>,
     velocity_sensitivity: f32,
     max_velocity_for_min_pressure: f32,
+    selection: Option<Selection>,
+    selection_start: Option<(i32, i32)>,
+    stabilizer: Stabilizer,
+    pressure_input: PressureInput,
+    sample_source: SampleSource,
+    smudge_strength: f32,
+    finger_painting: bool,
+    smudge_buffer: Option<Vec<[f32; 4]>>,
+    clone_source: Option<(i32, i32)>,
+    clone_offset: Option<(i32, i32)>,
+    clone_aligned: bool,
+    clone_snapshot: Option<HashMap<usize, Option<Color32>>>,
+    gradient_tool: GradientTool,
+    pen_tool: PenTool,
+    symmetry: Symmetry,
+    pixel_art: PixelArt,
+    wrap_mode: bool,
+    tiled_view: bool,
+    color_panel: ColorPanel,
+    palette_panel: PalettePanel,
+    indexed_panel: IndexedPanel,
+    export_settings: ExportSettings,
+    export_preview: Option<ExportPreview>,
+    pending_export: Option<String>,
+    animation_panel: AnimationPanel,
 }
 
 impl PaintApp {
@@ -257,34 +967,61 @@ impl PaintApp {
             last_cursor_time: None,
             velocity_sensitivity: 0.9,
             max_velocity_for_min_pressure: 1900.0,
+            selection: None,
+            selection_start: None,
+            stabilizer: Stabilizer::new(),
+            pressure_input: PressureInput::new(),
+            sample_source: SampleSource::AllLayers,
+            smudge_strength: 0.6,
+            finger_painting: false,
+            smudge_buffer: None,
+            clone_source: None,
+            clone_offset: None,
+            clone_aligned: true,
+            clone_snapshot: None,
+            gradient_tool: GradientTool::new(),
+            pen_tool: PenTool::new(),
+            symmetry: Symmetry::new(),
+            pixel_art: PixelArt::new(),
+            wrap_mode: false,
+            tiled_view: false,
+            color_panel: ColorPanel::new(),
+            palette_panel: PalettePanel::new(),
+            indexed_panel: IndexedPanel::new(),
+            export_settings: ExportSettings::load_default(),
+            export_preview: None,
+            pending_export: None,
+            animation_panel: AnimationPanel::new(),
         }
     }
 
     fn from_rustiq_file(file: RustiqueFile, language: Language) -> Self {
+        let mut frames: Vec<AnimationFrame> = file.frames
+            .into_iter()
+            .map(|frame| AnimationFrame {
+                layers: frame.layers.into_iter().map(Layer::from).collect(),
+                groups: frame.groups,
+                active_layer_index: frame.active_layer_index,
+                duration_ms: frame.duration_ms.clamp(MIN_FRAME_DURATION, MAX_FRAME_DURATION),
+            })
+            .collect();
+        if frames.is_empty() {
+            frames.push(AnimationFrame::new(DEFAULT_FRAME_DURATION));
+        }
+        
         let mut canvas = CanvasState {
             width: file.width,
             height: file.height,
-            layers: Vec::with_capacity(file.layers.len()),
+            layers: file.layers.into_iter().map(Layer::from).collect(),
+            groups: file.groups,
             active_layer_index: file.active_layer_index,
+            editing_mask: false,
+            color_mode: file.color_mode,
+            current_frame: file.current_frame.min(frames.len() - 1),
+            frames,
         };
-        
-        for layer_data in file.layers {
-            let mut layer = Layer {
-                name: layer_data.name,
-                data: Vec::with_capacity(layer_data.data.len()),
-                visible: layer_data.visible,
-            };
-            
-            for pixel_opt in layer_data.data {
-                if let Some(rgba) = pixel_opt {
-                    layer.data.push(Some(Color32::from_rgba_unmultiplied(rgba[0], rgba[1], rgba[2], rgba[3])));
-                } else {
-                    layer.data.push(None);
-                }
-            }
-            
-            canvas.layers.push(layer);
-        }
+        canvas.repair_layers();
+        canvas.sync_indices();
         
         let primary_color = Color32::from_rgba_unmultiplied(
             file.primary_color[0],
@@ -345,6 +1082,31 @@ impl PaintApp {
             last_cursor_time: None,
             velocity_sensitivity: 0.9,
             max_velocity_for_min_pressure: 1900.0,
+            selection: None,
+            selection_start: None,
+            stabilizer: Stabilizer::new(),
+            pressure_input: PressureInput::new(),
+            sample_source: SampleSource::AllLayers,
+            smudge_strength: 0.6,
+            finger_painting: false,
+            smudge_buffer: None,
+            clone_source: None,
+            clone_offset: None,
+            clone_aligned: true,
+            clone_snapshot: None,
+            gradient_tool: GradientTool::new(),
+            pen_tool: PenTool::with_paths(file.paths),
+            symmetry: Symmetry::new(),
+            pixel_art: PixelArt::new(),
+            wrap_mode: false,
+            tiled_view: false,
+            color_panel: ColorPanel::new(),
+            palette_panel: PalettePanel::new(),
+            indexed_panel: IndexedPanel::new(),
+            export_settings: ExportSettings::load_default(),
+            export_preview: None,
+            pending_export: None,
+            animation_panel: AnimationPanel::new(),
         }
     }
 
@@ -358,17 +1120,31 @@ impl PaintApp {
     
     
     fn update_pressure_from_velocity(&mut self, cursor_pos: Pos2, current_time: f64) {
+        let normalized_velocity = if let (Some(last_pos), Some(last_time)) = (self.last_cursor_pos, self.last_cursor_time) {
+            let delta_time = (current_time - last_time).max(0.001);
+            let distance = (cursor_pos - last_pos).length();
+            let velocity = distance / delta_time as f32;
+            Some((velocity / self.max_velocity_for_min_pressure).min(1.0))
+        } else {
+            None
+        };
+        self.brush_manager.current_velocity = normalized_velocity.unwrap_or(0.0);
+        
         if !self.pressure_enabled {
             self.current_pressure = 1.0;
+            self.last_cursor_pos = Some(cursor_pos);
+            self.last_cursor_time = Some(current_time);
             return;
         }
         
-        let pressure = if let (Some(last_pos), Some(last_time)) = (self.last_cursor_pos, self.last_cursor_time) {
-            let delta_time = (current_time - last_time).max(0.001);
-            let distance = (cursor_pos - last_pos).length();
-            let velocity = distance / delta_time as f32;
-            
-            let normalized_velocity = (velocity / self.max_velocity_for_min_pressure).min(1.0);
+        let device_pressure = self.pressure_input
+            .poll()
+            .filter(|sample| sample.in_contact)
+            .map(|sample| sample.pressure);
+        
+        let pressure = if let Some(device_pressure) = device_pressure {
+            device_pressure.clamp(0.0, 1.0)
+        } else if let Some(normalized_velocity) = normalized_velocity {
             let base_pressure = 1.0 - (normalized_velocity * self.velocity_sensitivity);
             
             base_pressure.clamp(0.1, 1.0)
@@ -410,18 +1186,12 @@ impl PaintApp {
     }
     
     fn save_as_image(&mut self, path: &str, format: ImageFormat) -> Result<(), String> {
-        let width = self.current_state.width;
-        let height = self.current_state.height;
-        let mut img = ImageBuffer::new(width as u32, height as u32);
+        let options = self.export_settings.options(format);
+        let palette = self.current_state.color_mode.palette().map(|palette| palette.colors.as_slice());
+        let pixels = self.current_state.composite_rgba();
+        let bytes = options.encode(format, self.current_state.width, self.current_state.height, pixels, palette, self.language)?;
         
-        for y in 0..height {
-            for x in 0..width {
-                let color = self.current_state.get(x, y).unwrap_or(Color32::TRANSPARENT);
-                img.put_pixel(x as u32, y as u32, Rgba([color.r(), color.g(), color.b(), color.a()]));
-            }
-        }
-
-        match img.save_with_format(path, format) {
+        match fs::write(path, bytes) {
             Ok(_) => {
                 self.has_unsaved_changes = false;
                 self.last_save_path = Some(path.to_string());
@@ -506,6 +1276,31 @@ impl PaintApp {
                             last_cursor_time: None,
                             velocity_sensitivity: 0.8,
                             max_velocity_for_min_pressure: 800.0,
+                            selection: None,
+                            selection_start: None,
+                            stabilizer: Stabilizer::new(),
+                            pressure_input: PressureInput::new(),
+                            sample_source: SampleSource::AllLayers,
+                            smudge_strength: 0.6,
+                            finger_painting: false,
+                            smudge_buffer: None,
+                            clone_source: None,
+                            clone_offset: None,
+                            clone_aligned: true,
+                            clone_snapshot: None,
+                            gradient_tool: GradientTool::new(),
+                            pen_tool: PenTool::new(),
+                            symmetry: Symmetry::new(),
+                            pixel_art: PixelArt::new(),
+                            wrap_mode: false,
+                            tiled_view: false,
+                            color_panel: ColorPanel::new(),
+                            palette_panel: PalettePanel::new(),
+                            indexed_panel: IndexedPanel::new(),
+                            export_settings: ExportSettings::load_default(),
+                            export_preview: None,
+                            pending_export: None,
+                            animation_panel: AnimationPanel::new(),
                         };
                         
                         Ok(app)
@@ -517,28 +1312,16 @@ impl PaintApp {
     }
     
     fn save_as_rustiq(&mut self, path: &str) -> Result<(), String> {
-        let mut layers = Vec::with_capacity(self.current_state.layers.len());
-        
-        for layer in &self.current_state.layers {
-            let mut layer_data = Vec::with_capacity(layer.data.len());
-            
-            for &pixel_opt in &layer.data {
-                match pixel_opt {
-                    Some(color) => {
-                        layer_data.push(Some([color.r(), color.g(), color.b(), color.a()]));
-                    },
-                    None => {
-                        layer_data.push(None);
-                    }
-                }
-            }
-            
-            layers.push(LayerData {
-                name: layer.name.clone(),
-                data: layer_data,
-                visible: layer.visible,
-            });
-        }
+        let layers = self.current_state.layers.iter().map(LayerData::from).collect();
+        let frames = self.current_state.frames
+            .iter()
+            .map(|frame| FrameData {
+                layers: frame.layers.iter().map(LayerData::from).collect(),
+                groups: frame.groups.clone(),
+                active_layer_index: frame.active_layer_index,
+                duration_ms: frame.duration_ms,
+            })
+            .collect();
         
         let mut saved_colors = Vec::with_capacity(self.saved_colors.len());
         for &color in &self.saved_colors {
@@ -555,6 +1338,11 @@ impl PaintApp {
             saved_colors,
             brush_size: self.brush_size,
             eraser_size: self.eraser_size,
+            paths: self.pen_tool.paths.clone(),
+            groups: self.current_state.groups.clone(),
+            color_mode: self.current_state.color_mode.clone(),
+            frames,
+            current_frame: self.current_state.current_frame,
         };
         
         let json = match serde_json::to_string(&rustiq_file) {
@@ -577,6 +1365,17 @@ impl PaintApp {
         }
     }
     
+    fn request_save(&mut self, path: &str) -> Result<(), String> {
+        match Self::detect_format(path).get_image_format() {
+            Some(_) => {
+                self.pending_export = Some(path.to_string());
+                self.export_preview = None;
+                Ok(())
+            },
+            None => self.save_file(path),
+        }
+    }
+    
     fn quick_save(&mut self) -> Result<(), String> {
         if let Some(path) = &self.last_save_path {
             let path_clone = path.clone();
@@ -587,22 +1386,65 @@ impl PaintApp {
     }
 
     fn add_layer(&mut self, name: String) {
+        self.begin_layer_operation();
+        let active = self.current_state.active_layer_index;
+        let group = self.current_state.layers.get(active).and_then(|layer| layer.group);
         self.current_state.layers.push(Layer {
             name,
             data: vec![None; self.current_state.width * self.current_state.height],
+            indices: None,
             visible: true,
+            group,
+            mask: None,
+            clipped: false,
+            lock_pixels: false,
+            lock_alpha: false,
+            lock_position: false,
+            adjustment: None,
+            style: LayerStyle::default(),
+            opacity: 1.0,
+            thumbnail: LayerThumbnail::default(),
         });
-        self.current_state.active_layer_index = self.current_state.layers.len() - 1;
+        
+        let new_index = self.current_state.layers.len() - 1;
+        self.current_state.conform_layer(new_index);
+        let insert_at = (active + 1).min(new_index);
+        let mut order: Vec<usize> = (0..new_index).collect();
+        order.insert(insert_at, new_index);
+        self.reorder_layers(order);
+        self.current_state.active_layer_index = insert_at;
         self.texture_dirty = true;
         self.has_unsaved_changes = true;
     }
     
+    fn reorder_layers(&mut self, order: Vec<usize>) {
+        let mut new_positions = vec![0; order.len()];
+        for (new_index, &old_index) in order.iter().enumerate() {
+            new_positions[old_index] = new_index;
+        }
+        
+        let mut old_layers: Vec<Option<Layer>> = std::mem::take(&mut self.current_state.layers)
+            .into_iter()
+            .map(Some)
+            .collect();
+        self.current_state.layers = order
+            .iter()
+            .filter_map(|&old_index| old_layers[old_index].take())
+            .collect();
+        
+        if let Some(&new_index) = new_positions.get(self.current_state.active_layer_index) {
+            self.current_state.active_layer_index = new_index;
+        }
+    }
+    
     fn remove_layer(&mut self, index: usize) {
         if self.current_state.layers.len() > 1 && index < self.current_state.layers.len() {
+            self.begin_layer_operation();
             self.current_state.layers.remove(index);
             if self.current_state.active_layer_index >= self.current_state.layers.len() {
                 self.current_state.active_layer_index = self.current_state.layers.len() - 1;
             }
+            self.current_state.prune_empty_groups();
             self.texture_dirty = true;
             self.has_unsaved_changes = true;
         }
@@ -610,51 +1452,583 @@ impl PaintApp {
     
     fn move_layer_up(&mut self, index: usize) {
         if index > 0 && index < self.current_state.layers.len() {
-            self.current_state.layers.swap(index, index - 1);
-            if self.current_state.active_layer_index == index {
-                self.current_state.active_layer_index -= 1;
-            } else if self.current_state.active_layer_index == index - 1 {
-                self.current_state.active_layer_index += 1;
-            }
-            self.texture_dirty = true;
-            self.has_unsaved_changes = true;
+            self.step_layer(index, index - 1);
         }
     }
     
     fn move_layer_down(&mut self, index: usize) {
-        if index < self.current_state.layers.len() - 1 {
-            self.current_state.layers.swap(index, index + 1);
-            if self.current_state.active_layer_index == index {
-                self.current_state.active_layer_index += 1;
-            } else if self.current_state.active_layer_index == index + 1 {
-                self.current_state.active_layer_index -= 1;
-            }
-            self.texture_dirty = true;
-            self.has_unsaved_changes = true;
+        if index + 1 < self.current_state.layers.len() {
+            self.step_layer(index, index + 1);
         }
     }
     
-    fn toggle_layer_visibility(&mut self, index: usize) {
-        if index < self.current_state.layers.len() {
-            self.current_state.layers[index].visible = !self.current_state.layers[index].visible;
-            self.texture_dirty = true;
-            self.has_unsaved_changes = true;
+    fn step_layer(&mut self, index: usize, neighbor: usize) {
+        if self.current_state.layers[index].lock_position {
+            return;
         }
-    }
-    
-    fn set_active_layer(&mut self, index: usize) {
-        if index < self.current_state.layers.len() {
-            self.current_state.active_layer_index = index;
+        
+        self.begin_layer_operation();
+        let neighbor_group = self.current_state.layers[neighbor].group;
+        if self.current_state.layers[index].group != neighbor_group {
+            self.current_state.layers[index].group = neighbor_group;
+        } else {
+            let mut order: Vec<usize> = (0..self.current_state.layers.len()).collect();
+            order.swap(index, neighbor);
+            self.reorder_layers(order);
         }
+        self.current_state.prune_empty_groups();
+        self.texture_dirty = true;
+        self.has_unsaved_changes = true;
     }
     
-    fn rename_layer(&mut self, index: usize, name: String) {
-        if index < self.current_state.layers.len() {
-            self.current_state.layers[index].name = name;
-            self.has_unsaved_changes = true;
+    fn drop_layer(&mut self, index: usize, target: LayerDrop) {
+        if self.current_state.layers.get(index).is_none_or(|layer| layer.lock_position) {
+            return;
         }
-    }
-    
+        
+        let (group, above) = match target {
+            LayerDrop::Above(target_index) => match self.current_state.layers.get(target_index) {
+                Some(layer) => (layer.group, Some(target_index)),
+                None => return,
+            },
+            LayerDrop::IntoGroup(group_id) => {
+                let groups = &self.current_state.groups;
+                let top_member = self.current_state.layers
+                    .iter()
+                    .rposition(|layer| layer_group::is_descendant(groups, layer.group, group_id));
+                (Some(group_id), top_member)
+            },
+            LayerDrop::Bottom => (None, None),
+        };
+        
+        self.begin_layer_operation();
+        if above == Some(index) {
+            self.current_state.layers[index].group = group;
+        } else {
+            let mut order: Vec<usize> = (0..self.current_state.layers.len()).filter(|&i| i != index).collect();
+            let insert_at = match above {
+                Some(target_index) => order.iter().position(|&i| i == target_index).map_or(order.len(), |p| p + 1),
+                None => 0,
+            };
+            order.insert(insert_at, index);
+            self.reorder_layers(order);
+            self.current_state.layers[insert_at].group = group;
+        }
+        
+        self.current_state.prune_empty_groups();
+        self.texture_dirty = true;
+        self.has_unsaved_changes = true;
+    }
+    
+    fn create_group(&mut self) {
+        let active = self.current_state.active_layer_index;
+        let Some(parent) = self.current_state.layers.get(active).map(|layer| layer.group) else {
+            return;
+        };
+        
+        self.begin_property_operation();
+        let id = self.current_state.next_group_id();
+        let name = format!("{} {}", get_text("group", self.language), self.current_state.groups.len() + 1);
+        self.current_state.groups.push(LayerGroup::new(id, name, parent));
+        self.current_state.layers[active].group = Some(id);
+        self.texture_dirty = true;
+        self.has_unsaved_changes = true;
+    }
+    
+    fn ungroup(&mut self, id: u32) {
+        let Some(parent) = layer_group::find_group(&self.current_state.groups, id).map(|group| group.parent) else {
+            return;
+        };
+        
+        self.begin_property_operation();
+        for layer in self.current_state.layers.iter_mut().filter(|layer| layer.group == Some(id)) {
+            layer.group = parent;
+        }
+        for group in self.current_state.groups.iter_mut().filter(|group| group.parent == Some(id)) {
+            group.parent = parent;
+        }
+        self.current_state.groups.retain(|group| group.id != id);
+        self.texture_dirty = true;
+        self.has_unsaved_changes = true;
+    }
+    
+    fn toggle_layer_visibility(&mut self, index: usize) {
+        if index < self.current_state.layers.len() {
+            self.begin_property_operation();
+            self.current_state.layers[index].visible = !self.current_state.layers[index].visible;
+            self.texture_dirty = true;
+            self.has_unsaved_changes = true;
+        }
+    }
+    
+    fn set_active_layer(&mut self, index: usize) {
+        if index < self.current_state.layers.len() {
+            if index != self.current_state.active_layer_index {
+                self.current_state.editing_mask = self.current_state.layers[index].adjustment.is_some();
+            }
+            self.current_state.active_layer_index = index;
+        }
+    }
+    
+    fn add_layer_mask(&mut self, index: usize) {
+        if index >= self.current_state.layers.len() {
+            return;
+        }
+        
+        self.save_state();
+        let previous = self.current_state.layers[index].mask.clone();
+        self.push_undo(UndoEntry::Mask(Box::new(MaskSwap {
+            index,
+            mask: previous,
+            frame: self.current_state.current_frame,
+        })));
+        let (width, height) = (self.current_state.width, self.current_state.height);
+        if let Some(layer) = self.current_state.layers.get_mut(index) {
+            layer.mask = Some(LayerMask::new(width, height));
+            layer.style.invalidate();
+            self.current_state.active_layer_index = index;
+            self.current_state.editing_mask = true;
+            self.texture_dirty = true;
+            self.has_unsaved_changes = true;
+        }
+    }
+    
+    fn delete_layer_mask(&mut self, index: usize) {
+        if self.current_state.layers.get(index).is_none_or(|layer| layer.mask.is_none()) {
+            return;
+        }
+        
+        self.begin_layer_operation();
+        if let Some(layer) = self.current_state.layers.get_mut(index) {
+            layer.mask = None;
+            self.current_state.editing_mask = false;
+            self.texture_dirty = true;
+            self.has_unsaved_changes = true;
+        }
+    }
+    
+    fn apply_layer_mask(&mut self, index: usize) {
+        if self.current_state.layers.get(index).is_none_or(|layer| layer.mask.is_none()) {
+            return;
+        }
+        
+        self.begin_layer_operation();
+        let layer = &mut self.current_state.layers[index];
+        let Some(mask) = layer.mask.take() else {
+            return;
+        };
+        for (idx, pixel) in layer.data.iter_mut().enumerate() {
+            let factor = mask.factor(idx);
+            if pixel.is_some() && factor < 1.0 {
+                *pixel = from_premultiplied(to_premultiplied(*pixel).map(|channel| channel * factor));
+            }
+        }
+        layer.style.invalidate();
+        layer.thumbnail.mark_all_dirty();
+        
+        self.current_state.editing_mask = false;
+        self.current_state.conform_layer(index);
+        self.texture_dirty = true;
+        self.has_unsaved_changes = true;
+    }
+    
+    fn duplicate_layer(&mut self, index: usize) {
+        let Some(layer) = self.current_state.layers.get(index) else {
+            return;
+        };
+        
+        let mut copy = layer.clone();
+        copy.name = format!("{} {}", layer.name, get_text("layer_copy_suffix", self.language));
+        self.begin_layer_operation();
+        self.current_state.layers.insert(index + 1, copy);
+        self.current_state.active_layer_index = index + 1;
+        self.current_state.editing_mask = false;
+        self.texture_dirty = true;
+        self.has_unsaved_changes = true;
+    }
+    
+    fn merge_layer_down(&mut self, index: usize) -> Result<(), String> {
+        if index == 0 || index >= self.current_state.layers.len() {
+            return Err(get_text("no_layer_below", self.language));
+        }
+        
+        let upper = &self.current_state.layers[index];
+        let lower = &self.current_state.layers[index - 1];
+        if upper.group != lower.group {
+            return Err(get_text("merge_across_groups", self.language));
+        }
+        if lower.lock_pixels {
+            return Err(get_text("layer_locked", self.language));
+        }
+        if lower.adjustment.is_some() {
+            return Err(get_text("merge_into_adjustment", self.language));
+        }
+        if !upper.visible || !lower.visible {
+            return Err(get_text("merge_hidden_layer", self.language));
+        }
+        
+        let pair = CanvasState {
+            width: self.current_state.width,
+            height: self.current_state.height,
+            layers: vec![
+                Layer { group: None, clipped: false, ..lower.clone() },
+                Layer { group: None, ..upper.clone() },
+            ],
+            groups: Vec::new(),
+            active_layer_index: 0,
+            editing_mask: false,
+            color_mode: ColorMode::Rgba,
+            frames: vec![AnimationFrame::new(DEFAULT_FRAME_DURATION)],
+            current_frame: 0,
+        };
+        let merged: Vec<Option<Color32>> = pair.composite().into_iter().map(from_premultiplied).collect();
+        
+        self.begin_layer_operation();
+        let lower = &mut self.current_state.layers[index - 1];
+        lower.data = merged;
+        lower.mask = None;
+        lower.style = LayerStyle::default();
+        lower.opacity = 1.0;
+        lower.thumbnail.mark_all_dirty();
+        self.current_state.layers.remove(index);
+        self.current_state.active_layer_index = index - 1;
+        self.current_state.editing_mask = false;
+        self.current_state.conform_layer(index - 1);
+        self.texture_dirty = true;
+        self.has_unsaved_changes = true;
+        Ok(())
+    }
+    
+    fn merge_visible_layers(&mut self) -> Result<(), String> {
+        let shown: Vec<usize> = (0..self.current_state.layers.len())
+            .filter(|&i| self.current_state.is_layer_shown(i))
+            .collect();
+        if shown.len() < 2 {
+            return Err(get_text("not_enough_visible_layers", self.language));
+        }
+        
+        let merged = Layer {
+            name: get_text("merged_layer", self.language),
+            data: self.current_state.composite().into_iter().map(from_premultiplied).collect(),
+            indices: None,
+            visible: true,
+            group: None,
+            mask: None,
+            clipped: false,
+            lock_pixels: false,
+            lock_alpha: false,
+            lock_position: false,
+            adjustment: None,
+            style: LayerStyle::default(),
+            opacity: 1.0,
+            thumbnail: LayerThumbnail::default(),
+        };
+        
+        self.begin_layer_operation();
+        let target = shown[0];
+        let mut merged = Some(merged);
+        self.current_state.layers = std::mem::take(&mut self.current_state.layers)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, layer)| match i {
+                _ if i == target => merged.take(),
+                _ if shown.contains(&i) => None,
+                _ => Some(layer),
+            })
+            .collect();
+        self.current_state.active_layer_index = target;
+        self.current_state.editing_mask = false;
+        self.current_state.prune_empty_groups();
+        self.current_state.conform_layer(target);
+        self.texture_dirty = true;
+        self.has_unsaved_changes = true;
+        Ok(())
+    }
+    
+    fn flatten_image(&mut self) {
+        let flattened = Layer {
+            name: get_text("background_layer", self.language),
+            data: self.current_state.composite().into_iter().map(from_premultiplied).collect(),
+            indices: None,
+            visible: true,
+            group: None,
+            mask: None,
+            clipped: false,
+            lock_pixels: false,
+            lock_alpha: false,
+            lock_position: false,
+            adjustment: None,
+            style: LayerStyle::default(),
+            opacity: 1.0,
+            thumbnail: LayerThumbnail::default(),
+        };
+        
+        self.begin_layer_operation();
+        self.current_state.layers = vec![flattened];
+        self.current_state.groups.clear();
+        self.current_state.active_layer_index = 0;
+        self.current_state.editing_mask = false;
+        self.current_state.conform_layer(0);
+        self.texture_dirty = true;
+        self.has_unsaved_changes = true;
+    }
+    
+    fn add_background_color_layer(&mut self) {
+        self.add_layer(get_text("background_color_layer", self.language));
+        let color = self.secondary_color;
+        if let Some(layer) = self.current_state.layers.get_mut(self.current_state.active_layer_index) {
+            layer.data.fill(Some(color));
+            layer.thumbnail.mark_all_dirty();
+        }
+        self.current_state.conform_layer(self.current_state.active_layer_index);
+    }
+    
+    fn add_adjustment_layer(&mut self, adjustment: Adjustment) {
+        self.add_layer(adjustment.get_name(self.language));
+        let (width, height) = (self.current_state.width, self.current_state.height);
+        if let Some(layer) = self.current_state.layers.get_mut(self.current_state.active_layer_index) {
+            layer.adjustment = Some(adjustment);
+            layer.mask = Some(LayerMask::new(width, height));
+            self.current_state.editing_mask = true;
+        }
+    }
+    
+    fn convert_to_indexed(&mut self, source: IndexedSource, color_count: usize) -> Result<(), String> {
+        let palette = match source {
+            IndexedSource::ActivePalette => match self.palette_panel.active_palette() {
+                Some(palette) => IndexedPalette::from_colors(palette.colors.iter().map(|entry| entry.color)),
+                None => return Err(get_text("no_active_palette", self.language)),
+            },
+            IndexedSource::Extracted => {
+                let pixels: Vec<[u8; 4]> = self.current_state
+                    .frame_composites_rgba()
+                    .into_iter()
+                    .flat_map(|(pixels, _)| pixels)
+                    .collect();
+                IndexedPalette::from_colors(extract_palette(&pixels, color_count, ExtractionMethod::KMeans))
+            },
+        };
+        if palette.colors.is_empty() {
+            return Err(get_text("empty_indexed_palette", self.language));
+        }
+        
+        self.begin_frame_operation();
+        self.primary_color = palette.snap(Some(self.primary_color)).unwrap_or(self.primary_color);
+        self.secondary_color = palette.snap(Some(self.secondary_color)).unwrap_or(self.secondary_color);
+        self.current_state.color_mode = ColorMode::Indexed(palette);
+        let current = self.current_state.current_frame;
+        for frame in 0..self.current_state.frames.len() {
+            self.current_state.show_frame(frame);
+            for index in 0..self.current_state.layers.len() {
+                self.current_state.conform_layer(index);
+            }
+        }
+        self.current_state.show_frame(current);
+        self.animation_panel.onion_skin.invalidate();
+        self.texture_dirty = true;
+        self.has_unsaved_changes = true;
+        Ok(())
+    }
+    
+    fn convert_to_rgba(&mut self) {
+        self.begin_frame_operation();
+        self.current_state.color_mode = ColorMode::Rgba;
+        self.current_state.sync_indices();
+        self.has_unsaved_changes = true;
+    }
+    
+    fn edit_indexed_entry(&mut self, index: usize, color: [u8; 3], begin: bool) {
+        let Some(&[r, g, b]) = self.current_state.color_mode.palette().and_then(|palette| palette.colors.get(index)) else {
+            return;
+        };
+        
+        if begin {
+            self.save_state();
+            self.push_undo(UndoEntry::PaletteEntry(index, [r, g, b]));
+        }
+        let old_color = Color32::from_rgb(r, g, b);
+        let new_color = Color32::from_rgb(color[0], color[1], color[2]);
+        self.current_state.set_palette_entry(index, color);
+        self.animation_panel.onion_skin.invalidate();
+        for paint_color in [&mut self.primary_color, &mut self.secondary_color] {
+            if *paint_color == old_color {
+                *paint_color = new_color;
+            }
+        }
+        self.texture_dirty = true;
+        self.has_unsaved_changes = true;
+    }
+    
+    fn select_frame(&mut self, index: usize) {
+        if index < self.current_state.frames.len() {
+            self.save_state();
+            self.current_state.show_frame(index);
+            self.animation_panel.onion_skin.invalidate();
+            self.texture_dirty = true;
+        }
+    }
+    
+    fn insert_frame(&mut self, layers: Vec<Layer>) {
+        let state = &mut self.current_state;
+        let frame = AnimationFrame {
+            layers,
+            groups: state.groups.clone(),
+            active_layer_index: state.active_layer_index,
+            duration_ms: state.frames[state.current_frame].duration_ms,
+        };
+        state.frames.insert(state.current_frame + 1, frame);
+        state.show_frame(state.current_frame + 1);
+        self.animation_panel.onion_skin.invalidate();
+        self.texture_dirty = true;
+        self.has_unsaved_changes = true;
+    }
+    
+    fn add_frame(&mut self) {
+        self.begin_frame_operation();
+        let size = self.current_state.width * self.current_state.height;
+        let layers = self.current_state.layers
+            .iter()
+            .map(|layer| Layer {
+                data: vec![None; size],
+                indices: layer.indices.as_ref().map(|_| vec![None; size]),
+                mask: None,
+                thumbnail: LayerThumbnail::default(),
+                ..layer.clone()
+            })
+            .collect();
+        self.insert_frame(layers);
+    }
+    
+    fn duplicate_frame(&mut self) {
+        self.begin_frame_operation();
+        let layers = self.current_state.layers.clone();
+        self.insert_frame(layers);
+    }
+    
+    fn delete_frame(&mut self) {
+        if self.current_state.frames.len() > 1 {
+            self.begin_frame_operation();
+            let state = &mut self.current_state;
+            state.frames.remove(state.current_frame);
+            state.current_frame = state.current_frame.min(state.frames.len() - 1);
+            state.unstash_frame();
+            state.editing_mask = false;
+            self.animation_panel.onion_skin.invalidate();
+            self.texture_dirty = true;
+            self.has_unsaved_changes = true;
+        }
+    }
+    
+    fn move_frame(&mut self, offset: isize) {
+        let state = &self.current_state;
+        let Some(target) = state.current_frame.checked_add_signed(offset).filter(|&target| target < state.frames.len()) else {
+            return;
+        };
+        
+        self.begin_frame_operation();
+        let state = &mut self.current_state;
+        state.frames.swap(state.current_frame, target);
+        state.current_frame = target;
+        self.animation_panel.onion_skin.invalidate();
+        self.texture_dirty = true;
+        self.has_unsaved_changes = true;
+    }
+    
+    fn set_frame_duration(&mut self, index: usize, duration_ms: u32, begin: bool) {
+        if index >= self.current_state.frames.len() {
+            return;
+        }
+        
+        if begin {
+            self.save_state();
+            self.push_undo(UndoEntry::FrameDuration(index, self.current_state.frames[index].duration_ms));
+        }
+        self.current_state.frames[index].duration_ms = duration_ms.clamp(MIN_FRAME_DURATION, MAX_FRAME_DURATION);
+        self.has_unsaved_changes = true;
+    }
+    
+    fn toggle_playback(&mut self) {
+        self.save_state();
+        self.animation_panel.playback.toggle();
+        self.texture_dirty = true;
+    }
+    
+    fn stop_playback(&mut self) {
+        if self.animation_panel.playback.stop() {
+            self.texture_dirty = true;
+        }
+    }
+    
+    fn advance_playback(&mut self) -> Option<std::time::Duration> {
+        if !self.animation_panel.playback.playing {
+            return None;
+        }
+        
+        let durations = self.current_state.frame_durations();
+        let (next, remaining) = self.animation_panel.playback.tick(self.current_state.current_frame, &durations);
+        if let Some(index) = next {
+            self.current_state.show_frame(index);
+            self.animation_panel.onion_skin.invalidate();
+            self.texture_dirty = true;
+        }
+        Some(remaining)
+    }
+    
+    fn export_animation(&mut self, path: &str, export: AnimationExport) -> Result<(), String> {
+        let (width, height) = (self.current_state.width, self.current_state.height);
+        let frames = self.current_state.frame_composites_rgba();
+        let palette = self.current_state.color_mode.palette().map(|palette| palette.colors.clone());
+        let language = self.language;
+        let write = |path: &Path, bytes: Vec<u8>| {
+            fs::write(path, bytes).map_err(|e| format!("{}: {}", get_text("error_saving_image", language), e))
+        };
+        
+        match export {
+            AnimationExport::Gif => {
+                let options = self.export_settings.options(ImageFormat::Gif);
+                let bytes = animation::encode_gif(&options, width, height, &frames, palette.as_deref(), language)?;
+                write(Path::new(path), bytes)
+            },
+            AnimationExport::Apng => {
+                let options = self.export_settings.options(ImageFormat::Png);
+                let bytes = animation::encode_apng(&options, width, height, &frames, language)?;
+                write(Path::new(path), bytes)
+            },
+            AnimationExport::PngSequence => {
+                let options = self.export_settings.options(ImageFormat::Png);
+                let frame_count = frames.len();
+                for (index, (pixels, _)) in frames.into_iter().enumerate() {
+                    let bytes = options.encode(ImageFormat::Png, width, height, pixels, palette.as_deref(), language)?;
+                    write(&animation::sequence_path(Path::new(path), index, frame_count), bytes)?;
+                }
+                Ok(())
+            },
+        }
+    }
+    
+    fn toggle_layer_lock(&mut self, index: usize, lock: LayerLock) {
+        if index >= self.current_state.layers.len() {
+            return;
+        }
+        
+        self.begin_property_operation();
+        let layer = &mut self.current_state.layers[index];
+        let flag = match lock {
+            LayerLock::Pixels => &mut layer.lock_pixels,
+            LayerLock::Alpha => &mut layer.lock_alpha,
+            LayerLock::Position => &mut layer.lock_position,
+        };
+        *flag = !*flag;
+        self.has_unsaved_changes = true;
+    }
+    
+    fn rename_layer(&mut self, index: usize, name: String) {
+        if index < self.current_state.layers.len() {
+            self.current_state.layers[index].name = name;
+            self.has_unsaved_changes = true;
+        }
+    }
+    
     fn add_saved_color(&mut self, color: Color32) {
         if !self.saved_colors.contains(&color) {
             if self.saved_colors.len() >= MAX_SAVED_COLORS {
@@ -664,6 +2038,10 @@ impl PaintApp {
         }
     }
     
+    fn swap_colors(&mut self) {
+        std::mem::swap(&mut self.primary_color, &mut self.secondary_color);
+    }
+    
     fn remove_saved_color(&mut self, index: usize) {
         if index < self.saved_colors.len() {
             self.saved_colors.remove(index);
@@ -671,86 +2049,258 @@ impl PaintApp {
     }
     
     fn record_change(&mut self, x: usize, y: usize, new_color: Option<Color32>) {
+        let (x, y) = if self.wrap_mode {
+            (x % self.current_state.width, y % self.current_state.height)
+        } else {
+            (x, y)
+        };
+        
+        if self.selection.as_ref().is_some_and(|selection| !selection.contains(x, y)) {
+            return;
+        }
+        
+        let Some(layer) = self.current_state.layers.get(self.current_state.active_layer_index) else {
+            return;
+        };
+        if layer.lock_pixels || (layer.adjustment.is_some() && !self.current_state.is_editing_mask()) {
+            return;
+        }
+        let lock_alpha = layer.lock_alpha && !self.current_state.is_editing_mask();
+        
         if x < self.current_state.width && y < self.current_state.height {
             let old_color = self.current_state.get_from_active_layer(x, y);
+            let new_color = if lock_alpha {
+                match (old_color, new_color) {
+                    (Some(old), Some(new)) => {
+                        let [r, g, b, _] = new.to_srgba_unmultiplied();
+                        Some(Color32::from_rgba_unmultiplied(r, g, b, old.a()))
+                    },
+                    _ => old_color,
+                }
+            } else {
+                new_color
+            };
+            let new_color = match self.current_state.color_mode.palette() {
+                Some(palette) if !self.current_state.is_editing_mask() => palette.snap(new_color),
+                _ => new_color,
+            };
             if old_color != new_color {
+                let old_index = self.current_state.get_index_from_active_layer(x, y);
+                self.current_state.set(x, y, new_color);
                 self.current_changes.push(CanvasChange {
                     x, 
                     y, 
                     layer_index: self.current_state.active_layer_index,
+                    frame: self.current_state.current_frame,
+                    mask: self.current_state.is_editing_mask(),
                     old_color, 
-                    new_color
+                    new_color,
+                    old_index,
+                    new_index: self.current_state.get_index_from_active_layer(x, y),
                 });
-                self.current_state.set(x, y, new_color);
                 self.has_unsaved_changes = true;
             }
         }
     }
 
+    fn canvas_coords(&self, x: i32, y: i32) -> Option<(usize, usize)> {
+        let width = self.current_state.width as i32;
+        let height = self.current_state.height as i32;
+        if self.wrap_mode {
+            Some((x.rem_euclid(width) as usize, y.rem_euclid(height) as usize))
+        } else if x >= 0 && y >= 0 && x < width && y < height {
+            Some((x as usize, y as usize))
+        } else {
+            None
+        }
+    }
+
+    fn push_undo(&mut self, entry: UndoEntry) {
+        self.undo_stack.push(entry);
+        if self.undo_stack.len() > MAX_UNDO_STEPS {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+    }
+
     fn save_state(&mut self) {
         if !self.current_changes.is_empty() {
-            self.undo_stack.push(std::mem::take(&mut self.current_changes));
-            self.current_changes = Vec::new();
-            if self.undo_stack.len() > MAX_UNDO_STEPS {
-                self.undo_stack.remove(0);
-            }
-            self.redo_stack.clear();
+            let changes = std::mem::take(&mut self.current_changes);
+            self.push_undo(UndoEntry::Pixels(changes));
             self.is_drawing = false;
             self.has_unsaved_changes = true;
+
+            if matches!(self.current_tool, Tool::Brush | Tool::PaintBucket | Tool::Line | Tool::Pen | Tool::Pencil) {
+                let color = if self.using_secondary_color { self.secondary_color } else { self.primary_color };
+                self.color_panel.push_recent(color);
+            }
+        }
+    }
+
+    fn begin_layer_operation(&mut self) {
+        self.save_state();
+        let snapshot = LayerStack {
+            layers: self.current_state.layers.clone(),
+            groups: self.current_state.groups.clone(),
+            active_layer_index: self.current_state.active_layer_index,
+            color_mode: self.current_state.color_mode.clone(),
+            frame: self.current_state.current_frame,
+        };
+        self.push_undo(UndoEntry::Layers(Box::new(snapshot)));
+    }
+
+    fn begin_property_operation(&mut self) {
+        self.save_state();
+        let state = &self.current_state;
+        let snapshot = LayerProperties {
+            flags: state.layers.iter().map(LayerFlags::of).collect(),
+            groups: state.groups.clone(),
+            frame: state.current_frame,
+        };
+        self.push_undo(UndoEntry::Properties(Box::new(snapshot)));
+    }
+
+    fn begin_frame_operation(&mut self) {
+        self.save_state();
+        let snapshot = self.current_state.snapshot_frames();
+        self.push_undo(UndoEntry::Frames(Box::new(snapshot)));
+    }
+
+    fn swap_layer_stack(&mut self, stack: LayerStack) -> LayerStack {
+        let state = &mut self.current_state;
+        state.show_frame(stack.frame);
+        LayerStack {
+            layers: std::mem::replace(&mut state.layers, stack.layers),
+            groups: std::mem::replace(&mut state.groups, stack.groups),
+            active_layer_index: std::mem::replace(&mut state.active_layer_index, stack.active_layer_index),
+            color_mode: std::mem::replace(&mut state.color_mode, stack.color_mode),
+            frame: stack.frame,
+        }
+    }
+
+    fn swap_layer_properties(&mut self, properties: LayerProperties) -> LayerProperties {
+        let state = &mut self.current_state;
+        state.show_frame(properties.frame);
+        let previous = LayerProperties {
+            flags: state.layers.iter().map(LayerFlags::of).collect(),
+            groups: std::mem::replace(&mut state.groups, properties.groups),
+            frame: properties.frame,
+        };
+        for (layer, flags) in state.layers.iter_mut().zip(properties.flags) {
+            flags.apply(layer);
+        }
+        previous
+    }
+
+    fn swap_layer_mask(&mut self, swap: MaskSwap) -> MaskSwap {
+        let state = &mut self.current_state;
+        state.show_frame(swap.frame);
+        let Some(layer) = state.layers.get_mut(swap.index) else {
+            return swap;
+        };
+        let previous = std::mem::replace(&mut layer.mask, swap.mask);
+        layer.style.invalidate();
+        layer.thumbnail.mark_all_dirty();
+        state.active_layer_index = swap.index;
+        state.editing_mask = layer.mask.is_some();
+        MaskSwap {
+            index: swap.index,
+            mask: previous,
+            frame: swap.frame,
         }
     }
 
     fn undo(&mut self) {
-        if let Some(changes) = self.undo_stack.pop() {
-            let mut redo_changes = Vec::with_capacity(changes.len());
-            
-            for change in changes.iter().rev() {
-                let layer_index_backup = self.current_state.active_layer_index;
-                self.current_state.active_layer_index = change.layer_index;
+        let Some(entry) = self.undo_stack.pop() else {
+            return;
+        };
+        
+        let redo_entry = match entry {
+            UndoEntry::Pixels(changes) => {
+                let mut redo_changes = Vec::with_capacity(changes.len());
                 
-                redo_changes.push(CanvasChange {
-                    x: change.x,
-                    y: change.y,
-                    layer_index: change.layer_index,
-                    old_color: change.old_color,
-                    new_color: change.new_color,
-                });
+                for change in changes.iter().rev() {
+                    self.current_state.show_frame(change.frame);
+                    let layer_index_backup = self.current_state.active_layer_index;
+                    let editing_mask_backup = self.current_state.editing_mask;
+                    self.current_state.active_layer_index = change.layer_index;
+                    self.current_state.editing_mask = change.mask;
+                    
+                    redo_changes.push(change.clone());
+                    
+                    self.current_state.restore(change.x, change.y, change.old_color, change.old_index);
+                    self.current_state.active_layer_index = layer_index_backup;
+                    self.current_state.editing_mask = editing_mask_backup;
+                }
                 
-                self.current_state.set(change.x, change.y, change.old_color);
-                self.current_state.active_layer_index = layer_index_backup;
-            }
-            
-            self.redo_stack.push(redo_changes);
-            self.texture_dirty = true;
-            self.has_unsaved_changes = true;
-        }
+                UndoEntry::Pixels(redo_changes)
+            },
+            UndoEntry::Properties(properties) => UndoEntry::Properties(Box::new(self.swap_layer_properties(*properties))),
+            UndoEntry::Mask(swap) => UndoEntry::Mask(Box::new(self.swap_layer_mask(*swap))),
+            UndoEntry::PaletteEntry(index, color) => {
+                let previous = self.current_state.set_palette_entry(index, color).unwrap_or(color);
+                UndoEntry::PaletteEntry(index, previous)
+            },
+            UndoEntry::FrameDuration(index, duration) => {
+                UndoEntry::FrameDuration(index, std::mem::replace(&mut self.current_state.frames[index].duration_ms, duration))
+            },
+            UndoEntry::Layers(stack) => UndoEntry::Layers(Box::new(self.swap_layer_stack(*stack))),
+            UndoEntry::Frames(stack) => UndoEntry::Frames(Box::new(self.current_state.swap_frames(*stack))),
+        };
+        
+        self.redo_stack.push(redo_entry);
+        self.animation_panel.onion_skin.invalidate();
+        self.texture_dirty = true;
+        self.has_unsaved_changes = true;
     }
 
     fn redo(&mut self) {
-        if let Some(changes) = self.redo_stack.pop() {
-            let mut undo_changes = Vec::with_capacity(changes.len());
-            
-            for change in changes.iter().rev() {
-                let layer_index_backup = self.current_state.active_layer_index;
-                self.current_state.active_layer_index = change.layer_index;
+        let Some(entry) = self.redo_stack.pop() else {
+            return;
+        };
+        
+        let undo_entry = match entry {
+            UndoEntry::Pixels(changes) => {
+                let mut undo_changes = Vec::with_capacity(changes.len());
                 
-                let current_color = self.current_state.get_from_active_layer(change.x, change.y);
-                undo_changes.push(CanvasChange {
-                    x: change.x,
-                    y: change.y,
-                    layer_index: change.layer_index,
-                    old_color: current_color,
-                    new_color: change.new_color,
-                });
+                for change in changes.iter().rev() {
+                    self.current_state.show_frame(change.frame);
+                    let layer_index_backup = self.current_state.active_layer_index;
+                    let editing_mask_backup = self.current_state.editing_mask;
+                    self.current_state.active_layer_index = change.layer_index;
+                    self.current_state.editing_mask = change.mask;
+                    
+                    let current_color = self.current_state.get_from_active_layer(change.x, change.y);
+                    undo_changes.push(CanvasChange {
+                        old_color: current_color,
+                        old_index: self.current_state.get_index_from_active_layer(change.x, change.y),
+                        ..change.clone()
+                    });
+                    
+                    self.current_state.restore(change.x, change.y, change.new_color, change.new_index);
+                    self.current_state.active_layer_index = layer_index_backup;
+                    self.current_state.editing_mask = editing_mask_backup;
+                }
                 
-                self.current_state.set(change.x, change.y, change.new_color);
-                self.current_state.active_layer_index = layer_index_backup;
-            }
-            
-            self.undo_stack.push(undo_changes);
-            self.texture_dirty = true;
-            self.has_unsaved_changes = true;
-        }
+                UndoEntry::Pixels(undo_changes)
+            },
+            UndoEntry::Properties(properties) => UndoEntry::Properties(Box::new(self.swap_layer_properties(*properties))),
+            UndoEntry::Mask(swap) => UndoEntry::Mask(Box::new(self.swap_layer_mask(*swap))),
+            UndoEntry::PaletteEntry(index, color) => {
+                let previous = self.current_state.set_palette_entry(index, color).unwrap_or(color);
+                UndoEntry::PaletteEntry(index, previous)
+            },
+            UndoEntry::FrameDuration(index, duration) => {
+                UndoEntry::FrameDuration(index, std::mem::replace(&mut self.current_state.frames[index].duration_ms, duration))
+            },
+            UndoEntry::Layers(stack) => UndoEntry::Layers(Box::new(self.swap_layer_stack(*stack))),
+            UndoEntry::Frames(stack) => UndoEntry::Frames(Box::new(self.current_state.swap_frames(*stack))),
+        };
+        
+        self.undo_stack.push(undo_entry);
+        self.animation_panel.onion_skin.invalidate();
+        self.texture_dirty = true;
+        self.has_unsaved_changes = true;
     }
 
     fn draw_line(&mut self, start: (i32, i32), end: (i32, i32), _color: Color32) {
@@ -772,7 +2322,7 @@ impl PaintApp {
         }
         
         let pressure = self.get_effective_pressure();
-        let is_eraser = self.current_tool == Tool::Eraser;
+        let is_eraser = self.current_tool == Tool::Eraser || self.pressure_input.eraser_active();
         
         let mut changes = Vec::new();
         {
@@ -784,7 +2334,12 @@ impl PaintApp {
                 }
             };
             
-            self.brush_manager.draw_line(start, end, color, pressure, &mut record_change);
+            let mirrors = self.symmetry.transforms(self.current_state.width, self.current_state.height);
+            for (index, mirror) in mirrors.iter().enumerate() {
+                self.symmetry.swap_brush_state(index, &mut self.brush_manager);
+                self.brush_manager.draw_line(mirror.apply(start), mirror.apply(end), color, pressure, &mut record_change);
+                self.symmetry.swap_brush_state(index, &mut self.brush_manager);
+            }
         }
         
         for (x, y, color) in changes {
@@ -839,6 +2394,7 @@ impl PaintApp {
         };
         
         let num_points = (distance / spacing).ceil() as i32;
+        let mirrors = self.symmetry.transforms(self.current_state.width, self.current_state.height);
         
         for i in 0..=num_points {
             let t = if num_points > 0 { i as f32 / num_points as f32 } else { 0.0 };
@@ -846,7 +2402,12 @@ impl PaintApp {
             let x = x0 as f32 + dx * t;
             let y = y0 as f32 + dy * t;
             
-            self.draw_smooth_point(x, y, false);
+            for (index, mirror) in mirrors.iter().enumerate() {
+                let (mx, my) = mirror.apply_f32(x, y);
+                self.symmetry.swap_brush_state(index, &mut self.brush_manager);
+                self.draw_smooth_point(mx, my, false);
+                self.symmetry.swap_brush_state(index, &mut self.brush_manager);
+            }
         }
         
         self.last_action_time = Instant::now();
@@ -941,15 +2502,13 @@ impl PaintApp {
         let mask = self.brush_manager.generate_brush_mask(mask_size);
         
         let center = mask_size as i32 / 2;
-        let width = self.current_state.width as i32;
-        let height = self.current_state.height as i32;
         
         for dy in 0..mask_size as i32 {
             for dx in 0..mask_size as i32 {
                 let nx = x + dx - center;
                 let ny = y + dy - center;
                 
-                if nx >= 0 && nx < width && ny >= 0 && ny < height {
+                if let Some((nx, ny)) = self.canvas_coords(nx, ny) {
                     let mask_value = mask[(dy as usize) * mask_size + (dx as usize)];
                     
                     if mask_value > 0.0 {
@@ -967,7 +2526,7 @@ impl PaintApp {
                             None
                         };
                         
-                        self.record_change(nx as usize, ny as usize, new_color);
+                        self.record_change(nx, ny, new_color);
                     }
                 }
             }
@@ -995,7 +2554,7 @@ impl PaintApp {
         }
         
         let pressure = self.get_effective_pressure();
-        let is_eraser = self.current_tool == Tool::Eraser;
+        let is_eraser = self.current_tool == Tool::Eraser || self.pressure_input.eraser_active();
         
         let mut changes = Vec::new();
         {
@@ -1007,7 +2566,13 @@ impl PaintApp {
                 }
             };
             
-            self.brush_manager.draw_point(x, y, color, pressure, &mut record_change);
+            let mirrors = self.symmetry.transforms(self.current_state.width, self.current_state.height);
+            for (index, mirror) in mirrors.iter().enumerate() {
+                self.symmetry.swap_brush_state(index, &mut self.brush_manager);
+                let (mx, my) = mirror.apply((x, y));
+                self.brush_manager.draw_point(mx, my, color, pressure, &mut record_change);
+                self.symmetry.swap_brush_state(index, &mut self.brush_manager);
+            }
         }
         
         for (x, y, color) in changes {
@@ -1061,40 +2626,464 @@ impl PaintApp {
             },
         };
         
-        let mask = self.brush_manager.generate_brush_mask(mask_size);
+        let mask = self.brush_manager.generate_brush_mask(mask_size);
+        
+        let center = mask_size as i32 / 2;
+        
+        for dy in 0..mask_size as i32 {
+            for dx in 0..mask_size as i32 {
+                let nx = x + dx - center;
+                let ny = y + dy - center;
+                
+                if let Some((nx, ny)) = self.canvas_coords(nx, ny) {
+                    let mask_value = mask[(dy as usize) * mask_size + (dx as usize)];
+                    
+                    if mask_value > 0.0 {
+                        let final_color = if self.current_tool == Tool::Eraser || fill_color.is_none() {
+                            None
+                        } else {
+                            let alpha = (color.a() as f32 * mask_value) as u8;
+                            if alpha > 0 {
+                                Some(Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), alpha))
+                            } else {
+                                None
+                            }
+                        };
+                        
+                        self.record_change(nx, ny, final_color);
+                    }
+                }
+            }
+        }
+        
+        self.texture_dirty = true;
+    }
+
+    fn continue_stroke(&mut self, x: f32, y: f32, color: Color32) {
+        let points = if self.last_position.is_none() {
+            self.brush_manager.begin_stroke((x as i32, y as i32));
+            self.symmetry.reset_brush_states();
+            self.pixel_art.begin_stroke();
+            if matches!(self.current_tool, Tool::CloneStamp | Tool::Heal) {
+                self.begin_clone_stroke(x as i32, y as i32);
+            }
+            self.stabilizer.begin(x, y)
+        } else {
+            self.stabilizer.push(x, y)
+        };
+        
+        for (px, py) in points {
+            self.stroke_segment_to((px as i32, py as i32), color);
+        }
+    }
+    
+    fn stroke_segment_to(&mut self, point: (i32, i32), color: Color32) {
+        let retouching = matches!(self.current_tool, Tool::Smudge | Tool::CloneStamp | Tool::Heal);
+        let pixel_tool = matches!(self.current_tool, Tool::Pencil | Tool::Shade);
+        if let Some(last_pos) = self.last_position {
+            if last_pos == point {
+                return;
+            }
+            if pixel_tool {
+                self.pixel_line(last_pos, point, color);
+            } else if retouching {
+                self.retouch_line(last_pos, point);
+            } else {
+                self.draw_line(last_pos, point, color);
+            }
+        } else if pixel_tool {
+            self.pixel_line(point, point, color);
+        } else if retouching {
+            self.retouch_dab(point.0, point.1);
+        } else {
+            self.draw_point(point.0, point.1, self.using_secondary_color);
+        }
+        self.last_position = Some(point);
+    }
+    
+    fn pixel_line(&mut self, start: (i32, i32), end: (i32, i32), color: Color32) {
+        if self.current_state.active_layer_index < self.current_state.layers.len() &&
+           !self.current_state.layers[self.current_state.active_layer_index].visible {
+            return;
+        }
+        
+        let is_eraser = self.pressure_input.eraser_active();
+        let fill = if is_eraser { None } else { Some(color) };
+        
+        for (x, y) in bresenham(start, end) {
+            if self.current_tool == Tool::Shade {
+                self.shade_pixels(x, y);
+            } else if self.pixel_art.uses_pixel_perfect() {
+                self.pixel_perfect_plot(x, y, fill);
+            } else {
+                for (px, py) in self.pixel_art.footprint(x, y) {
+                    self.record_pixel(px, py, fill);
+                }
+            }
+        }
+        
+        self.last_action_time = Instant::now();
+        self.texture_dirty = true;
+    }
+    
+    fn record_pixel(&mut self, x: i32, y: i32, color: Option<Color32>) {
+        if let Some((x, y)) = self.canvas_coords(x, y) {
+            self.record_change(x, y, color);
+        }
+    }
+    
+    fn pixel_perfect_plot(&mut self, x: i32, y: i32, color: Option<Color32>) {
+        if !self.pixel_art.is_new_trail_point((x, y)) {
+            return;
+        }
+        
+        let replaced = self.canvas_coords(x, y)
+            .and_then(|(px, py)| self.current_state.get_from_active_layer(px, py));
+        
+        self.record_pixel(x, y, color);
+        if let Some(((cx, cy), original)) = self.pixel_art.push_trail((x, y), replaced) {
+            self.record_pixel(cx, cy, original);
+        }
+    }
+    
+    fn shade_pixels(&mut self, x: i32, y: i32) {
+        for (px, py) in self.pixel_art.footprint(x, y) {
+            let Some((px, py)) = self.canvas_coords(px, py) else {
+                continue;
+            };
+            if !self.pixel_art.mark_shaded((px as i32, py as i32)) {
+                continue;
+            }
+            
+            let current = self.current_state.get_from_active_layer(px, py);
+            if let Some(shaded) = self.pixel_art.shade_color(current, &self.saved_colors) {
+                self.record_change(px, py, shaded);
+            }
+        }
+    }
+    
+    fn retouch_line(&mut self, start: (i32, i32), end: (i32, i32)) {
+        let spacing = (self.brush_manager.active_brush().spacing * self.brush_size as f32).max(1.0);
+        let dx = (end.0 - start.0) as f32;
+        let dy = (end.1 - start.1) as f32;
+        let steps = ((dx * dx + dy * dy).sqrt() / spacing).ceil().max(1.0) as i32;
+        
+        for i in 1..=steps {
+            let t = i as f32 / steps as f32;
+            self.retouch_dab(start.0 + (dx * t).round() as i32, start.1 + (dy * t).round() as i32);
+        }
+    }
+    
+    fn retouch_dab(&mut self, x: i32, y: i32) {
+        match self.current_tool {
+            Tool::Smudge => self.smudge_dab(x, y),
+            Tool::CloneStamp => self.clone_dab(x, y, false),
+            Tool::Heal => self.clone_dab(x, y, true),
+            _ => {}
+        }
+    }
+    
+    fn set_clone_source(&mut self, x: i32, y: i32) {
+        self.clone_source = Some((x, y));
+        self.clone_offset = None;
+    }
+    
+    fn begin_clone_stroke(&mut self, x: i32, y: i32) {
+        let Some((source_x, source_y)) = self.clone_source else {
+            return;
+        };
+        
+        if !self.clone_aligned || self.clone_offset.is_none() {
+            self.clone_offset = Some((source_x - x, source_y - y));
+        }
+        
+        // Filled lazily by `capture_clone_pixels`: a pixel is stored the first time it is
+        // sampled or painted, so it always holds its value from before the stroke.
+        self.clone_snapshot = Some(HashMap::new());
+    }
+    
+    fn capture_clone_pixels(&mut self, pixels: &[(usize, usize)]) {
+        let width = self.current_state.width;
+        let Some(snapshot) = self.clone_snapshot.as_mut() else {
+            return;
+        };
+        let missing: Vec<(usize, usize)> = pixels
+            .iter()
+            .copied()
+            .filter(|&(x, y)| !snapshot.contains_key(&(y * width + x)))
+            .collect();
+        if missing.is_empty() {
+            return;
+        }
+        
+        let colors = self.current_state.sample_pixels(&missing, self.sample_source);
+        for ((x, y), color) in missing.into_iter().zip(colors) {
+            snapshot.insert(y * width + x, color);
+        }
+    }
+    
+    fn clone_dab(&mut self, x: i32, y: i32, heal: bool) {
+        if self.current_state.active_layer_index < self.current_state.layers.len() &&
+           !self.current_state.layers[self.current_state.active_layer_index].visible {
+            return;
+        }
+        let Some((offset_x, offset_y)) = self.clone_offset.filter(|_| self.clone_snapshot.is_some()) else {
+            return;
+        };
+        
+        self.brush_manager.current_size = self.brush_size as f32;
+        let pressure = self.get_effective_pressure();
+        let (mask, size, opacity) = self.brush_manager.dab_mask(x, y, pressure);
+        let center = size as i32 / 2;
+        let width = self.current_state.width;
+        let ring = boundary_ring(&mask, size);
+        
+        let mut pairs = Vec::new();
+        for (i, &mask_value) in mask.iter().enumerate() {
+            let px = x + (i % size) as i32 - center;
+            let py = y + (i / size) as i32 - center;
+            let target = self.canvas_coords(px, py);
+            let source = self.canvas_coords(px + offset_x, py + offset_y);
+            if let (true, Some(target), Some(source)) = (mask_value > 0.0, target, source) {
+                pairs.push((target, source, mask_value, ring[i]));
+            }
+        }
+        
+        let touched: Vec<(usize, usize)> = pairs.iter().flat_map(|pair| [pair.0, pair.1]).collect();
+        self.capture_clone_pixels(&touched);
+        let Some(snapshot) = self.clone_snapshot.as_ref() else {
+            return;
+        };
+        
+        let dabs: Vec<_> = pairs
+            .into_iter()
+            .map(|((px, py), (sx, sy), mask_value, on_ring)| {
+                let source = to_premultiplied(snapshot[&(sy * width + sx)]);
+                let destination = to_premultiplied(self.current_state.get_from_active_layer(px, py));
+                (px, py, mask_value, source, destination, on_ring)
+            })
+            .collect();
+        
+        let correction = if heal {
+            let source_mean = mean_rgb(dabs.iter().filter(|dab| dab.5).map(|dab| dab.3));
+            let destination_mean = mean_rgb(dabs.iter().filter(|dab| dab.5).map(|dab| dab.4));
+            [
+                destination_mean[0] - source_mean[0],
+                destination_mean[1] - source_mean[1],
+                destination_mean[2] - source_mean[2],
+            ]
+        } else {
+            [0.0; 3]
+        };
+        
+        let changes: Vec<(usize, usize, Option<Color32>)> = dabs
+            .into_iter()
+            .map(|(px, py, mask_value, source, destination, _)| {
+                let alpha = source[3].max(destination[3]);
+                let patched = [
+                    (source[0] + correction[0]).clamp(0.0, alpha),
+                    (source[1] + correction[1]).clamp(0.0, alpha),
+                    (source[2] + correction[2]).clamp(0.0, alpha),
+                    if heal { alpha } else { source[3] },
+                ];
+                let amount = (opacity * mask_value).clamp(0.0, 1.0);
+                (px, py, from_premultiplied(mix_premultiplied(destination, patched, amount)))
+            })
+            .collect();
+        
+        for (px, py, color) in changes {
+            self.record_change(px, py, color);
+        }
+        
+        self.texture_dirty = true;
+    }
+    
+    fn smudge_dab(&mut self, x: i32, y: i32) {
+        if self.current_state.active_layer_index < self.current_state.layers.len() &&
+           !self.current_state.layers[self.current_state.active_layer_index].visible {
+            return;
+        }
+        
+        self.brush_manager.current_size = self.brush_size as f32;
+        let pressure = self.get_effective_pressure();
+        let (mask, size, opacity) = self.brush_manager.dab_mask(x, y, pressure);
+        let center = size as i32 / 2;
+        
+        let positions: Vec<Option<(usize, usize)>> = (0..size * size)
+            .map(|i| self.canvas_coords(x + (i % size) as i32 - center, y + (i / size) as i32 - center))
+            .collect();
+        let inside: Vec<(usize, usize)> = positions.iter().flatten().copied().collect();
+        let mut sampled = self.current_state.sample_pixels(&inside, self.sample_source).into_iter();
+        let canvas: Vec<[f32; 4]> = positions
+            .iter()
+            .map(|position| match position {
+                Some(_) => to_premultiplied(sampled.next().flatten()),
+                None => [0.0; 4],
+            })
+            .collect();
+        
+        let buffer = match self.smudge_buffer.take() {
+            Some(buffer) => resize_smudge_buffer(buffer, size),
+            None if self.finger_painting => vec![to_premultiplied(Some(self.primary_color)); size * size],
+            None => canvas.clone(),
+        };
+        
+        let mut next_buffer = buffer.clone();
+        let mut changes = Vec::new();
+        for (i, &mask_value) in mask.iter().enumerate() {
+            let Some((px, py)) = positions[i].filter(|_| mask_value > 0.0) else {
+                continue;
+            };
+            
+            let amount = (self.smudge_strength * opacity * mask_value).clamp(0.0, 1.0);
+            let mixed = mix_premultiplied(canvas[i], buffer[i], amount);
+            changes.push((px, py, from_premultiplied(mixed)));
+            
+            let pickup = ((1.0 - self.smudge_strength) * mask_value).clamp(0.0, 1.0);
+            next_buffer[i] = mix_premultiplied(buffer[i], canvas[i], pickup);
+        }
+        self.smudge_buffer = Some(next_buffer);
+        
+        for (px, py, color) in changes {
+            self.record_change(px, py, color);
+        }
+        
+        self.texture_dirty = true;
+    }
+    
+    fn apply_gradient(&mut self, start: Pos2, end: Pos2) {
+        if self.current_state.active_layer_index < self.current_state.layers.len() &&
+           !self.current_state.layers[self.current_state.active_layer_index].visible {
+            return;
+        }
+        
+        let (x0, y0, x1, y1) = match &self.selection {
+            Some(selection) => match selection.bounds() {
+                Some(bounds) => bounds,
+                None => return,
+            },
+            None => (0, 0, self.current_state.width, self.current_state.height),
+        };
+        
+        let shape = self.gradient_tool.shape;
+        let colors = self.gradient_tool.gradient.render(x1 - x0, y1 - y0, self.gradient_tool.dither, |x, y| {
+            shape.position(start, end, (x + x0) as f32 + 0.5, (y + y0) as f32 + 0.5)
+        });
+        
+        let region_width = x1 - x0;
+        let mut changes = Vec::with_capacity(colors.len());
+        for (i, color) in colors.into_iter().enumerate() {
+            let x = x0 + i % region_width;
+            let y = y0 + i / region_width;
+            let coverage = self.selection.as_ref().map(|s| s.value(x, y) as f32 / 255.0).unwrap_or(1.0);
+            if coverage <= 0.0 {
+                continue;
+            }
+            
+            let source = [color[0] * coverage, color[1] * coverage, color[2] * coverage, color[3] * coverage];
+            let destination = to_premultiplied(self.current_state.get_from_active_layer(x, y));
+            changes.push((x, y, from_premultiplied(blend_over(destination, source))));
+        }
+        
+        for (x, y, color) in changes {
+            self.record_change(x, y, color);
+        }
+        
+        self.texture_dirty = true;
+    }
+    
+    fn stroke_vector_path(&mut self, index: usize) {
+        let Some(path) = self.pen_tool.paths.get(index) else {
+            return;
+        };
+        if self.current_state.active_layer_index < self.current_state.layers.len() &&
+           !self.current_state.layers[self.current_state.active_layer_index].visible {
+            return;
+        }
+        
+        self.brush_manager.current_size = self.brush_size as f32;
+        let spacing = (self.brush_manager.active_brush().spacing * self.brush_size as f32).max(1.0);
+        let samples = path.sample_evenly(spacing);
+        let color = if self.using_secondary_color { self.secondary_color } else { self.primary_color };
+        
+        if let Some((start, _)) = samples.first() {
+            self.brush_manager.begin_stroke((start.x.round() as i32, start.y.round() as i32));
+        }
+        
+        let mut changes = Vec::new();
+        {
+            let mut record_change = |x: usize, y: usize, new_color: Option<Color32>| {
+                changes.push((x, y, new_color));
+            };
+            
+            for (point, t) in samples {
+                let pressure = self.pen_tool.taper_pressure(t);
+                self.brush_manager.draw_point(point.x.round() as i32, point.y.round() as i32, color, pressure, &mut record_change);
+            }
+        }
+        
+        for (x, y, color) in changes {
+            self.record_change(x, y, color);
+        }
         
-        let center = mask_size as i32 / 2;
-        let width = self.current_state.width as i32;
-        let height = self.current_state.height as i32;
+        self.texture_dirty = true;
+    }
+    
+    fn fill_vector_path(&mut self, index: usize) {
+        let Some(path) = self.pen_tool.paths.get(index) else {
+            return;
+        };
+        if self.current_state.active_layer_index < self.current_state.layers.len() &&
+           !self.current_state.layers[self.current_state.active_layer_index].visible {
+            return;
+        }
         
-        for dy in 0..mask_size as i32 {
-            for dx in 0..mask_size as i32 {
-                let nx = x + dx - center;
-                let ny = y + dy - center;
-                
-                if nx >= 0 && nx < width && ny >= 0 && ny < height {
-                    let mask_value = mask[(dy as usize) * mask_size + (dx as usize)];
-                    
-                    if mask_value > 0.0 {
-                        let final_color = if self.current_tool == Tool::Eraser || fill_color.is_none() {
-                            None
-                        } else {
-                            let alpha = (color.a() as f32 * mask_value) as u8;
-                            if alpha > 0 {
-                                Some(Color32::from_rgba_unmultiplied(color.r(), color.g(), color.b(), alpha))
-                            } else {
-                                None
-                            }
-                        };
-                        
-                        self.record_change(nx as usize, ny as usize, final_color);
-                    }
-                }
+        let width = self.current_state.width;
+        let coverage = path.coverage_mask(width, self.current_state.height);
+        let color = to_premultiplied(Some(if self.using_secondary_color { self.secondary_color } else { self.primary_color }));
+        
+        let mut changes = Vec::new();
+        for (i, &amount) in coverage.iter().enumerate() {
+            if amount <= 0.0 {
+                continue;
             }
+            let (x, y) = (i % width, i / width);
+            let source = [color[0] * amount, color[1] * amount, color[2] * amount, color[3] * amount];
+            let destination = to_premultiplied(self.current_state.get_from_active_layer(x, y));
+            changes.push((x, y, from_premultiplied(blend_over(destination, source))));
+        }
+        
+        for (x, y, color) in changes {
+            self.record_change(x, y, color);
         }
         
         self.texture_dirty = true;
     }
+    
+    fn vector_path_to_selection(&mut self, index: usize) {
+        let Some(path) = self.pen_tool.paths.get(index) else {
+            return;
+        };
+        
+        let (width, height) = (self.current_state.width, self.current_state.height);
+        let mask = path.coverage_mask(width, height)
+            .into_iter()
+            .map(|amount| (amount * 255.0).round() as u8)
+            .collect();
+        let selection = Selection::from_mask(width, height, mask);
+        self.selection = if selection.is_empty() { None } else { Some(selection) };
+    }
+    
+    fn end_stroke(&mut self) {
+        self.smudge_buffer = None;
+        self.clone_snapshot = None;
+        if self.stabilizer.is_active() {
+            let color = if self.using_secondary_color { self.secondary_color } else { self.primary_color };
+            for (px, py) in self.stabilizer.finish() {
+                self.stroke_segment_to((px as i32, py as i32), color);
+            }
+        }
+    }
 
     fn paint_bucket(&mut self, x: usize, y: usize, _use_secondary: bool) {
         if x >= self.current_state.width || y >= self.current_state.height {
@@ -1131,10 +3120,11 @@ impl PaintApp {
             visited[idx] = true;
             self.record_change(cx, cy, fill_color);
             
-            if cx > 0 { queue.push_back((cx - 1, cy)); }
-            if cx + 1 < self.current_state.width { queue.push_back((cx + 1, cy)); }
-            if cy > 0 { queue.push_back((cx, cy - 1)); }
-            if cy + 1 < self.current_state.height { queue.push_back((cx, cy + 1)); }
+            for (nx, ny) in [(cx as i32 - 1, cy as i32), (cx as i32 + 1, cy as i32), (cx as i32, cy as i32 - 1), (cx as i32, cy as i32 + 1)] {
+                if let Some(neighbor) = self.canvas_coords(nx, ny) {
+                    queue.push_back(neighbor);
+                }
+            }
         }
         
         self.last_action_time = Instant::now();
@@ -1142,7 +3132,12 @@ impl PaintApp {
     }
 
     fn pick_color(&mut self, x: usize, y: usize, _use_secondary: bool) {
-        if let Some(color) = self.current_state.get(x, y) {
+        let sampled = self.current_state.sample(x, y, self.sample_source);
+        let sampled = match self.current_state.color_mode.palette() {
+            Some(palette) => palette.snap(sampled),
+            None => sampled,
+        };
+        if let Some(color) = sampled {
             if self.using_secondary_color {
                 self.secondary_color = color;
             } else {
@@ -1151,36 +3146,66 @@ impl PaintApp {
         }
     }
 
+    fn brush_tip_from_selection(&self) -> Result<BrushTip, String> {
+        let selection = match &self.selection {
+            Some(selection) => selection,
+            None => return Err(get_text("no_selection", self.language)),
+        };
+        let (x0, y0, x1, y1) = match selection.bounds() {
+            Some(bounds) => bounds,
+            None => return Err(get_text("no_selection", self.language)),
+        };
+        
+        let composite = self.current_state.composite_region((x0, y0, x1, y1));
+        let mut pixels = Vec::with_capacity((x1 - x0) * (y1 - y0));
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let color = from_premultiplied(composite[(y - y0) * (x1 - x0) + (x - x0)]);
+                pixels.push((color, selection.value(x, y)));
+            }
+        }
+        
+        BrushTip::from_pixels(get_text("selection", self.language), x1 - x0, y1 - y0, &pixels)
+            .ok_or_else(|| get_text("no_selection", self.language))
+    }
+    
     fn update_texture(&mut self, ctx: &egui::Context) {
         if self.texture_dirty {
             let width = self.current_state.width;
             let height = self.current_state.height;
+            let playing = self.animation_panel.playback.playing;
+            
+            if self.animation_panel.onion_skin.needs_build() && !playing {
+                let state = &mut self.current_state;
+                let composites = self.animation_panel.onion_skin
+                    .neighbours(state.current_frame, state.frames.len())
+                    .into_iter()
+                    .map(|(frame, distance, previous)| (state.frame_composite(frame), distance, previous))
+                    .collect();
+                self.animation_panel.onion_skin.build(width * height, composites);
+            }
+            let onion = self.animation_panel.onion_skin.overlay().filter(|_| !playing).unwrap_or(&[]);
             
-            let mut image_data = vec![0_u8; width * height * 4];
+            let composite = self.current_state.composite();
+            let mut pixels = Vec::with_capacity(width * height);
             
             for y in 0..height {
                 for x in 0..width {
-                    let color = if let Some(pixel) = self.current_state.get(x, y) {
-                        pixel
-                    } else {
-                        let checker_x = x / CHECKERBOARD_SIZE;
-                        let checker_y = y / CHECKERBOARD_SIZE;
-                        if (checker_x + checker_y) % 2 == 0 {
-                            Color32::from_gray(200)
-                        } else {
-                            Color32::from_gray(160)
-                        }
-                    };
+                    let checker_x = x / CHECKERBOARD_SIZE;
+                    let checker_y = y / CHECKERBOARD_SIZE;
+                    let checker = if (checker_x + checker_y) % 2 == 0 { 200.0 } else { 160.0 };
                     
-                    let idx = (y * width + x) * 4;
-                    image_data[idx] = color.r();
-                    image_data[idx + 1] = color.g();
-                    image_data[idx + 2] = color.b();
-                    image_data[idx + 3] = color.a();
+                    let background = blend_over([checker, checker, checker, 255.0], onion.get(y * width + x).copied().unwrap_or([0.0; 4]));
+                    let color = blend_over(background, composite[y * width + x]);
+                    let channel = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+                    pixels.push(Color32::from_rgb(channel(color[0]), channel(color[1]), channel(color[2])));
                 }
             }
             
-            let color_image = egui::ColorImage::from_rgba_unmultiplied([width, height], &image_data);
+            let color_image = egui::ColorImage {
+                size: [width, height],
+                pixels,
+            };
             self.texture = Some(ctx.load_texture("canvas", color_image, TextureOptions::NEAREST));
             
             self.texture_dirty = false;
@@ -1210,6 +3235,34 @@ enum LayerAction {
     ToggleVisibility(usize),
     SetActive(usize),
     Edit(usize),
+    Drop(usize, LayerDrop),
+    ToggleGroupVisibility(u32),
+    ToggleGroupCollapsed(u32),
+    Ungroup(u32),
+    AddMask(usize),
+    DeleteMask(usize),
+    ApplyMask(usize),
+    Duplicate(usize),
+    MergeDown(usize),
+    MergeVisible,
+    Flatten,
+    BackgroundColorLayer,
+    AddAdjustment(Adjustment),
+    ToggleLock(usize, LayerLock),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LayerLock {
+    Pixels,
+    Alpha,
+    Position,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LayerDrop {
+    Above(usize),
+    IntoGroup(u32),
+    Bottom,
 }
 
 struct MyApp {
@@ -1218,6 +3271,7 @@ struct MyApp {
     show_error: bool,
     rename_layer_index: Option<usize>,
     rename_layer_name: String,
+    dragged_layer: Option<usize>,
     pending_action: PendingAction,
     language: Language,
 }
@@ -1230,6 +3284,7 @@ impl Default for MyApp {
             show_error: false,
             rename_layer_index: None,
             rename_layer_name: String::new(),
+            dragged_layer: None,
             pending_action: PendingAction::None,
             language: Language::French,
         }
@@ -1294,6 +3349,60 @@ impl eframe::App for MyApp {
                                 self.rename_layer_index = Some(*idx);
                                 self.rename_layer_name = layer.name.clone();
                             }
+                        },
+                        LayerAction::Drop(idx, target) => {
+                            paint_app.drop_layer(*idx, *target);
+                        },
+                        LayerAction::ToggleGroupVisibility(id) => {
+                            if let Some(group) = paint_app.current_state.groups.iter_mut().find(|group| group.id == *id) {
+                                group.visible = !group.visible;
+                                paint_app.texture_dirty = true;
+                                paint_app.has_unsaved_changes = true;
+                            }
+                        },
+                        LayerAction::ToggleGroupCollapsed(id) => {
+                            if let Some(group) = paint_app.current_state.groups.iter_mut().find(|group| group.id == *id) {
+                                group.collapsed = !group.collapsed;
+                            }
+                        },
+                        LayerAction::Ungroup(id) => {
+                            paint_app.ungroup(*id);
+                        },
+                        LayerAction::AddMask(idx) => {
+                            paint_app.add_layer_mask(*idx);
+                        },
+                        LayerAction::DeleteMask(idx) => {
+                            paint_app.delete_layer_mask(*idx);
+                        },
+                        LayerAction::ApplyMask(idx) => {
+                            paint_app.apply_layer_mask(*idx);
+                        },
+                        LayerAction::Duplicate(idx) => {
+                            paint_app.duplicate_layer(*idx);
+                        },
+                        LayerAction::MergeDown(idx) => {
+                            if let Err(e) = paint_app.merge_layer_down(*idx) {
+                                self.error_message = Some(e);
+                                self.show_error = true;
+                            }
+                        },
+                        LayerAction::MergeVisible => {
+                            if let Err(e) = paint_app.merge_visible_layers() {
+                                self.error_message = Some(e);
+                                self.show_error = true;
+                            }
+                        },
+                        LayerAction::Flatten => {
+                            paint_app.flatten_image();
+                        },
+                        LayerAction::BackgroundColorLayer => {
+                            paint_app.add_background_color_layer();
+                        },
+                        LayerAction::AddAdjustment(adjustment) => {
+                            paint_app.add_adjustment_layer(adjustment.clone());
+                        },
+                        LayerAction::ToggleLock(idx, lock) => {
+                            paint_app.toggle_layer_lock(*idx, *lock);
                         }
                     }
                 }
@@ -1365,6 +3474,16 @@ impl eframe::App for MyApp {
                         shift && ctx.input(|i| i.key_pressed(egui::Key::Z)) {
                         self.pending_action = PendingAction::RedoAction;
                     }
+                    if ctx.input(|i| i.key_pressed(egui::Key::D)) {
+                        paint_app.selection = None;
+                    }
+                    // The panel lists the stack top-first, so raising a layer on screen means a higher index.
+                    if ctx.input(|i| i.key_pressed(egui::Key::PageUp)) {
+                        paint_app.move_layer_down(paint_app.current_state.active_layer_index);
+                    }
+                    if ctx.input(|i| i.key_pressed(egui::Key::PageDown)) {
+                        paint_app.move_layer_up(paint_app.current_state.active_layer_index);
+                    }
                     if ctx.input(|i| i.key_pressed(egui::Key::S)) {
                         if let Some(_) = &paint_app.last_save_path {
                             match paint_app.quick_save() {
@@ -1386,7 +3505,7 @@ impl eframe::App for MyApp {
                                 .add_filter("Rustique File", &["rustiq"])
                                 .set_directory("/")
                                 .save_file() {
-                                match paint_app.save_file(path.to_str().unwrap()) {
+                                match paint_app.request_save(path.to_str().unwrap()) {
                                     Ok(_) => {},
                                     Err(e) => {
                                         self.error_message = Some(e);
@@ -1396,6 +3515,8 @@ impl eframe::App for MyApp {
                             }
                         }
                     }
+                } else if !ctx.wants_keyboard_input() && ctx.input(|i| i.key_pressed(egui::Key::X)) {
+                    paint_app.swap_colors();
                 }
                 
                 match &mut paint_app.save_dialog {
@@ -1452,6 +3573,52 @@ impl eframe::App for MyApp {
                     }
                 }
                 
+                if let Some(path) = paint_app.pending_export.clone() {
+                    let format = PaintApp::detect_format(&path).get_image_format();
+                    let indexed_document = paint_app.current_state.color_mode.palette().is_some();
+                    egui::Window::new(get_text("export_options", self.language))
+                        .collapsible(false)
+                        .resizable(false)
+                        .show(ctx, |ui| {
+                            ui.label(RustiqueTheme::muted_text(&path));
+                            if let Some(format) = format {
+                                let options = paint_app.export_settings.options_mut(format);
+                                options.ui(ui, format, indexed_document, self.language);
+                                
+                                let options = options.clone();
+                                let state = &paint_app.current_state;
+                                let preview = paint_app.export_preview.get_or_insert_with(|| {
+                                    let palette = state.color_mode.palette().map(|palette| palette.colors.clone());
+                                    ExportPreview::new(state.width, state.height, state.composite_rgba(), palette)
+                                });
+                                if let Some(wait) = preview.update(ctx, format, &options, self.language) {
+                                    ctx.request_repaint_after(wait);
+                                }
+                                ui.separator();
+                                preview.ui(ui, self.language);
+                            }
+                            ui.horizontal(|ui| {
+                                if ui.button(get_text("export", self.language)).clicked() {
+                                    paint_app.pending_export = None;
+                                    paint_app.export_preview = None;
+                                    let result = paint_app.save_file(&path)
+                                        .and_then(|_| paint_app.export_settings.save(self.language));
+                                    if let Err(e) = result {
+                                        self.error_message = Some(e);
+                                        self.show_error = true;
+                                    }
+                                }
+                                if ui.button(get_text("cancel", self.language)).clicked() {
+                                    paint_app.pending_export = None;
+                                    paint_app.export_preview = None;
+                                }
+                            });
+                        });
+                }
+                
+                if let Some(remaining) = paint_app.advance_playback() {
+                    ctx.request_repaint_after(remaining);
+                }
                 paint_app.update_texture(ctx);
 
                 egui::SidePanel::left("layers_panel")
@@ -1502,45 +3669,150 @@ impl eframe::App for MyApp {
                                     
                                     ui.add_space(RustiqueTheme::SPACING_SM);
                                     
-                                    let up_btn = ui.add(
+                                    let group_btn = ui.add(
                                         egui::Button::new("")
                                             .fill(RustiqueTheme::SURFACE_SECONDARY)
                                             .stroke(egui::Stroke::new(1.0, RustiqueTheme::BORDER_LIGHT))
                                             .rounding(RustiqueTheme::rounding_small())
                                             .min_size(btn_size)
                                     );
-                                    ui.put(up_btn.rect, ToolIcons::move_up());
-                                    if up_btn.clicked() {
-                                        paint_app.move_layer_up(paint_app.current_state.active_layer_index);
+                                    ui.put(group_btn.rect, ToolIcons::group());
+                                    if group_btn.clicked() {
+                                        paint_app.create_group();
                                     }
+                                    group_btn.on_hover_text(get_text("new_group", self.language));
+                                });
+                                
+                                ui.add_space(RustiqueTheme::SPACING_XS);
+                                
+                                ui.horizontal(|ui| {
+                                    let btn_size = Vec2::new(32.0, 28.0);
+                                    let active = paint_app.current_state.active_layer_index;
+                                    let operations = [
+                                        (ToolIcons::duplicate(), "duplicate_layer", LayerAction::Duplicate(active)),
+                                        (ToolIcons::merge_down(), "merge_down", LayerAction::MergeDown(active)),
+                                        (ToolIcons::merge_visible(), "merge_visible", LayerAction::MergeVisible),
+                                        (ToolIcons::flatten(), "flatten_image", LayerAction::Flatten),
+                                        (ToolIcons::background_layer(), "layer_from_background", LayerAction::BackgroundColorLayer),
+                                    ];
                                     
-                                    ui.add_space(RustiqueTheme::SPACING_XS);
-                                    
-                                    let down_btn = ui.add(
-                                        egui::Button::new("")
-                                            .fill(RustiqueTheme::SURFACE_SECONDARY)
-                                            .stroke(egui::Stroke::new(1.0, RustiqueTheme::BORDER_LIGHT))
-                                            .rounding(RustiqueTheme::rounding_small())
-                                            .min_size(btn_size)
-                                    );
-                                    ui.put(down_btn.rect, ToolIcons::move_down());
-                                    if down_btn.clicked() {
-                                        paint_app.move_layer_down(paint_app.current_state.active_layer_index);
+                                    for (icon, hover, action) in operations {
+                                        let operation_btn = ui.add(
+                                            egui::Button::new("")
+                                                .fill(RustiqueTheme::SURFACE_SECONDARY)
+                                                .stroke(egui::Stroke::new(1.0, RustiqueTheme::BORDER_LIGHT))
+                                                .rounding(RustiqueTheme::rounding_small())
+                                                .min_size(btn_size)
+                                        );
+                                        ui.put(operation_btn.rect, icon);
+                                        if operation_btn.clicked() {
+                                            self.pending_action = PendingAction::HandleLayerAction(action);
+                                        }
+                                        operation_btn.on_hover_text(get_text(hover, self.language));
+                                        ui.add_space(RustiqueTheme::SPACING_XS);
                                     }
+                                    
+                                    let adjustments = Adjustment::all_adjustments(paint_app.primary_color, paint_app.secondary_color);
+                                    ui.menu_button("◑", |ui| {
+                                        for adjustment in adjustments {
+                                            if ui.button(adjustment.get_name(self.language)).clicked() {
+                                                self.pending_action = PendingAction::HandleLayerAction(
+                                                    LayerAction::AddAdjustment(adjustment)
+                                                );
+                                                ui.close_menu();
+                                            }
+                                        }
+                                    }).response.on_hover_text(get_text("new_adjustment_layer", self.language));
                                 });
                                 
                                 ui.add_space(RustiqueTheme::SPACING_SM);
                                 ui.add_space(RustiqueTheme::SPACING_SM);
                                 
-                                let layers_info: Vec<(usize, String, bool, bool)> = paint_app.current_state.layers
-                                    .iter()
-                                    .enumerate()
-                                    .map(|(i, layer)| (i, layer.name.clone(), layer.visible, i == paint_app.current_state.active_layer_index))
-                                    .collect();
+                                let mut rows = Vec::new();
+                                layer_group::display_rows(&paint_app.current_state.layer_tree(), &paint_app.current_state.groups, 0, &mut rows);
+                                let mut drop_targets: Vec<(LayerDrop, Rect)> = Vec::new();
                                 
-                                for (i, name, visible, is_active) in layers_info.iter().rev() {
-                                    RustiqueTheme::card_frame().show(ui, |ui| {
+                                for (row, depth) in rows {
+                                    let indent = depth as f32 * 12.0;
+                                    
+                                    let (i, name, visible, is_active, opacity) = match row {
+                                        LayerRow::Layer(index) => {
+                                            let layer = &paint_app.current_state.layers[index];
+                                            (index, layer.name.clone(), layer.visible, index == paint_app.current_state.active_layer_index, layer.opacity)
+                                        },
+                                        LayerRow::Group(id) => {
+                                            let Some(group) = paint_app.current_state.groups.iter_mut().find(|group| group.id == id) else {
+                                                continue;
+                                            };
+                                            
+                                            let frame = RustiqueTheme::card_frame().show(ui, |ui| {
+                                                ui.horizontal(|ui| {
+                                                    ui.add_space(indent);
+                                                    
+                                                    if ui.small_button(if group.collapsed { "▶" } else { "▼" }).clicked() {
+                                                        self.pending_action = PendingAction::HandleLayerAction(
+                                                            LayerAction::ToggleGroupCollapsed(id)
+                                                        );
+                                                    }
+                                                    
+                                                    let visibility_btn = ui.add(
+                                                        egui::Button::new("")
+                                                            .fill(Color32::TRANSPARENT)
+                                                            .stroke(egui::Stroke::new(1.0, RustiqueTheme::BORDER_LIGHT))
+                                                            .rounding(RustiqueTheme::rounding_small())
+                                                            .min_size(Vec2::new(28.0, 28.0))
+                                                    );
+                                                    ui.put(visibility_btn.rect, if group.visible {
+                                                        ToolIcons::layer_visible()
+                                                    } else {
+                                                        ToolIcons::layer_hidden()
+                                                    });
+                                                    if visibility_btn.clicked() {
+                                                        self.pending_action = PendingAction::HandleLayerAction(
+                                                            LayerAction::ToggleGroupVisibility(id)
+                                                        );
+                                                    }
+                                                    
+                                                    if ui.add(egui::TextEdit::singleline(&mut group.name).desired_width(ui.available_width() - 36.0)).changed() {
+                                                        paint_app.has_unsaved_changes = true;
+                                                    }
+                                                    
+                                                    if ui.small_button("⇱").on_hover_text(get_text("ungroup", self.language)).clicked() {
+                                                        self.pending_action = PendingAction::HandleLayerAction(
+                                                            LayerAction::Ungroup(id)
+                                                        );
+                                                    }
+                                                });
+                                                
+                                                ui.horizontal(|ui| {
+                                                    ui.add_space(indent);
+                                                    let opacity = ui.add(egui::Slider::new(&mut group.opacity, 0.0..=1.0).show_value(false))
+                                                        .on_hover_text(get_text("opacity", self.language));
+                                                    let mut blend_changed = false;
+                                                    egui::ComboBox::from_id_source(("group_blend", id))
+                                                        .selected_text(group.blend.get_name(self.language))
+                                                        .width(90.0)
+                                                        .show_ui(ui, |ui| {
+                                                            for mode in GroupBlend::all_modes() {
+                                                                blend_changed |= ui.selectable_value(&mut group.blend, mode, mode.get_name(self.language)).changed();
+                                                            }
+                                                        });
+                                                    if opacity.changed() || blend_changed {
+                                                        paint_app.texture_dirty = true;
+                                                        paint_app.has_unsaved_changes = true;
+                                                    }
+                                                });
+                                            });
+                                            drop_targets.push((LayerDrop::IntoGroup(id), frame.response.rect));
+                                            ui.add_space(RustiqueTheme::SPACING_XS);
+                                            continue;
+                                        },
+                                    };
+                                    
+                                    let frame = RustiqueTheme::card_frame().show(ui, |ui| {
                                         ui.horizontal(|ui| {
+                                            ui.add_space(indent);
+                                            
                                             let visibility_btn = ui.add(
                                                 egui::Button::new("")
                                                     .fill(Color32::TRANSPARENT)
@@ -1549,7 +3821,7 @@ impl eframe::App for MyApp {
                                                     .min_size(Vec2::new(28.0, 28.0))
                                             );
                                             
-                                            ui.put(visibility_btn.rect, if *visible { 
+                                            ui.put(visibility_btn.rect, if visible { 
                                                 ToolIcons::layer_visible() 
                                             } else { 
                                                 ToolIcons::layer_hidden() 
@@ -1557,34 +3829,51 @@ impl eframe::App for MyApp {
                                             
                                             if visibility_btn.clicked() {
                                                 self.pending_action = PendingAction::HandleLayerAction(
-                                                    LayerAction::ToggleVisibility(*i)
+                                                    LayerAction::ToggleVisibility(i)
                                                 );
                                             }
                                             
                                             ui.add_space(RustiqueTheme::SPACING_XS);
                                             
+                                            let (width, height) = (paint_app.current_state.width, paint_app.current_state.height);
+                                            let layer = &mut paint_app.current_state.layers[i];
+                                            let shows_mask = layer.adjustment.is_some();
+                                            let (thumbnail, thumbnail_size) = layer.thumbnail.texture(ctx, &format!("layer_thumbnail_{}", i), width, height, |idx| {
+                                                match layer.mask.as_ref().filter(|_| shows_mask) {
+                                                    Some(mask) => Some(mask.as_color(idx)),
+                                                    None => layer.data[idx],
+                                                }
+                                            });
+                                            ui.image(thumbnail, thumbnail_size);
+                                            
+                                            ui.add_space(RustiqueTheme::SPACING_XS);
+                                            
                                             let layer_btn = ui.add(
-                                                egui::Button::new(RustiqueTheme::body_text(name))
-                                                    .fill(if *is_active { 
+                                                egui::Button::new(RustiqueTheme::body_text(&name))
+                                                    .fill(if is_active { 
                                                         RustiqueTheme::ACCENT_PRIMARY.linear_multiply(0.3) 
                                                     } else { 
                                                         Color32::TRANSPARENT 
                                                     })
                                                     .stroke(egui::Stroke::new(
-                                                        if *is_active { 2.0 } else { 1.0 },
-                                                        if *is_active { 
+                                                        if is_active { 2.0 } else { 1.0 },
+                                                        if is_active { 
                                                             RustiqueTheme::ACCENT_PRIMARY 
                                                         } else { 
                                                             RustiqueTheme::BORDER_LIGHT 
                                                         }
                                                     ))
                                                     .rounding(RustiqueTheme::rounding_small())
-                                                    .min_size(Vec2::new(ui.available_width() - 60.0, 28.0))
-                                            );
+                                                    .min_size(Vec2::new(ui.available_width() - 72.0, 28.0))
+                                            ).interact(egui::Sense::drag());
+                                            
+                                            if layer_btn.drag_started() {
+                                                self.dragged_layer = Some(i);
+                                            }
                                             
                                             if layer_btn.clicked() {
                                                 self.pending_action = PendingAction::HandleLayerAction(
-                                                    LayerAction::SetActive(*i)
+                                                    LayerAction::SetActive(i)
                                                 );
                                             }
                                             
@@ -1599,14 +3888,148 @@ impl eframe::App for MyApp {
                                                 ui.put(edit_btn.rect, ToolIcons::edit());
                                                 if edit_btn.clicked() {
                                                     self.pending_action = PendingAction::HandleLayerAction(
-                                                        LayerAction::Edit(*i)
+                                                        LayerAction::Edit(i)
                                                     );
                                                 }
+                                                ui.label(RustiqueTheme::muted_text(&format!("{:.0}%", opacity * 100.0)))
+                                                    .on_hover_text(get_text("opacity", self.language));
                                             });
                                         });
+                                        
+                                        if is_active {
+                                            ui.horizontal(|ui| {
+                                                ui.add_space(indent);
+                                                let layer = &mut paint_app.current_state.layers[i];
+                                                let mut changed = false;
+                                                
+                                                match layer.mask.as_mut() {
+                                                    None => {
+                                                        if ui.small_button(get_text("add_mask", self.language)).clicked() {
+                                                            self.pending_action = PendingAction::HandleLayerAction(
+                                                                LayerAction::AddMask(i)
+                                                            );
+                                                        }
+                                                    },
+                                                    Some(mask) => {
+                                                        if ui.selectable_label(paint_app.current_state.editing_mask, get_text("edit_mask", self.language)).clicked() {
+                                                            paint_app.current_state.editing_mask = !paint_app.current_state.editing_mask;
+                                                        }
+                                                        changed |= ui.checkbox(&mut mask.enabled, "").on_hover_text(get_text("mask_enabled", self.language)).changed();
+                                                        if ui.small_button("◑").on_hover_text(get_text("invert_mask", self.language)).clicked() {
+                                                            mask.inverted = !mask.inverted;
+                                                            changed = true;
+                                                        }
+                                                        if ui.small_button("✔").on_hover_text(get_text("apply_mask", self.language)).clicked() {
+                                                            self.pending_action = PendingAction::HandleLayerAction(
+                                                                LayerAction::ApplyMask(i)
+                                                            );
+                                                        }
+                                                        if ui.small_button("✖").on_hover_text(get_text("delete_mask", self.language)).clicked() {
+                                                            self.pending_action = PendingAction::HandleLayerAction(
+                                                                LayerAction::DeleteMask(i)
+                                                            );
+                                                        }
+                                                    },
+                                                }
+                                                
+                                                changed |= ui.checkbox(&mut layer.clipped, get_text("clipping_mask", self.language)).changed();
+                                                if changed {
+                                                    layer.style.invalidate();
+                                                    paint_app.texture_dirty = true;
+                                                    paint_app.has_unsaved_changes = true;
+                                                }
+                                            });
+                                            
+                                            ui.horizontal(|ui| {
+                                                ui.add_space(indent);
+                                                let layer = &mut paint_app.current_state.layers[i];
+                                                let opacity = ui.add(egui::Slider::new(&mut layer.opacity, 0.0..=1.0).text(get_text("opacity", self.language)));
+                                                if opacity.changed() {
+                                                    paint_app.texture_dirty = true;
+                                                    paint_app.has_unsaved_changes = true;
+                                                }
+                                            });
+                                            
+                                            ui.horizontal(|ui| {
+                                                ui.add_space(indent);
+                                                ui.label(RustiqueTheme::muted_text(&get_text("lock", self.language)));
+                                                let layer = &paint_app.current_state.layers[i];
+                                                let locks = [
+                                                    (LayerLock::Pixels, layer.lock_pixels, "🔒", "lock_pixels"),
+                                                    (LayerLock::Alpha, layer.lock_alpha, "α", "lock_alpha"),
+                                                    (LayerLock::Position, layer.lock_position, "✥", "lock_position"),
+                                                ];
+                                                for (lock, locked, label, hover) in locks {
+                                                    if ui.selectable_label(locked, label).on_hover_text(get_text(hover, self.language)).clicked() {
+                                                        self.pending_action = PendingAction::HandleLayerAction(
+                                                            LayerAction::ToggleLock(i, lock)
+                                                        );
+                                                    }
+                                                }
+                                            });
+                                            
+                                            let layer = &mut paint_app.current_state.layers[i];
+                                            if let Some(adjustment) = layer.adjustment.as_mut() {
+                                                ui.add_space(RustiqueTheme::SPACING_XS);
+                                                if adjustment.settings_ui(ui, &format!("adjustment_{}", i), self.language) {
+                                                    paint_app.texture_dirty = true;
+                                                    paint_app.has_unsaved_changes = true;
+                                                }
+                                            } else {
+                                                let style_changed = egui::CollapsingHeader::new(get_text("layer_style", self.language))
+                                                    .id_source(format!("layer_style_{}", i))
+                                                    .show(ui, |ui| layer.style.settings_ui(ui, &format!("layer_style_{}", i), self.language))
+                                                    .body_returned
+                                                    .unwrap_or(false);
+                                                if style_changed {
+                                                    paint_app.texture_dirty = true;
+                                                    paint_app.has_unsaved_changes = true;
+                                                }
+                                            }
+                                        }
                                     });
+                                    drop_targets.push((LayerDrop::Above(i), frame.response.rect));
                                     ui.add_space(RustiqueTheme::SPACING_XS);
                                 }
+                                
+                                if let Some(dragged) = self.dragged_layer {
+                                    let pointer = ctx.input(|input| input.pointer.hover_pos());
+                                    let hovered = pointer.and_then(|pos| {
+                                        drop_targets
+                                            .iter()
+                                            .find(|(_, rect)| rect.contains(pos))
+                                            .map(|(target, rect)| (*target, *rect))
+                                            .or_else(|| {
+                                                let below_all = drop_targets.last().is_some_and(|(_, rect)| pos.y > rect.bottom());
+                                                below_all.then(|| (LayerDrop::Bottom, Rect::from_min_size(pos, Vec2::ZERO)))
+                                            })
+                                    });
+                                    
+                                    if let Some((_, rect)) = hovered.filter(|(target, _)| *target != LayerDrop::Bottom) {
+                                        ui.painter().rect_stroke(rect, RustiqueTheme::rounding_small(), Stroke::new(2.0, RustiqueTheme::ACCENT_PRIMARY));
+                                    }
+                                    
+                                    if let (Some(pos), Some(layer)) = (pointer, paint_app.current_state.layers.get(dragged)) {
+                                        egui::Area::new("dragged_layer")
+                                            .order(egui::Order::Tooltip)
+                                            .fixed_pos(pos + Vec2::new(12.0, 4.0))
+                                            .interactable(false)
+                                            .show(ctx, |ui| {
+                                                RustiqueTheme::card_frame().show(ui, |ui| {
+                                                    ui.label(RustiqueTheme::body_text(&layer.name));
+                                                });
+                                            });
+                                    }
+                                    
+                                    if ctx.input(|input| input.pointer.any_released()) {
+                                        if let Some((target, _)) = hovered {
+                                            self.pending_action = PendingAction::HandleLayerAction(
+                                                LayerAction::Drop(dragged, target)
+                                            );
+                                        }
+                                        self.dragged_layer = None;
+                                    }
+                                }
                             });
                         });
                     });
@@ -1743,11 +4166,103 @@ impl eframe::App for MyApp {
                                         .rounding(RustiqueTheme::rounding_small())
                                         .min_size(tool_size)
                                 );
-                                ui.put(line_btn.rect, ToolIcons::line());
-                                if line_btn.clicked() {
-                                    paint_app.current_tool = Tool::Line;
+                                ui.put(line_btn.rect, ToolIcons::line());
+                                if line_btn.clicked() {
+                                    paint_app.current_tool = Tool::Line;
+                                }
+                                line_btn.on_hover_text("Line Tool");
+                                
+                                ui.add_space(RustiqueTheme::SPACING_XS);
+                                
+                                let select_btn = ui.add(
+                                    RustiqueTheme::tool_button(paint_app.current_tool == Tool::Select)
+                                        .min_size(tool_size)
+                                );
+                                ui.put(select_btn.rect, ToolIcons::select());
+                                if select_btn.clicked() {
+                                    paint_app.current_tool = Tool::Select;
+                                }
+                                select_btn.on_hover_text(get_text("select_tool", self.language));
+                                
+                                ui.add_space(RustiqueTheme::SPACING_XS);
+                                
+                                let smudge_btn = ui.add(
+                                    RustiqueTheme::tool_button(paint_app.current_tool == Tool::Smudge)
+                                        .min_size(tool_size)
+                                );
+                                ui.put(smudge_btn.rect, ToolIcons::smudge());
+                                if smudge_btn.clicked() {
+                                    paint_app.current_tool = Tool::Smudge;
+                                }
+                                smudge_btn.on_hover_text(get_text("smudge_tool", self.language));
+                                
+                                ui.add_space(RustiqueTheme::SPACING_XS);
+                                
+                                let clone_btn = ui.add(
+                                    RustiqueTheme::tool_button(paint_app.current_tool == Tool::CloneStamp)
+                                        .min_size(tool_size)
+                                );
+                                ui.put(clone_btn.rect, ToolIcons::clone_stamp());
+                                if clone_btn.clicked() {
+                                    paint_app.current_tool = Tool::CloneStamp;
+                                }
+                                clone_btn.on_hover_text(get_text("clone_stamp_tool", self.language));
+                                
+                                ui.add_space(RustiqueTheme::SPACING_XS);
+                                
+                                let heal_btn = ui.add(
+                                    RustiqueTheme::tool_button(paint_app.current_tool == Tool::Heal)
+                                        .min_size(tool_size)
+                                );
+                                ui.put(heal_btn.rect, ToolIcons::heal());
+                                if heal_btn.clicked() {
+                                    paint_app.current_tool = Tool::Heal;
+                                }
+                                heal_btn.on_hover_text(get_text("heal_tool", self.language));
+                                
+                                ui.add_space(RustiqueTheme::SPACING_XS);
+                                
+                                let gradient_btn = ui.add(
+                                    RustiqueTheme::tool_button(paint_app.current_tool == Tool::Gradient)
+                                        .min_size(tool_size)
+                                );
+                                ui.put(gradient_btn.rect, ToolIcons::gradient());
+                                if gradient_btn.clicked() {
+                                    paint_app.current_tool = Tool::Gradient;
+                                }
+                                gradient_btn.on_hover_text(get_text("gradient_tool", self.language));
+                                
+                                ui.add_space(RustiqueTheme::SPACING_XS);
+                                
+                                let pen_btn = ui.add(
+                                    RustiqueTheme::tool_button(paint_app.current_tool == Tool::Pen)
+                                        .min_size(tool_size)
+                                );
+                                ui.put(pen_btn.rect, ToolIcons::pen());
+                                if pen_btn.clicked() {
+                                    paint_app.current_tool = Tool::Pen;
+                                }
+                                pen_btn.on_hover_text(get_text("pen_tool", self.language));
+                                
+                                let pencil_btn = ui.add(
+                                    RustiqueTheme::tool_button(paint_app.current_tool == Tool::Pencil)
+                                        .min_size(tool_size)
+                                );
+                                ui.put(pencil_btn.rect, ToolIcons::pencil());
+                                if pencil_btn.clicked() {
+                                    paint_app.current_tool = Tool::Pencil;
                                 }
-                                line_btn.on_hover_text("Line Tool");
+                                pencil_btn.on_hover_text(get_text("pencil_tool", self.language));
+                                
+                                let shade_btn = ui.add(
+                                    RustiqueTheme::tool_button(paint_app.current_tool == Tool::Shade)
+                                        .min_size(tool_size)
+                                );
+                                ui.put(shade_btn.rect, ToolIcons::shade());
+                                if shade_btn.clicked() {
+                                    paint_app.current_tool = Tool::Shade;
+                                }
+                                shade_btn.on_hover_text(get_text("shade_tool", self.language));
                                 
                                 ui.add_space(RustiqueTheme::SPACING_MD);
                                 
@@ -1817,8 +4332,24 @@ impl eframe::App for MyApp {
                                                 ui.add_space(RustiqueTheme::SPACING_XS);
                                                 
                                                 if paint_app.brush_manager.brush_selector_grid(ui, ctx, self.language) {
+                                                    if let Some(size) = paint_app.brush_manager.pending_size.take() {
+                                                        paint_app.brush_size = (size.round() as i32).clamp(1, 500);
+                                                    }
                                                     paint_app.brush_manager.current_size = paint_app.brush_size as f32;
                                                 }
+                                                
+                                                if paint_app.brush_manager.pending_tip_from_selection {
+                                                    paint_app.brush_manager.pending_tip_from_selection = false;
+                                                    match paint_app.brush_tip_from_selection() {
+                                                        Ok(tip) => paint_app.brush_manager.set_active_tip(Some(tip)),
+                                                        Err(e) => paint_app.brush_manager.pending_error = Some(e),
+                                                    }
+                                                }
+                                                
+                                                if let Some(e) = paint_app.brush_manager.pending_error.take() {
+                                                    self.error_message = Some(e);
+                                                    self.show_error = true;
+                                                }
                                             });
                                         });
                                         
@@ -1856,6 +4387,216 @@ impl eframe::App for MyApp {
                                                         .suffix("x"));
                                                 });
                                                 
+                                                ui.horizontal(|ui| {
+                                                    ui.checkbox(&mut paint_app.wrap_mode, get_text("wrap_mode", self.language))
+                                                        .on_hover_text(get_text("wrap_mode_hint", self.language));
+                                                    ui.checkbox(&mut paint_app.tiled_view, get_text("tiled_view", self.language));
+                                                });
+                                                
+                                                ui.horizontal(|ui| {
+                                                    ui.checkbox(&mut paint_app.pixel_art.show_pixel_grid, get_text("pixel_grid", self.language));
+                                                    ui.add(egui::DragValue::new(&mut paint_app.pixel_art.grid_min_zoom)
+                                                        .speed(0.1)
+                                                        .clamp_range(2.0..=64.0)
+                                                        .suffix("px"))
+                                                        .on_hover_text(get_text("pixel_grid_threshold", self.language));
+                                                });
+                                                
+                                                ui.horizontal(|ui| {
+                                                    ui.checkbox(&mut paint_app.pixel_art.show_tile_grid, get_text("tile_grid", self.language));
+                                                    if paint_app.pixel_art.show_tile_grid {
+                                                        ui.add(egui::DragValue::new(&mut paint_app.pixel_art.tile_width).clamp_range(1..=512));
+                                                        ui.label("×");
+                                                        ui.add(egui::DragValue::new(&mut paint_app.pixel_art.tile_height).clamp_range(1..=512));
+                                                    }
+                                                });
+                                                
+                                                if matches!(paint_app.current_tool, Tool::Pencil | Tool::Shade) {
+                                                    ui.add_space(RustiqueTheme::SPACING_XS);
+                                                    
+                                                    ui.horizontal(|ui| {
+                                                        ui.label(RustiqueTheme::muted_text(&format!("{}:", get_text("pencil_size", self.language))));
+                                                        ui.add(egui::DragValue::new(&mut paint_app.pixel_art.pencil_size)
+                                                            .clamp_range(1..=64)
+                                                            .suffix("px"));
+                                                    });
+                                                    
+                                                    if paint_app.current_tool == Tool::Pencil {
+                                                        ui.checkbox(&mut paint_app.pixel_art.pixel_perfect, get_text("pixel_perfect", self.language));
+                                                    } else {
+                                                        ui.checkbox(&mut paint_app.pixel_art.shade_dither, get_text("shade_dither", self.language));
+                                                        ui.label(RustiqueTheme::muted_text(&get_text("shade_tool_hint", self.language)));
+                                                    }
+                                                }
+                                                
+                                                if matches!(paint_app.current_tool, Tool::Brush | Tool::Eraser) {
+                                                    ui.add_space(RustiqueTheme::SPACING_XS);
+                                                    
+                                                    ui.horizontal(|ui| {
+                                                        ui.label(RustiqueTheme::muted_text(&format!("{}:", get_text("symmetry", self.language))));
+                                                        egui::ComboBox::from_id_source("symmetry_mode")
+                                                            .selected_text(paint_app.symmetry.mode.get_name(self.language))
+                                                            .show_ui(ui, |ui| {
+                                                                for mode in SymmetryMode::all_modes() {
+                                                                    ui.selectable_value(&mut paint_app.symmetry.mode, mode, mode.get_name(self.language));
+                                                                }
+                                                            });
+                                                    });
+                                                    
+                                                    if matches!(paint_app.symmetry.mode, SymmetryMode::Radial | SymmetryMode::Kaleidoscope) {
+                                                        ui.horizontal(|ui| {
+                                                            ui.label(RustiqueTheme::muted_text(&format!("{}:", get_text("symmetry_segments", self.language))));
+                                                            ui.add(egui::Slider::new(&mut paint_app.symmetry.radial_count, 2..=32));
+                                                        });
+                                                    }
+                                                    
+                                                    if paint_app.symmetry.is_active() {
+                                                        ui.horizontal(|ui| {
+                                                            ui.checkbox(&mut paint_app.symmetry.show_guides, get_text("symmetry_show_guides", self.language));
+                                                            if ui.button(get_text("symmetry_reset_center", self.language)).clicked() {
+                                                                paint_app.symmetry.center = None;
+                                                            }
+                                                        });
+                                                        ui.label(RustiqueTheme::muted_text(&get_text("symmetry_center_hint", self.language)));
+                                                    }
+                                                }
+                                                
+                                                if paint_app.current_tool == Tool::Smudge {
+                                                    ui.add_space(RustiqueTheme::SPACING_XS);
+                                                    
+                                                    ui.horizontal(|ui| {
+                                                        ui.label(RustiqueTheme::muted_text(&format!("{}:", get_text("smudge_strength", self.language))));
+                                                        ui.add(egui::Slider::new(&mut paint_app.smudge_strength, 0.0..=1.0));
+                                                    });
+                                                    
+                                                    ui.checkbox(&mut paint_app.finger_painting, get_text("finger_painting", self.language));
+                                                }
+                                                
+                                                if paint_app.current_tool == Tool::Pen {
+                                                    ui.add_space(RustiqueTheme::SPACING_XS);
+                                                    
+                                                    let mut delete_path = None;
+                                                    for (index, path) in paint_app.pen_tool.paths.iter().enumerate() {
+                                                        ui.horizontal(|ui| {
+                                                            let active = paint_app.pen_tool.active_path == Some(index);
+                                                            if ui.selectable_label(active, &path.name).clicked() {
+                                                                paint_app.pen_tool.active_path = Some(index);
+                                                            }
+                                                            if ui.small_button("✖").clicked() {
+                                                                delete_path = Some(index);
+                                                            }
+                                                        });
+                                                    }
+                                                    if let Some(index) = delete_path {
+                                                        paint_app.pen_tool.delete_path(index);
+                                                    }
+                                                    
+                                                    if let Some(index) = paint_app.pen_tool.active_path {
+                                                        ui.horizontal(|ui| {
+                                                            if ui.button(get_text("stroke_path", self.language)).clicked() {
+                                                                paint_app.stroke_vector_path(index);
+                                                                paint_app.save_state();
+                                                            }
+                                                            if ui.button(get_text("fill_path", self.language)).clicked() {
+                                                                paint_app.fill_vector_path(index);
+                                                                paint_app.save_state();
+                                                            }
+                                                        });
+                                                        if ui.button(get_text("path_to_selection", self.language)).clicked() {
+                                                            paint_app.vector_path_to_selection(index);
+                                                        }
+                                                    }
+                                                    
+                                                    ui.checkbox(&mut paint_app.pen_tool.taper, get_text("pressure_taper", self.language));
+                                                    if paint_app.pen_tool.taper {
+                                                        ui.add(egui::Slider::new(&mut paint_app.pen_tool.taper_length, 0.01..=0.5));
+                                                    }
+                                                    ui.label(RustiqueTheme::muted_text(&get_text("pen_tool_hint", self.language)));
+                                                }
+                                                
+                                                if paint_app.current_tool == Tool::Gradient {
+                                                    ui.add_space(RustiqueTheme::SPACING_XS);
+                                                    
+                                                    let (primary, secondary) = (paint_app.primary_color, paint_app.secondary_color);
+                                                    if let Some(e) = paint_app.gradient_tool.settings_ui(ui, ctx, primary, secondary, self.language) {
+                                                        self.error_message = Some(e);
+                                                        self.show_error = true;
+                                                    }
+                                                }
+                                                
+                                                if matches!(paint_app.current_tool, Tool::CloneStamp | Tool::Heal) {
+                                                    ui.add_space(RustiqueTheme::SPACING_XS);
+                                                    
+                                                    ui.checkbox(&mut paint_app.clone_aligned, get_text("clone_aligned", self.language));
+                                                    let source_text = match paint_app.clone_source {
+                                                        Some((sx, sy)) => format!("{}: {}, {}", get_text("clone_source", self.language), sx, sy),
+                                                        None => get_text("clone_source_hint", self.language),
+                                                    };
+                                                    ui.label(RustiqueTheme::muted_text(&source_text));
+                                                }
+                                                
+                                                if matches!(paint_app.current_tool, Tool::Smudge | Tool::ColorPicker | Tool::CloneStamp | Tool::Heal) {
+                                                    ui.add_space(RustiqueTheme::SPACING_XS);
+                                                    
+                                                    ui.horizontal(|ui| {
+                                                        ui.label(RustiqueTheme::muted_text(&format!("{}:", get_text("sample", self.language))));
+                                                        egui::ComboBox::from_id_source("sample_source")
+                                                            .selected_text(paint_app.sample_source.get_name(self.language))
+                                                            .show_ui(ui, |ui| {
+                                                                for source in SampleSource::all_sources() {
+                                                                    ui.selectable_value(&mut paint_app.sample_source, source, source.get_name(self.language));
+                                                                }
+                                                            });
+                                                    });
+                                                }
+                                                
+                                                ui.add_space(RustiqueTheme::SPACING_MD);
+                                                ui.separator();
+                                                ui.add_space(RustiqueTheme::SPACING_XS);
+                                                
+                                                ui.label(RustiqueTheme::body_text(&get_text("stabilizer", self.language)));
+                                                ui.add_space(RustiqueTheme::SPACING_XS);
+                                                
+                                                ui.horizontal(|ui| {
+                                                    ui.label(RustiqueTheme::muted_text(&format!("{}:", get_text("mode", self.language))));
+                                                    egui::ComboBox::from_id_source("stabilizer_mode")
+                                                        .selected_text(paint_app.stabilizer.mode.get_name(self.language))
+                                                        .show_ui(ui, |ui| {
+                                                            for mode in StabilizerMode::all_modes() {
+                                                                ui.selectable_value(&mut paint_app.stabilizer.mode, mode, mode.get_name(self.language));
+                                                            }
+                                                        });
+                                                });
+                                                
+                                                match paint_app.stabilizer.mode {
+                                                    StabilizerMode::MovingAverage => {
+                                                        ui.horizontal(|ui| {
+                                                            ui.label(RustiqueTheme::muted_text(&format!("{}:", get_text("stabilizer_window", self.language))));
+                                                            ui.add(egui::Slider::new(&mut paint_app.stabilizer.window_size, 2..=32));
+                                                        });
+                                                    },
+                                                    StabilizerMode::PulledString => {
+                                                        ui.horizontal(|ui| {
+                                                            ui.label(RustiqueTheme::muted_text(&format!("{}:", get_text("stabilizer_radius", self.language))));
+                                                            ui.add(egui::Slider::new(&mut paint_app.stabilizer.string_radius, 1.0..=200.0)
+                                                                .suffix("px"));
+                                                        });
+                                                    },
+                                                    _ => {}
+                                                }
+                                                
+                                                if paint_app.stabilizer.mode != StabilizerMode::None {
+                                                    ui.checkbox(&mut paint_app.stabilizer.catch_up, get_text("catch_up_stroke_end", self.language));
+                                                }
+                                                
+                                                ui.add_space(RustiqueTheme::SPACING_MD);
+                                                ui.separator();
+                                                ui.add_space(RustiqueTheme::SPACING_XS);
+                                                
+                                                ui.collapsing(get_text("brush_dynamics", self.language), |ui| {
+                                                    paint_app.brush_manager.active_brush_mut().dynamics.settings_ui(ui, self.language);
+                                                });
+                                                
                                                 ui.add_space(RustiqueTheme::SPACING_MD);
                                                 ui.separator();
                                                 ui.add_space(RustiqueTheme::SPACING_XS);
@@ -1871,6 +4612,68 @@ impl eframe::App for MyApp {
                                                 if paint_app.pressure_enabled {
                                                     ui.add_space(RustiqueTheme::SPACING_XS);
                                                     
+                                                    let mut selected_backend = paint_app.pressure_input.backend;
+                                                    ui.horizontal(|ui| {
+                                                        ui.label(RustiqueTheme::muted_text(&format!("{}:", get_text("pressure_source", self.language))));
+                                                        egui::ComboBox::from_id_source("pressure_backend")
+                                                            .selected_text(selected_backend.get_name(self.language))
+                                                            .show_ui(ui, |ui| {
+                                                                for backend in PressureBackend::all_backends() {
+                                                                    ui.selectable_value(&mut selected_backend, backend, backend.get_name(self.language));
+                                                                }
+                                                            });
+                                                    });
+                                                    
+                                                    let switching = selected_backend != paint_app.pressure_input.backend;
+                                                    let pick_replay = selected_backend == PressureBackend::Replay &&
+                                                        ((switching && paint_app.pressure_input.replay_path.is_none()) ||
+                                                        ui.button(get_text("choose_replay_file", self.language)).clicked());
+                                                    let result = if pick_replay {
+                                                        FileDialog::new()
+                                                            .add_filter(&get_text("pressure_replay_files", self.language), &["txt"])
+                                                            .pick_file()
+                                                            .map_or(Ok(()), |path| paint_app.pressure_input.load_replay(path, self.language))
+                                                    } else if switching {
+                                                        paint_app.pressure_input.set_backend(selected_backend, self.language)
+                                                    } else {
+                                                        Ok(())
+                                                    };
+                                                    if let Err(e) = result {
+                                                        paint_app.pressure_input.fall_back_to_velocity();
+                                                        self.error_message = Some(e);
+                                                        self.show_error = true;
+                                                    }
+                                                    
+                                                    if let Some(description) = paint_app.pressure_input.description() {
+                                                        ui.label(RustiqueTheme::muted_text(&description));
+                                                    }
+                                                    
+                                                    if let Some(sample) = paint_app.pressure_input.last_sample {
+                                                        ui.label(RustiqueTheme::muted_text(&format!("{}: {:.2} / {:.2}{}",
+                                                            get_text("tilt", self.language), sample.tilt_x, sample.tilt_y,
+                                                            if sample.eraser { format!(" - {}", get_text("eraser", self.language)) } else { String::new() })));
+                                                    }
+                                                    
+                                                    if paint_app.pressure_input.backend == PressureBackend::Evdev {
+                                                        let recording = paint_app.pressure_input.is_recording();
+                                                        let button_label = if recording { "stop_pressure_recording" } else { "record_pressure" };
+                                                        let clicked = ui.button(get_text(button_label, self.language)).clicked();
+                                                        
+                                                        if let Some(e) = (clicked && recording).then(|| paint_app.pressure_input.stop_recording(self.language).err()).flatten() {
+                                                            self.error_message = Some(e);
+                                                            self.show_error = true;
+                                                        }
+                                                        
+                                                        if let Some(path) = (clicked && !recording).then(|| FileDialog::new()
+                                                            .add_filter(&get_text("pressure_replay_files", self.language), &["txt"])
+                                                            .set_file_name("pressure_replay.txt")
+                                                            .save_file()).flatten() {
+                                                            paint_app.pressure_input.start_recording(path);
+                                                        }
+                                                    }
+                                                    
+                                                    ui.add_space(RustiqueTheme::SPACING_XS);
+                                                    
                                                     ui.horizontal(|ui| {
                                                         ui.label(RustiqueTheme::muted_text(&format!("{}:", get_text("smoothing", self.language))));
                                                         ui.add(egui::Slider::new(&mut paint_app.pressure_smoothing, 0.0..=1.0)
@@ -1915,6 +4718,7 @@ impl eframe::App for MyApp {
                                                     
                                                     ui.add_space(RustiqueTheme::SPACING_XS);
                                                     
+                                                    let current_pressure = paint_app.current_pressure;
                                                     let active_brush = paint_app.brush_manager.active_brush_mut();
                                                     
                                                     ui.horizontal(|ui| {
@@ -1928,6 +4732,9 @@ impl eframe::App for MyApp {
                                                             ui.add(egui::Slider::new(&mut active_brush.pressure_size_min, 0.1..=1.0)
                                                                 .suffix("%"));
                                                         });
+                                                        ui.collapsing(get_text("size_curve", self.language), |ui| {
+                                                            curve_editor(ui, &mut active_brush.size_curve, Some(current_pressure), "size_curve", self.language);
+                                                        });
                                                     }
                                                     
                                                     ui.horizontal(|ui| {
@@ -1941,6 +4748,20 @@ impl eframe::App for MyApp {
                                                             ui.add(egui::Slider::new(&mut active_brush.pressure_opacity_min, 0.0..=1.0)
                                                                 .suffix("%"));
                                                         });
+                                                        ui.collapsing(get_text("opacity_curve", self.language), |ui| {
+                                                            curve_editor(ui, &mut active_brush.opacity_curve, Some(current_pressure), "opacity_curve", self.language);
+                                                        });
+                                                    }
+                                                    
+                                                    ui.horizontal(|ui| {
+                                                        ui.checkbox(&mut active_brush.pressure_affects_flow, 
+                                                                   get_text("affects_flow", self.language));
+                                                    });
+                                                    
+                                                    if active_brush.pressure_affects_flow {
+                                                        ui.collapsing(get_text("flow_curve", self.language), |ui| {
+                                                            curve_editor(ui, &mut active_brush.flow_curve, Some(current_pressure), "flow_curve", self.language);
+                                                        });
                                                     }
                                                 }
                                             });
@@ -1954,36 +4775,59 @@ impl eframe::App for MyApp {
                                                 ui.add_space(RustiqueTheme::SPACING_XS);
                                                 
                                                 ui.horizontal(|ui| {
-                                                    ui.label(RustiqueTheme::muted_text(&get_text("primary", self.language)));
-                                                    ui.color_edit_button_srgba(&mut paint_app.primary_color);
-                                                    let add_primary_btn = ui.add(
-                                                        egui::Button::new("")
-                                                            .fill(RustiqueTheme::SURFACE_SECONDARY)
-                                                            .rounding(RustiqueTheme::rounding_small())
-                                                            .min_size(Vec2::new(24.0, 24.0))
-                                                    );
-                                                    ui.put(add_primary_btn.rect, ToolIcons::add());
-                                                    if add_primary_btn.clicked() {
-                                                        paint_app.add_saved_color(paint_app.primary_color);
+                                                    for secondary in [false, true] {
+                                                        let color = if secondary { paint_app.secondary_color } else { paint_app.primary_color };
+                                                        let selected = paint_app.using_secondary_color == secondary;
+                                                        let swatch = ui.add(
+                                                            egui::Button::new("")
+                                                                .fill(color)
+                                                                .stroke(egui::Stroke::new(
+                                                                    if selected { 2.0 } else { 1.0 },
+                                                                    if selected { RustiqueTheme::ACCENT_PRIMARY } else { RustiqueTheme::BORDER_LIGHT }
+                                                                ))
+                                                                .rounding(RustiqueTheme::rounding_small())
+                                                                .min_size(Vec2::new(28.0, 28.0))
+                                                        );
+                                                        if swatch.clicked() {
+                                                            paint_app.using_secondary_color = secondary;
+                                                        }
+                                                        swatch.on_hover_text(get_text(if secondary { "secondary" } else { "primary" }, self.language));
+
+                                                        let add_btn = ui.add(
+                                                            egui::Button::new("")
+                                                                .fill(RustiqueTheme::SURFACE_SECONDARY)
+                                                                .rounding(RustiqueTheme::rounding_small())
+                                                                .min_size(Vec2::new(24.0, 24.0))
+                                                        );
+                                                        ui.put(add_btn.rect, ToolIcons::add());
+                                                        if add_btn.clicked() {
+                                                            paint_app.add_saved_color(color);
+                                                        }
+                                                    }
+
+                                                    if ui.button("⇄").on_hover_text(get_text("swap_colors", self.language)).clicked() {
+                                                        paint_app.swap_colors();
                                                     }
                                                 });
-                                                
+
                                                 ui.add_space(RustiqueTheme::SPACING_XS);
-                                                
-                                                ui.horizontal(|ui| {
-                                                    ui.label(RustiqueTheme::muted_text(&get_text("secondary", self.language)));
-                                                    ui.color_edit_button_srgba(&mut paint_app.secondary_color);
-                                                    let add_secondary_btn = ui.add(
-                                                        egui::Button::new("")
-                                                            .fill(RustiqueTheme::SURFACE_SECONDARY)
-                                                            .rounding(RustiqueTheme::rounding_small())
-                                                            .min_size(Vec2::new(24.0, 24.0))
-                                                    );
-                                                    ui.put(add_secondary_btn.rect, ToolIcons::add());
-                                                    if add_secondary_btn.clicked() {
-                                                        paint_app.add_saved_color(paint_app.secondary_color);
+
+                                                let active_color = if paint_app.using_secondary_color {
+                                                    &mut paint_app.secondary_color
+                                                } else {
+                                                    &mut paint_app.primary_color
+                                                };
+                                                paint_app.color_panel.ui(ui, active_color, self.language);
+
+                                                if !paint_app.color_panel.recent.is_empty() {
+                                                    ui.add_space(RustiqueTheme::SPACING_XS);
+                                                    ui.label(RustiqueTheme::muted_text(&get_text("recent_colors", self.language)));
+                                                    match paint_app.color_panel.recent_strip(ui) {
+                                                        Some((color, false)) => paint_app.primary_color = color,
+                                                        Some((color, true)) => paint_app.secondary_color = color,
+                                                        None => {},
                                                     }
-                                                });
+                                                }
                                             });
                                         });
                                         
@@ -2037,6 +4881,69 @@ impl eframe::App for MyApp {
                                         
                                         ui.add_space(RustiqueTheme::SPACING_MD);
                                         
+                                        RustiqueTheme::card_frame().show(ui, |ui| {
+                                            ui.vertical(|ui| {
+                                                ui.label(RustiqueTheme::body_text(&get_text("palettes", self.language)));
+                                                ui.add_space(RustiqueTheme::SPACING_XS);
+                                                
+                                                let active_color = if paint_app.using_secondary_color { paint_app.secondary_color } else { paint_app.primary_color };
+                                                let result = match paint_app.palette_panel.ui(ui, active_color, self.language) {
+                                                    PaletteAction::None => Ok(()),
+                                                    PaletteAction::SetPrimary(color) => {
+                                                        paint_app.primary_color = color;
+                                                        Ok(())
+                                                    },
+                                                    PaletteAction::SetSecondary(color) => {
+                                                        paint_app.secondary_color = color;
+                                                        Ok(())
+                                                    },
+                                                    PaletteAction::ExtractFromCanvas => {
+                                                        let pixels = paint_app.current_state.composite_rgba();
+                                                        paint_app.palette_panel.extract(&pixels, self.language)
+                                                    },
+                                                    PaletteAction::Error(e) => Err(e),
+                                                };
+                                                if let Err(e) = result {
+                                                    self.error_message = Some(e);
+                                                    self.show_error = true;
+                                                }
+                                            });
+                                        });
+                                        
+                                        ui.add_space(RustiqueTheme::SPACING_MD);
+                                        
+                                        RustiqueTheme::card_frame().show(ui, |ui| {
+                                            ui.vertical(|ui| {
+                                                ui.label(RustiqueTheme::body_text(&get_text("color_mode", self.language)));
+                                                ui.add_space(RustiqueTheme::SPACING_XS);
+                                                
+                                                let action = paint_app.indexed_panel.ui(ui, &paint_app.current_state.color_mode, self.language);
+                                                match action {
+                                                    IndexedAction::None => {},
+                                                    IndexedAction::ConvertToIndexed(source, color_count) => {
+                                                        if let Err(e) = paint_app.convert_to_indexed(source, color_count) {
+                                                            self.error_message = Some(e);
+                                                            self.show_error = true;
+                                                        }
+                                                    },
+                                                    IndexedAction::ConvertToRgba => paint_app.convert_to_rgba(),
+                                                    IndexedAction::EditEntry(index, color, begin) => paint_app.edit_indexed_entry(index, color, begin),
+                                                    IndexedAction::SelectEntry(index, secondary) => {
+                                                        if let Some(palette) = paint_app.current_state.color_mode.palette() {
+                                                            let color = palette.color(index);
+                                                            if secondary {
+                                                                paint_app.secondary_color = color;
+                                                            } else {
+                                                                paint_app.primary_color = color;
+                                                            }
+                                                        }
+                                                    },
+                                                }
+                                            });
+                                        });
+                                        
+                                        ui.add_space(RustiqueTheme::SPACING_MD);
+                                        
                                         RustiqueTheme::card_frame().show(ui, |ui| {
                                             ui.vertical(|ui| {
                                                 ui.label(RustiqueTheme::body_text("Quick Actions"));
@@ -2060,7 +4967,7 @@ impl eframe::App for MyApp {
                                                         .add_filter("Rustique File", &["rustiq"])
                                                         .set_directory("/")
                                                         .save_file() {
-                                                        match paint_app.save_file(path.to_str().unwrap()) {
+                                                        match paint_app.request_save(path.to_str().unwrap()) {
                                                             Ok(_) => {},
                                                             Err(e) => {
                                                                 self.error_message = Some(e);
@@ -2152,6 +5059,46 @@ impl eframe::App for MyApp {
                     }
                 }
 
+                let timeline_action = egui::TopBottomPanel::bottom("timeline_panel")
+                    .frame(RustiqueTheme::panel_frame())
+                    .show(ctx, |ui| {
+                        let durations = paint_app.current_state.frame_durations();
+                        paint_app.animation_panel.ui(ui, &durations, paint_app.current_state.current_frame, self.language)
+                    }).inner;
+                
+                match timeline_action {
+                    TimelineAction::None => {},
+                    TimelineAction::SelectFrame(index) => {
+                        paint_app.stop_playback();
+                        paint_app.select_frame(index);
+                    },
+                    TimelineAction::AddFrame => paint_app.add_frame(),
+                    TimelineAction::DuplicateFrame => paint_app.duplicate_frame(),
+                    TimelineAction::DeleteFrame => paint_app.delete_frame(),
+                    TimelineAction::MoveFrame(offset) => paint_app.move_frame(offset),
+                    TimelineAction::SetDuration(index, duration, begin) => paint_app.set_frame_duration(index, duration, begin),
+                    TimelineAction::TogglePlayback => paint_app.toggle_playback(),
+                    TimelineAction::OnionChanged => {
+                        paint_app.animation_panel.onion_skin.invalidate();
+                        paint_app.texture_dirty = true;
+                    },
+                    TimelineAction::Export(export) => {
+                        paint_app.stop_playback();
+                        let result = if let Some(path) = FileDialog::new()
+                            .add_filter(&export.get_name(self.language), &[export.extension()])
+                            .set_directory("/")
+                            .save_file() {
+                            paint_app.export_animation(path.to_str().unwrap(), export)
+                        } else {
+                            Ok(())
+                        };
+                        if let Err(e) = result {
+                            self.error_message = Some(e);
+                            self.show_error = true;
+                        }
+                    },
+                }
+
                 egui::CentralPanel::default().show(ctx, |ui| {
                     let available_size = ui.available_size();
                     let canvas_width = paint_app.current_state.width as f32;
@@ -2164,10 +5111,29 @@ impl eframe::App for MyApp {
                     );
 
                     let (response, painter) = ui.allocate_painter(ui.available_size(), egui::Sense::click_and_drag());
+                    if response.drag_started() || response.clicked() {
+                        paint_app.stop_playback();
+                    }
 
                     if let Some(texture) = &paint_app.texture {
-                        painter.image(texture.id(), canvas_rect, Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0)), Color32::WHITE);
+                        let uv = Rect::from_min_max(Pos2::ZERO, Pos2::new(1.0, 1.0));
+                        if paint_app.tiled_view {
+                            for row in -1..=1 {
+                                for column in -1..=1 {
+                                    let offset = Vec2::new(column as f32 * canvas_rect.width(), row as f32 * canvas_rect.height());
+                                    painter.image(texture.id(), canvas_rect.translate(offset), uv, Color32::WHITE);
+                                }
+                            }
+                            painter.rect_stroke(canvas_rect, 0.0, Stroke::new(1.0, RustiqueTheme::ACCENT_PRIMARY.linear_multiply(0.6)));
+                        } else {
+                            painter.image(texture.id(), canvas_rect, uv, Color32::WHITE);
+                        }
                     }
+                    
+                    paint_app.brush_manager.wrap_size = paint_app.wrap_mode
+                        .then_some((paint_app.current_state.width, paint_app.current_state.height));
+                    
+                    paint_app.pixel_art.draw_grids(&painter, canvas_rect, paint_app.current_state.width, paint_app.current_state.height);
 
                     let to_canvas = egui::emath::RectTransform::from_to(
                         canvas_rect,
@@ -2177,8 +5143,118 @@ impl eframe::App for MyApp {
                     if response.dragged_by(egui::PointerButton::Middle) {
                         paint_app.pan += response.drag_delta();
                     }
+                    
+                    if let Some((x0, y0, x1, y1)) = paint_app.selection.as_ref().and_then(|s| s.bounds()) {
+                        let from_canvas = to_canvas.inverse();
+                        let min = from_canvas.transform_pos(Pos2::new(x0 as f32, y0 as f32));
+                        let max = from_canvas.transform_pos(Pos2::new(x1 as f32, y1 as f32));
+                        let corners = [min, Pos2::new(max.x, min.y), max, Pos2::new(min.x, max.y), min];
+                        painter.add(egui::Shape::dashed_line(&corners, Stroke::new(1.0, Color32::BLACK), 4.0, 4.0));
+                        painter.add(egui::Shape::dashed_line(&corners[1..], Stroke::new(1.0, Color32::WHITE), 4.0, 4.0));
+                    }
+
+                    let symmetry_pointer = response.interact_pointer_pos().map(|pos| to_canvas.transform_pos(pos));
+                    if let (true, Some(canvas_pos)) = (response.drag_started_by(egui::PointerButton::Primary), symmetry_pointer) {
+                        let handle_radius = Symmetry::center_handle_radius() * canvas_width / canvas_rect.width();
+                        paint_app.symmetry.moving_center = paint_app.symmetry.hits_center(
+                            canvas_pos,
+                            paint_app.current_state.width,
+                            paint_app.current_state.height,
+                            handle_radius,
+                        );
+                    }
 
-                    if paint_app.current_tool == Tool::Line {
+                    if paint_app.symmetry.moving_center {
+                        if let Some(canvas_pos) = symmetry_pointer.filter(|_| response.dragged_by(egui::PointerButton::Primary)) {
+                            paint_app.symmetry.center = Some((
+                                canvas_pos.x.clamp(0.0, canvas_width),
+                                canvas_pos.y.clamp(0.0, canvas_height),
+                            ));
+                        }
+                        if response.drag_released() {
+                            paint_app.symmetry.moving_center = false;
+                        }
+                    } else if paint_app.current_tool == Tool::Pen {
+                        let pointer_pos = response.interact_pointer_pos().map(|pos| to_canvas.transform_pos(pos));
+                        let hit_radius = 6.0 * canvas_width / canvas_rect.width();
+                        
+                        if let (true, Some(canvas_pos)) = (response.drag_started_by(egui::PointerButton::Primary), pointer_pos) {
+                            let name = format!("{} {}", get_text("path", self.language), paint_app.pen_tool.paths.len() + 1);
+                            paint_app.pen_tool.press(canvas_pos, hit_radius, name);
+                        }
+                        
+                        if let (true, Some(canvas_pos)) = (response.dragged_by(egui::PointerButton::Primary), pointer_pos) {
+                            let break_handles = ui.input(|i| i.modifiers.alt);
+                            paint_app.pen_tool.drag_to(canvas_pos, break_handles);
+                        }
+                        
+                        if response.drag_released() {
+                            paint_app.pen_tool.release();
+                        }
+                        
+                        let keyboard_free = !ctx.wants_keyboard_input();
+                        if keyboard_free && ctx.input(|i| i.key_pressed(egui::Key::Enter) || i.key_pressed(egui::Key::Escape)) {
+                            paint_app.pen_tool.finish_path();
+                        }
+                        
+                        if keyboard_free && ctx.input(|i| i.key_pressed(egui::Key::Backspace)) {
+                            paint_app.pen_tool.remove_last_anchor();
+                        }
+                    } else if paint_app.current_tool == Tool::Gradient {
+                        let pointer_pos = response.interact_pointer_pos().map(|pos| to_canvas.transform_pos(pos));
+                        
+                        if let (true, Some(canvas_pos)) = (response.drag_started_by(egui::PointerButton::Primary), pointer_pos) {
+                            paint_app.gradient_tool.drag_start = Some(canvas_pos);
+                            paint_app.gradient_tool.drag_end = Some(canvas_pos);
+                        }
+                        
+                        if let (true, Some(canvas_pos)) = (response.dragged_by(egui::PointerButton::Primary), pointer_pos) {
+                            paint_app.gradient_tool.drag_end = Some(canvas_pos);
+                        }
+                        
+                        if let (Some(start), Some(end)) = (paint_app.gradient_tool.drag_start, paint_app.gradient_tool.drag_end) {
+                            let start_screen = to_canvas.inverse().transform_pos(start);
+                            let end_screen = to_canvas.inverse().transform_pos(end);
+                            painter.line_segment([start_screen, end_screen], Stroke::new(3.0, Color32::BLACK));
+                            painter.line_segment([start_screen, end_screen], Stroke::new(1.0, Color32::WHITE));
+                            painter.circle_filled(start_screen, 4.0, Color32::WHITE);
+                            painter.circle_stroke(end_screen, 4.0, Stroke::new(1.0, Color32::WHITE));
+                            
+                            if response.drag_released() {
+                                paint_app.gradient_tool.drag_start = None;
+                                paint_app.gradient_tool.drag_end = None;
+                                if start.distance(end) >= 1.0 {
+                                    paint_app.apply_gradient(start, end);
+                                    paint_app.save_state();
+                                }
+                            }
+                        }
+                    } else if paint_app.current_tool == Tool::Select {
+                        let pointer_pos = response.interact_pointer_pos().map(|pos| to_canvas.transform_pos(pos));
+                        
+                        if let (true, Some(canvas_pos)) = (response.drag_started_by(egui::PointerButton::Primary), pointer_pos) {
+                            paint_app.selection_start = Some((canvas_pos.x as i32, canvas_pos.y as i32));
+                        }
+                        
+                        if let (true, Some(start), Some(canvas_pos)) = (response.dragged_by(egui::PointerButton::Primary), paint_app.selection_start, pointer_pos) {
+                            let end = (canvas_pos.x.round() as i32, canvas_pos.y.round() as i32);
+                            let selection = Selection::rectangle(
+                                paint_app.current_state.width,
+                                paint_app.current_state.height,
+                                start,
+                                end,
+                            );
+                            paint_app.selection = if selection.is_empty() { None } else { Some(selection) };
+                        }
+                        
+                        if response.drag_released() {
+                            paint_app.selection_start = None;
+                        }
+                        
+                        if response.clicked_by(egui::PointerButton::Primary) {
+                            paint_app.selection = None;
+                        }
+                    } else if paint_app.current_tool == Tool::Line {
                         if response.clicked() && !response.clicked_by(egui::PointerButton::Middle) {
                             let is_secondary = response.clicked_by(egui::PointerButton::Secondary);
                             if let Some(pos) = response.interact_pointer_pos() {
@@ -2259,8 +5335,14 @@ impl eframe::App for MyApp {
                                 paint_app.update_pressure_from_velocity(pos, current_time);
                                 
                                 let canvas_pos = to_canvas.transform_pos(pos);
-                                let x = canvas_pos.x as usize;
-                                let y = canvas_pos.y as usize;
+                                let (x, y) = if paint_app.wrap_mode {
+                                    (
+                                        canvas_pos.x.rem_euclid(canvas_width) as usize,
+                                        canvas_pos.y.rem_euclid(canvas_height) as usize,
+                                    )
+                                } else {
+                                    (canvas_pos.x as usize, canvas_pos.y as usize)
+                                };
                                 let is_secondary = response.dragged_by(egui::PointerButton::Secondary) || 
                                                  response.clicked_by(egui::PointerButton::Secondary);
                                 
@@ -2268,22 +5350,24 @@ impl eframe::App for MyApp {
                                     match paint_app.current_tool {
                                         Tool::PaintBucket => paint_app.paint_bucket(x, y, is_secondary),
                                         Tool::ColorPicker => paint_app.pick_color(x, y, is_secondary),
+                                        Tool::CloneStamp | Tool::Heal if ui.input(|i| i.modifiers.alt) => {
+                                            paint_app.set_clone_source(x as i32, y as i32);
+                                        },
+                                        Tool::Shade => {
+                                            paint_app.pixel_art.shade_backwards = is_secondary;
+                                            paint_app.continue_stroke(canvas_pos.x, canvas_pos.y, paint_app.primary_color);
+                                        },
                                         _ => {
-                                            let (x, y) = (canvas_pos.x as i32, canvas_pos.y as i32);
-                                            if let Some(last_pos) = paint_app.last_position {
-                                                paint_app.draw_line(last_pos, (x, y), 
+                                            paint_app.continue_stroke(canvas_pos.x, canvas_pos.y,
                                                                   if is_secondary { paint_app.secondary_color } 
                                                                   else { paint_app.primary_color });
-                                            } else {
-                                                paint_app.draw_point(x, y, is_secondary);
-                                            }
-                                            paint_app.last_position = Some((x, y));
                                         }
                                     }
                                     paint_app.is_drawing = true;
                                 }
                             }
                         } else {
+                            paint_app.end_stroke();
                             paint_app.save_state();
                             paint_app.last_position = None;
                             paint_app.last_cursor_pos = None;
@@ -2291,6 +5375,45 @@ impl eframe::App for MyApp {
                         }
                     }
 
+                    if !paint_app.pen_tool.paths.is_empty() {
+                        let from_canvas = to_canvas.inverse();
+                        paint_app.pen_tool.draw_overlay(&painter, |p| from_canvas.transform_pos(p), RustiqueTheme::ACCENT_PRIMARY);
+                    }
+                    
+                    let from_canvas = to_canvas.inverse();
+                    paint_app.symmetry.draw_guides(
+                        &painter,
+                        paint_app.current_state.width,
+                        paint_app.current_state.height,
+                        |p| from_canvas.transform_pos(p),
+                    );
+                    
+                    let cloning = matches!(paint_app.current_tool, Tool::CloneStamp | Tool::Heal);
+                    if let (true, Some((source_x, source_y)), Some(pos)) = (cloning, paint_app.clone_source, response.hover_pos()) {
+                        let stroke_offset = paint_app.clone_offset
+                            .filter(|_| paint_app.clone_aligned || paint_app.last_position.is_some());
+                        let source = match stroke_offset {
+                            Some((offset_x, offset_y)) => {
+                                let hover = to_canvas.transform_pos(pos);
+                                Pos2::new(hover.x + offset_x as f32, hover.y + offset_y as f32)
+                            },
+                            None => Pos2::new(source_x as f32, source_y as f32),
+                        };
+                        let source_screen = to_canvas.inverse().transform_pos(source);
+                        let radius = (paint_app.brush_size as f32 * canvas_rect.width() / canvas_width).max(4.0);
+                        painter.circle_stroke(source_screen, radius, Stroke::new(1.5, Color32::BLACK));
+                        painter.circle_stroke(source_screen, radius, Stroke::new(1.0, Color32::WHITE));
+                        painter.line_segment([source_screen - Vec2::new(radius * 0.5, 0.0), source_screen + Vec2::new(radius * 0.5, 0.0)], Stroke::new(1.0, Color32::WHITE));
+                        painter.line_segment([source_screen - Vec2::new(0.0, radius * 0.5), source_screen + Vec2::new(0.0, radius * 0.5)], Stroke::new(1.0, Color32::WHITE));
+                    }
+                    
+                    if let (Some((ax, ay)), Some(pos)) = (paint_app.stabilizer.anchor(), response.hover_pos()) {
+                        let anchor = to_canvas.inverse().transform_pos(Pos2::new(ax, ay));
+                        let radius = paint_app.stabilizer.string_radius * canvas_rect.width() / canvas_width;
+                        painter.circle_stroke(anchor, radius, Stroke::new(1.0, RustiqueTheme::ACCENT_PRIMARY.linear_multiply(0.6)));
+                        painter.line_segment([anchor, pos], Stroke::new(1.0, RustiqueTheme::ACCENT_PRIMARY));
+                    }
+
                     let delta = ui.input(|i| i.scroll_delta.y);
                     if delta != 0.0 {
                         let zoom_speed = 0.001;
@@ -0,0 +1,865 @@
+use eframe::egui;
+use egui::{Color32, Stroke, Vec2};
+use rfd::FileDialog;
+use serde::{Serialize, Deserialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::color_space::{hsv_to_rgb, lab_to_rgb, parse_color, to_hex};
+use crate::localization::{Language, get_text};
+
+const PALETTE_LIBRARY_PATH: &str = "palettes.json";
+const MAX_EXTRACTION_SAMPLES: usize = 65536;
+const KMEANS_ITERATIONS: usize = 12;
+const SWATCH_SIZE: f32 = 20.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaletteColor {
+    #[serde(default)]
+    pub name: String,
+    pub color: [u8; 4],
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+    pub name: String,
+    pub colors: Vec<PaletteColor>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PaletteFormat {
+    Gpl,
+    Ase,
+    Aco,
+    PaintNet,
+    Hex,
+}
+
+impl PaletteFormat {
+    pub fn from_path(path: &Path) -> Option<PaletteFormat> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "gpl" => Some(PaletteFormat::Gpl),
+            "ase" => Some(PaletteFormat::Ase),
+            "aco" => Some(PaletteFormat::Aco),
+            "txt" => Some(PaletteFormat::PaintNet),
+            "hex" => Some(PaletteFormat::Hex),
+            _ => None,
+        }
+    }
+
+    pub fn all_formats() -> Vec<PaletteFormat> {
+        vec![
+            PaletteFormat::Gpl,
+            PaletteFormat::Ase,
+            PaletteFormat::Aco,
+            PaletteFormat::PaintNet,
+            PaletteFormat::Hex,
+        ]
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            PaletteFormat::Gpl => "GIMP Palette",
+            PaletteFormat::Ase => "Adobe Swatch Exchange",
+            PaletteFormat::Aco => "Adobe Color Swatch",
+            PaletteFormat::PaintNet => "Paint.NET Palette",
+            PaletteFormat::Hex => "Hex List",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            PaletteFormat::Gpl => "gpl",
+            PaletteFormat::Ase => "ase",
+            PaletteFormat::Aco => "aco",
+            PaletteFormat::PaintNet => "txt",
+            PaletteFormat::Hex => "hex",
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn bytes(&mut self, count: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.position..self.position + count)?;
+        self.position += count;
+        Some(bytes)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.bytes(2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.bytes(4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        self.u32().map(f32::from_bits)
+    }
+
+    fn utf16(&mut self, units: usize) -> Option<String> {
+        let code_units: Vec<u16> = (0..units).map(|_| self.u16()).collect::<Option<_>>()?;
+        Some(String::from_utf16_lossy(&code_units).trim_end_matches('\0').to_string())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.position >= self.data.len()
+    }
+}
+
+fn push_utf16(out: &mut Vec<u8>, text: &str) {
+    for unit in text.encode_utf16().chain(std::iter::once(0)) {
+        out.extend_from_slice(&unit.to_be_bytes());
+    }
+}
+
+fn channel(value: f32) -> u8 {
+    (value * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+fn opaque(rgb: [f32; 3]) -> [u8; 4] {
+    [channel(rgb[0]), channel(rgb[1]), channel(rgb[2]), 255]
+}
+
+fn cmyk_to_rgb(c: f32, m: f32, y: f32, k: f32) -> [f32; 3] {
+    [(1.0 - c) * (1.0 - k), (1.0 - m) * (1.0 - k), (1.0 - y) * (1.0 - k)]
+}
+
+impl Palette {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            colors: Vec::new(),
+        }
+    }
+
+    fn from_colors(name: String, colors: impl IntoIterator<Item = [u8; 4]>) -> Self {
+        Self {
+            name,
+            colors: colors.into_iter().map(|color| PaletteColor { name: String::new(), color }).collect(),
+        }
+    }
+
+    pub fn load(path: &Path, language: Language) -> Result<Palette, String> {
+        let format = PaletteFormat::from_path(path)
+            .ok_or_else(|| format!("{}: {}", get_text("palette_format_not_supported", language), path.display()))?;
+        let data = fs::read(path)
+            .map_err(|e| format!("{}: {}", get_text("error_loading_palette", language), e))?;
+        let file_name = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default().to_string();
+
+        let palette = match format {
+            PaletteFormat::Gpl => Self::parse_gpl(&String::from_utf8_lossy(&data), file_name),
+            PaletteFormat::Ase => Self::parse_ase(&data, file_name),
+            PaletteFormat::Aco => Self::parse_aco(&data, file_name),
+            PaletteFormat::PaintNet => Self::parse_paint_net(&String::from_utf8_lossy(&data), file_name),
+            PaletteFormat::Hex => Self::parse_hex_list(&String::from_utf8_lossy(&data), file_name),
+        };
+
+        match palette {
+            Some(palette) if !palette.colors.is_empty() => Ok(palette),
+            _ => Err(format!("{}: {}", get_text("invalid_palette_file", language), path.display())),
+        }
+    }
+
+    pub fn save(&self, path: &Path, language: Language) -> Result<(), String> {
+        let format = PaletteFormat::from_path(path)
+            .ok_or_else(|| format!("{}: {}", get_text("palette_format_not_supported", language), path.display()))?;
+        let data = match format {
+            PaletteFormat::Gpl => self.to_gpl().into_bytes(),
+            PaletteFormat::Ase => self.to_ase(),
+            PaletteFormat::Aco => self.to_aco().ok_or_else(|| {
+                format!("{}: {}", get_text("palette_too_large_for_aco", language), self.colors.len())
+            })?,
+            PaletteFormat::PaintNet => self.to_paint_net().into_bytes(),
+            PaletteFormat::Hex => self.to_hex_list().into_bytes(),
+        };
+        fs::write(path, data)
+            .map_err(|e| format!("{}: {}", get_text("error_saving_palette", language), e))
+    }
+
+    fn parse_gpl(text: &str, fallback_name: String) -> Option<Palette> {
+        let mut lines = text.lines();
+        if !lines.next()?.trim().starts_with("GIMP Palette") {
+            return None;
+        }
+
+        let mut palette = Palette::new(fallback_name);
+        for line in lines {
+            let line = line.trim();
+            if let Some(name) = line.strip_prefix("Name:") {
+                palette.name = name.trim().to_string();
+                continue;
+            }
+            if line.is_empty() || line.starts_with('#') || line.starts_with("Columns:") {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let mut component = || parts.next()?.parse::<u8>().ok();
+            let color = [component()?, component()?, component()?, 255];
+            let name = parts.collect::<Vec<_>>().join(" ");
+            palette.colors.push(PaletteColor { name, color });
+        }
+        Some(palette)
+    }
+
+    fn to_gpl(&self) -> String {
+        let mut text = format!("GIMP Palette\nName: {}\nColumns: 0\n#\n", self.name);
+        for entry in &self.colors {
+            let [r, g, b, _] = entry.color;
+            let name = if entry.name.is_empty() { to_hex([r, g, b, 255]) } else { entry.name.clone() };
+            text.push_str(&format!("{:3} {:3} {:3}\t{}\n", r, g, b, name));
+        }
+        text
+    }
+
+    fn parse_ase(data: &[u8], fallback_name: String) -> Option<Palette> {
+        let mut reader = Reader::new(data);
+        if reader.bytes(4)? != b"ASEF" {
+            return None;
+        }
+        reader.u32()?;
+        let block_count = reader.u32()?;
+
+        let mut palette = Palette::new(fallback_name);
+        for _ in 0..block_count {
+            let block_type = reader.u16()?;
+            let length = reader.u32()? as usize;
+            let mut block = Reader::new(reader.bytes(length)?);
+
+            match block_type {
+                0x0001 => {
+                    let name_length = block.u16()? as usize;
+                    let name = block.utf16(name_length)?;
+                    let model = block.bytes(4)?;
+                    let rgb = match model {
+                        b"RGB " => [block.f32()?, block.f32()?, block.f32()?],
+                        b"CMYK" => cmyk_to_rgb(block.f32()?, block.f32()?, block.f32()?, block.f32()?),
+                        b"LAB " => lab_to_rgb([block.f32()? * 100.0, block.f32()?, block.f32()?]),
+                        b"Gray" => [block.f32()?; 3],
+                        _ => continue,
+                    };
+                    palette.colors.push(PaletteColor { name, color: opaque(rgb) });
+                },
+                0xC001 if palette.colors.is_empty() => {
+                    let name_length = block.u16()? as usize;
+                    let name = block.utf16(name_length)?;
+                    if !name.is_empty() {
+                        palette.name = name;
+                    }
+                },
+                _ => {},
+            }
+        }
+        Some(palette)
+    }
+
+    fn to_ase(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(b"ASEF");
+        out.extend_from_slice(&1u16.to_be_bytes());
+        out.extend_from_slice(&0u16.to_be_bytes());
+        out.extend_from_slice(&(self.colors.len() as u32 + 2).to_be_bytes());
+
+        let mut group = Vec::new();
+        group.extend_from_slice(&(self.name.encode_utf16().count() as u16 + 1).to_be_bytes());
+        push_utf16(&mut group, &self.name);
+        out.extend_from_slice(&0xC001u16.to_be_bytes());
+        out.extend_from_slice(&(group.len() as u32).to_be_bytes());
+        out.extend_from_slice(&group);
+
+        for entry in &self.colors {
+            let name = if entry.name.is_empty() { to_hex(entry.color) } else { entry.name.clone() };
+            let mut block = Vec::new();
+            block.extend_from_slice(&(name.encode_utf16().count() as u16 + 1).to_be_bytes());
+            push_utf16(&mut block, &name);
+            block.extend_from_slice(b"RGB ");
+            for &component in &entry.color[..3] {
+                block.extend_from_slice(&(component as f32 / 255.0).to_be_bytes());
+            }
+            block.extend_from_slice(&2u16.to_be_bytes());
+
+            out.extend_from_slice(&0x0001u16.to_be_bytes());
+            out.extend_from_slice(&(block.len() as u32).to_be_bytes());
+            out.extend_from_slice(&block);
+        }
+
+        out.extend_from_slice(&0xC002u16.to_be_bytes());
+        out.extend_from_slice(&0u32.to_be_bytes());
+        out
+    }
+
+    fn parse_aco(data: &[u8], fallback_name: String) -> Option<Palette> {
+        let mut reader = Reader::new(data);
+        let mut palette = None;
+
+        while !reader.is_empty() {
+            let version = reader.u16()?;
+            let count = reader.u16()?;
+            let mut colors = Vec::with_capacity(count as usize);
+
+            for _ in 0..count {
+                let space = reader.u16()?;
+                let values = [reader.u16()?, reader.u16()?, reader.u16()?, reader.u16()?];
+                let name = if version == 2 {
+                    let length = reader.u32()? as usize;
+                    reader.utf16(length)?
+                } else {
+                    String::new()
+                };
+
+                let unit = |value: u16| value as f32 / 65535.0;
+                let rgb = match space {
+                    0 => [unit(values[0]), unit(values[1]), unit(values[2])],
+                    1 => {
+                        let color = hsv_to_rgb(unit(values[0]), unit(values[1]), unit(values[2]), 255);
+                        [color.r() as f32 / 255.0, color.g() as f32 / 255.0, color.b() as f32 / 255.0]
+                    },
+                    2 => cmyk_to_rgb(1.0 - unit(values[0]), 1.0 - unit(values[1]), 1.0 - unit(values[2]), 1.0 - unit(values[3])),
+                    7 => lab_to_rgb([
+                        values[0] as f32 / 100.0,
+                        values[1] as i16 as f32 / 100.0,
+                        values[2] as i16 as f32 / 100.0,
+                    ]),
+                    8 => [1.0 - values[0] as f32 / 10000.0; 3],
+                    _ => continue,
+                };
+                colors.push(PaletteColor { name, color: opaque(rgb) });
+            }
+
+            palette = Some(Palette { name: fallback_name.clone(), colors });
+        }
+        palette
+    }
+
+    fn to_aco(&self) -> Option<Vec<u8>> {
+        let count = u16::try_from(self.colors.len()).ok()?;
+        let mut out = Vec::new();
+        for version in [1u16, 2] {
+            out.extend_from_slice(&version.to_be_bytes());
+            out.extend_from_slice(&count.to_be_bytes());
+            for entry in &self.colors {
+                out.extend_from_slice(&0u16.to_be_bytes());
+                for &component in &entry.color[..3] {
+                    out.extend_from_slice(&(component as u16 * 257).to_be_bytes());
+                }
+                out.extend_from_slice(&0u16.to_be_bytes());
+                if version == 2 {
+                    let name = if entry.name.is_empty() { to_hex(entry.color) } else { entry.name.clone() };
+                    out.extend_from_slice(&(name.encode_utf16().count() as u32 + 1).to_be_bytes());
+                    push_utf16(&mut out, &name);
+                }
+            }
+        }
+        Some(out)
+    }
+
+    fn parse_paint_net(text: &str, fallback_name: String) -> Option<Palette> {
+        let colors = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with(';'))
+            .map(|line| {
+                if line.len() != 8 {
+                    return None;
+                }
+                let value = u32::from_str_radix(line, 16).ok()?;
+                let [a, r, g, b] = value.to_be_bytes();
+                Some([r, g, b, a])
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Palette::from_colors(fallback_name, colors))
+    }
+
+    fn to_paint_net(&self) -> String {
+        let mut text = format!("; paint.net Palette File\n; {}\n", self.name);
+        for entry in &self.colors {
+            let [r, g, b, a] = entry.color;
+            text.push_str(&format!("{:02X}{:02X}{:02X}{:02X}\n", a, r, g, b));
+        }
+        text
+    }
+
+    fn parse_hex_list(text: &str, fallback_name: String) -> Option<Palette> {
+        let colors = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(parse_color)
+            .collect::<Option<Vec<_>>>()?;
+        Some(Palette::from_colors(fallback_name, colors))
+    }
+
+    fn to_hex_list(&self) -> String {
+        self.colors
+            .iter()
+            .map(|entry| format!("{}\n", to_hex(entry.color).trim_start_matches('#').to_lowercase()))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExtractionMethod {
+    MedianCut,
+    KMeans,
+}
+
+impl ExtractionMethod {
+    pub fn get_name(&self, language: Language) -> String {
+        match self {
+            ExtractionMethod::MedianCut => get_text("extraction_median_cut", language),
+            ExtractionMethod::KMeans => get_text("extraction_kmeans", language),
+        }
+    }
+
+    pub fn all_methods() -> Vec<ExtractionMethod> {
+        vec![ExtractionMethod::MedianCut, ExtractionMethod::KMeans]
+    }
+}
+
+fn average(colors: &[[u8; 3]]) -> [f32; 3] {
+    let mut sum = [0.0; 3];
+    for color in colors {
+        for (total, &component) in sum.iter_mut().zip(color) {
+            *total += component as f32;
+        }
+    }
+    sum.map(|total| total / colors.len().max(1) as f32)
+}
+
+fn widest_channel(colors: &[[u8; 3]]) -> (usize, u8) {
+    (0..3)
+        .map(|c| {
+            let min = colors.iter().map(|color| color[c]).min().unwrap_or(0);
+            let max = colors.iter().map(|color| color[c]).max().unwrap_or(0);
+            (c, max - min)
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap_or((0, 0))
+}
+
+fn median_cut(samples: &mut [[u8; 3]], count: usize) -> Vec<[f32; 3]> {
+    let mut boxes: Vec<&mut [[u8; 3]]> = vec![samples];
+
+    while boxes.len() < count {
+        let candidate = boxes
+            .iter()
+            .enumerate()
+            .map(|(index, colors)| (index, widest_channel(colors)))
+            .filter(|(_, (_, range))| *range > 0)
+            .max_by_key(|(index, (_, range))| *range as usize * boxes[*index].len());
+        let Some((index, (channel, _))) = candidate else {
+            break;
+        };
+
+        let colors = boxes.swap_remove(index);
+        colors.sort_unstable_by_key(|color| color[channel]);
+        let (low, high) = colors.split_at_mut(colors.len() / 2);
+        boxes.push(low);
+        boxes.push(high);
+    }
+
+    boxes.iter().filter(|colors| !colors.is_empty()).map(|colors| average(colors)).collect()
+}
+
+fn kmeans(samples: &[[u8; 3]], mut centers: Vec<[f32; 3]>) -> Vec<[f32; 3]> {
+    let distance = |a: [f32; 3], b: &[u8; 3]| {
+        (0..3).map(|c| (a[c] - b[c] as f32).powi(2)).sum::<f32>()
+    };
+
+    for _ in 0..KMEANS_ITERATIONS {
+        let mut sums = vec![([0.0_f32; 3], 0usize); centers.len()];
+        for sample in samples {
+            let nearest = centers
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| distance(**a, sample).total_cmp(&distance(**b, sample)))
+                .map(|(index, _)| index)
+                .unwrap_or(0);
+            let (sum, count) = &mut sums[nearest];
+            for (total, &component) in sum.iter_mut().zip(sample) {
+                *total += component as f32;
+            }
+            *count += 1;
+        }
+
+        let mut moved = false;
+        for (center, (sum, count)) in centers.iter_mut().zip(sums) {
+            if count == 0 {
+                continue;
+            }
+            let updated = sum.map(|total| total / count as f32);
+            moved |= (0..3).any(|c| (updated[c] - center[c]).abs() > 0.5);
+            *center = updated;
+        }
+        if !moved {
+            break;
+        }
+    }
+    centers
+}
+
+pub fn extract_palette(pixels: &[[u8; 4]], count: usize, method: ExtractionMethod) -> Vec<[u8; 4]> {
+    let opaque_pixels = pixels.iter().filter(|pixel| pixel[3] >= 128);
+    let total = opaque_pixels.clone().count();
+    let step = total.div_ceil(MAX_EXTRACTION_SAMPLES).max(1);
+    let mut samples: Vec<[u8; 3]> = opaque_pixels.step_by(step).map(|pixel| [pixel[0], pixel[1], pixel[2]]).collect();
+    if samples.is_empty() {
+        return Vec::new();
+    }
+
+    let mut centers = median_cut(&mut samples, count.max(1));
+    if method == ExtractionMethod::KMeans {
+        centers = kmeans(&samples, centers);
+    }
+
+    let mut colors: Vec<[u8; 4]> = centers
+        .into_iter()
+        .map(|center| center.map(|v| v.round().clamp(0.0, 255.0) as u8))
+        .map(|[r, g, b]| [r, g, b, 255])
+        .collect();
+    colors.sort_by_key(|&[r, g, b, _]| r as u32 * 299 + g as u32 * 587 + b as u32 * 114);
+    colors.dedup();
+    colors
+}
+
+pub struct PaletteLibrary {
+    pub palettes: Vec<Palette>,
+    library_path: PathBuf,
+}
+
+impl PaletteLibrary {
+    pub fn load_default() -> Self {
+        let library_path = PathBuf::from(PALETTE_LIBRARY_PATH);
+        let palettes = fs::read_to_string(&library_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+
+        Self {
+            palettes,
+            library_path,
+        }
+    }
+
+    pub fn save(&self, language: Language) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(&self.palettes)
+            .map_err(|e| format!("{}: {}", get_text("error_saving_palette", language), e))?;
+        fs::write(&self.library_path, json)
+            .map_err(|e| format!("{}: {}", get_text("error_saving_palette", language), e))
+    }
+
+    fn unique_name(&self, base: &str) -> String {
+        let mut name = base.to_string();
+        let mut suffix = 2;
+        while self.palettes.iter().any(|palette| palette.name == name) {
+            name = format!("{} {}", base, suffix);
+            suffix += 1;
+        }
+        name
+    }
+
+    pub fn add(&mut self, mut palette: Palette) -> usize {
+        palette.name = self.unique_name(&palette.name);
+        self.palettes.push(palette);
+        self.palettes.len() - 1
+    }
+}
+
+pub enum PaletteAction {
+    None,
+    SetPrimary(Color32),
+    SetSecondary(Color32),
+    ExtractFromCanvas,
+    Error(String),
+}
+
+pub struct PalettePanel {
+    pub library: PaletteLibrary,
+    pub active: usize,
+    pub extract_count: usize,
+    pub extract_method: ExtractionMethod,
+}
+
+impl Default for PalettePanel {
+    fn default() -> Self {
+        Self {
+            library: PaletteLibrary::load_default(),
+            active: 0,
+            extract_count: 8,
+            extract_method: ExtractionMethod::KMeans,
+        }
+    }
+}
+
+impl PalettePanel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn active_palette(&self) -> Option<&Palette> {
+        self.library.palettes.get(self.active)
+    }
+
+    fn insert(&mut self, palette: Palette, language: Language) -> Result<(), String> {
+        self.active = self.library.add(palette);
+        self.library.save(language)
+    }
+
+    pub fn extract(&mut self, pixels: &[[u8; 4]], language: Language) -> Result<(), String> {
+        let colors = extract_palette(pixels, self.extract_count, self.extract_method);
+        if colors.is_empty() {
+            return Err(get_text("nothing_to_extract", language));
+        }
+        self.insert(Palette::from_colors(get_text("extracted_palette", language), colors), language)
+    }
+
+    fn import(&mut self, language: Language) -> Result<(), String> {
+        let mut dialog = FileDialog::new()
+            .add_filter("All Palettes", &["gpl", "ase", "aco", "txt", "hex"]);
+        for format in PaletteFormat::all_formats() {
+            dialog = dialog.add_filter(format.get_name(), &[format.extension()]);
+        }
+        match dialog.pick_file() {
+            Some(path) => {
+                let palette = Palette::load(&path, language)?;
+                self.insert(palette, language)
+            },
+            None => Ok(()),
+        }
+    }
+
+    fn export(&self, language: Language) -> Result<(), String> {
+        let Some(palette) = self.active_palette() else {
+            return Ok(());
+        };
+        let mut dialog = FileDialog::new().set_file_name(&format!("{}.gpl", palette.name));
+        for format in PaletteFormat::all_formats() {
+            dialog = dialog.add_filter(format.get_name(), &[format.extension()]);
+        }
+        match dialog.save_file() {
+            Some(path) => palette.save(&path, language),
+            None => Ok(()),
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui, active_color: Color32, language: Language) -> PaletteAction {
+        let mut action = PaletteAction::None;
+        let mut result = Ok(());
+        self.active = self.active.min(self.library.palettes.len().saturating_sub(1));
+
+        ui.horizontal(|ui| {
+            let selected = self.active_palette().map(|palette| palette.name.clone()).unwrap_or_default();
+            egui::ComboBox::from_id_source("palette_select")
+                .selected_text(selected)
+                .width(120.0)
+                .show_ui(ui, |ui| {
+                    for (index, palette) in self.library.palettes.iter().enumerate() {
+                        ui.selectable_value(&mut self.active, index, &palette.name);
+                    }
+                });
+            if ui.small_button("+").on_hover_text(get_text("new_palette", language)).clicked() {
+                result = self.insert(Palette::new(get_text("palette", language)), language);
+            }
+            let has_palette = self.active_palette().is_some();
+            if ui.add_enabled(has_palette, egui::Button::new("✖").small()).on_hover_text(get_text("delete_palette", language)).clicked() {
+                self.library.palettes.remove(self.active);
+                self.active = self.active.saturating_sub(1);
+                result = self.library.save(language);
+            }
+        });
+
+        if let Some(palette) = self.library.palettes.get_mut(self.active) {
+            let name_edit = ui.add(egui::TextEdit::singleline(&mut palette.name)
+                .hint_text(get_text("palette_name", language))
+                .desired_width(ui.available_width()));
+            let mut changed = name_edit.lost_focus();
+
+            let mut remove = None;
+            ui.horizontal_wrapped(|ui| {
+                ui.spacing_mut().item_spacing = Vec2::splat(2.0);
+                for (index, entry) in palette.colors.iter().enumerate() {
+                    let [r, g, b, a] = entry.color;
+                    let color = Color32::from_rgba_unmultiplied(r, g, b, a);
+                    let swatch = ui.add(
+                        egui::Button::new("")
+                            .fill(color)
+                            .stroke(Stroke::new(1.0, Color32::from_gray(90)))
+                            .min_size(Vec2::splat(SWATCH_SIZE))
+                    );
+                    if swatch.clicked() {
+                        action = PaletteAction::SetPrimary(color);
+                    }
+                    if swatch.secondary_clicked() {
+                        action = PaletteAction::SetSecondary(color);
+                    }
+                    if swatch.middle_clicked() {
+                        remove = Some(index);
+                    }
+                    let hex = to_hex(entry.color);
+                    swatch.on_hover_text(if entry.name.is_empty() { hex } else { format!("{} ({})", entry.name, hex) });
+                }
+            });
+            if let Some(index) = remove {
+                palette.colors.remove(index);
+                changed = true;
+            }
+
+            if ui.button(get_text("add_color_to_palette", language)).clicked() {
+                palette.colors.push(PaletteColor { name: String::new(), color: active_color.to_srgba_unmultiplied() });
+                changed = true;
+            }
+            if changed {
+                result = self.library.save(language);
+            }
+        }
+
+        ui.horizontal(|ui| {
+            if ui.button(get_text("import_palette", language)).clicked() {
+                result = self.import(language);
+            }
+            if ui.add_enabled(self.active_palette().is_some(), egui::Button::new(get_text("export_palette", language))).clicked() {
+                result = self.export(language);
+            }
+        });
+
+        ui.collapsing(get_text("extract_palette", language), |ui| {
+            ui.add(egui::Slider::new(&mut self.extract_count, 2..=64).text(get_text("palette_size", language)));
+            egui::ComboBox::from_id_source("palette_extraction_method")
+                .selected_text(self.extract_method.get_name(language))
+                .show_ui(ui, |ui| {
+                    for method in ExtractionMethod::all_methods() {
+                        ui.selectable_value(&mut self.extract_method, method, method.get_name(language));
+                    }
+                });
+            if ui.button(get_text("extract_from_image", language)).clicked() {
+                action = PaletteAction::ExtractFromCanvas;
+            }
+        });
+
+        match result {
+            Err(e) => PaletteAction::Error(e),
+            Ok(()) => action,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_palette() -> Palette {
+        Palette {
+            name: "Sunset".to_string(),
+            colors: vec![
+                PaletteColor { name: "Coral".to_string(), color: [255, 127, 80, 255] },
+                PaletteColor { name: "Night Sky".to_string(), color: [20, 24, 82, 255] },
+                PaletteColor { name: String::new(), color: [0, 0, 0, 255] },
+            ],
+        }
+    }
+
+    fn colors(palette: &Palette) -> Vec<[u8; 4]> {
+        palette.colors.iter().map(|entry| entry.color).collect()
+    }
+
+    #[test]
+    fn gpl_round_trips() {
+        let palette = sample_palette();
+        let parsed = Palette::parse_gpl(&palette.to_gpl(), String::new()).unwrap();
+        assert_eq!(parsed.name, "Sunset");
+        assert_eq!(colors(&parsed), colors(&palette));
+        assert_eq!(parsed.colors[1].name, "Night Sky");
+        assert_eq!(parsed.colors[2].name, "#000000");
+    }
+
+    #[test]
+    fn gpl_parses_gimp_files() {
+        let text = "GIMP Palette\nName: Tiny\nColumns: 4\n# comment\n  0 128 255\tBlue-ish\n\n10 20 30\n";
+        let palette = Palette::parse_gpl(text, "fallback".to_string()).unwrap();
+        assert_eq!(palette.name, "Tiny");
+        assert_eq!(colors(&palette), vec![[0, 128, 255, 255], [10, 20, 30, 255]]);
+        assert_eq!(palette.colors[0].name, "Blue-ish");
+        assert!(Palette::parse_gpl("Not a palette\n1 2 3\n", String::new()).is_none());
+        assert!(Palette::parse_gpl("GIMP Palette\n1 2 300\n", String::new()).is_none());
+    }
+
+    #[test]
+    fn ase_round_trips() {
+        let palette = sample_palette();
+        let parsed = Palette::parse_ase(&palette.to_ase(), String::new()).unwrap();
+        assert_eq!(parsed.name, "Sunset");
+        assert_eq!(colors(&parsed), colors(&palette));
+        assert_eq!(parsed.colors[0].name, "Coral");
+    }
+
+    #[test]
+    fn truncated_ase_is_rejected() {
+        let data = sample_palette().to_ase();
+        assert!(Palette::parse_ase(&data[..data.len() / 2], String::new()).is_none());
+        assert!(Palette::parse_ase(b"ASEX\0\0\0\0", String::new()).is_none());
+    }
+
+    #[test]
+    fn aco_round_trips_with_names() {
+        let palette = sample_palette();
+        let parsed = Palette::parse_aco(&palette.to_aco().unwrap(), "Swatches".to_string()).unwrap();
+        assert_eq!(parsed.name, "Swatches");
+        assert_eq!(colors(&parsed), colors(&palette));
+        assert_eq!(parsed.colors[1].name, "Night Sky");
+    }
+
+    #[test]
+    fn aco_refuses_more_than_u16_colors() {
+        let palette = Palette::from_colors(String::new(), vec![[1, 2, 3, 255]; u16::MAX as usize + 1]);
+        assert!(palette.to_aco().is_none());
+
+        let palette = Palette::from_colors(String::new(), vec![[1, 2, 3, 255]; u16::MAX as usize]);
+        assert!(palette.to_aco().is_some());
+    }
+
+    #[test]
+    fn paint_net_round_trips_alpha() {
+        let palette = Palette::from_colors(String::new(), vec![[255, 0, 0, 128], [1, 2, 3, 255]]);
+        let text = palette.to_paint_net();
+        assert!(text.contains("80FF0000"));
+        let parsed = Palette::parse_paint_net(&text, String::new()).unwrap();
+        assert_eq!(colors(&parsed), colors(&palette));
+        assert!(Palette::parse_paint_net("FF0000\n", String::new()).is_none());
+    }
+
+    #[test]
+    fn hex_list_round_trips() {
+        let palette = Palette::from_colors(String::new(), vec![[255, 128, 0, 255], [0, 0, 0, 64]]);
+        let text = palette.to_hex_list();
+        assert_eq!(text, "ff8000\n00000040\n");
+        let parsed = Palette::parse_hex_list(&text, String::new()).unwrap();
+        assert_eq!(colors(&parsed), colors(&palette));
+        assert!(Palette::parse_hex_list("ff8000\nnope\n", String::new()).is_none());
+    }
+
+    #[test]
+    fn extraction_finds_dominant_colors() {
+        let mut pixels = vec![[250, 10, 10, 255]; 300];
+        pixels.extend(vec![[10, 10, 250, 255]; 300]);
+        pixels.extend(vec![[0, 255, 0, 0]; 300]);
+
+        for method in ExtractionMethod::all_methods() {
+            let extracted = extract_palette(&pixels, 2, method);
+            assert_eq!(extracted.len(), 2);
+            assert!(extracted.contains(&[250, 10, 10, 255]));
+            assert!(extracted.contains(&[10, 10, 250, 255]));
+        }
+        assert!(extract_palette(&[[0, 0, 0, 0]; 4], 4, ExtractionMethod::MedianCut).is_empty());
+    }
+}
@@ -0,0 +1,169 @@
+use eframe::egui;
+use egui::{Color32, Pos2, Rect, Stroke};
+use std::collections::HashSet;
+
+const TILE_GRID_MIN_SPACING: f32 = 4.0;
+
+pub fn bresenham(start: (i32, i32), end: (i32, i32)) -> Vec<(i32, i32)> {
+    let (mut x, mut y) = start;
+    let dx = (end.0 - x).abs();
+    let dy = -(end.1 - y).abs();
+    let sx = if x < end.0 { 1 } else { -1 };
+    let sy = if y < end.1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    let mut points = Vec::with_capacity((dx - dy) as usize + 1);
+
+    loop {
+        points.push((x, y));
+        if (x, y) == end {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+
+    points
+}
+
+pub struct PixelArt {
+    pub pencil_size: u32,
+    pub pixel_perfect: bool,
+    pub shade_dither: bool,
+    pub shade_backwards: bool,
+    pub show_pixel_grid: bool,
+    pub grid_min_zoom: f32,
+    pub show_tile_grid: bool,
+    pub tile_width: u32,
+    pub tile_height: u32,
+    trail: Vec<((i32, i32), Option<Color32>)>,
+    shaded: HashSet<(i32, i32)>,
+}
+
+impl Default for PixelArt {
+    fn default() -> Self {
+        Self {
+            pencil_size: 1,
+            pixel_perfect: true,
+            shade_dither: false,
+            shade_backwards: false,
+            show_pixel_grid: true,
+            grid_min_zoom: 8.0,
+            show_tile_grid: false,
+            tile_width: 16,
+            tile_height: 16,
+            trail: Vec::new(),
+            shaded: HashSet::new(),
+        }
+    }
+}
+
+impl PixelArt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn begin_stroke(&mut self) {
+        self.trail.clear();
+        self.shaded.clear();
+    }
+
+    pub fn footprint(&self, x: i32, y: i32) -> Vec<(i32, i32)> {
+        let size = self.pencil_size.max(1) as i32;
+        let offset = (size - 1) / 2;
+        (0..size)
+            .flat_map(|dy| (0..size).map(move |dx| (x + dx - offset, y + dy - offset)))
+            .collect()
+    }
+
+    pub fn uses_pixel_perfect(&self) -> bool {
+        self.pixel_perfect && self.pencil_size <= 1
+    }
+
+    pub fn push_trail(&mut self, point: (i32, i32), replaced: Option<Color32>) -> Option<((i32, i32), Option<Color32>)> {
+        self.trail.push((point, replaced));
+
+        let n = self.trail.len();
+        if n < 3 {
+            return None;
+        }
+
+        let (a, _) = self.trail[n - 3];
+        let (b, _) = self.trail[n - 2];
+        let (c, _) = self.trail[n - 1];
+        let diagonal = (a.0 - c.0).abs() == 1 && (a.1 - c.1).abs() == 1;
+        let touches = |p: (i32, i32), q: (i32, i32)| (p.0 - q.0).abs() + (p.1 - q.1).abs() == 1;
+
+        if diagonal && touches(a, b) && touches(b, c) {
+            let corner = self.trail.remove(n - 2);
+            return Some(corner);
+        }
+
+        if n > 3 {
+            self.trail.remove(0);
+        }
+        None
+    }
+
+    pub fn is_new_trail_point(&self, point: (i32, i32)) -> bool {
+        self.trail.last().is_none_or(|(last, _)| *last != point)
+    }
+
+    pub fn mark_shaded(&mut self, point: (i32, i32)) -> bool {
+        let in_pattern = !self.shade_dither || (point.0 + point.1).rem_euclid(2) == 0;
+        in_pattern && self.shaded.insert(point)
+    }
+
+    pub fn shade_color(&self, current: Option<Color32>, palette: &[Color32]) -> Option<Option<Color32>> {
+        let current = current?;
+        let index = palette.iter().position(|&entry| entry == current)?;
+        let next = if self.shade_backwards {
+            index.checked_sub(1)?
+        } else {
+            index + 1
+        };
+        palette.get(next).map(|&color| Some(color))
+    }
+
+    pub fn draw_grids(&self, painter: &egui::Painter, canvas_rect: Rect, width: usize, height: usize) {
+        let pixel_size = canvas_rect.width() / width.max(1) as f32;
+        let visible = painter.clip_rect().intersect(canvas_rect);
+        if visible.width() <= 0.0 || visible.height() <= 0.0 {
+            return;
+        }
+
+        let first_x = ((visible.left() - canvas_rect.left()) / pixel_size).floor().max(0.0) as usize;
+        let last_x = (((visible.right() - canvas_rect.left()) / pixel_size).ceil() as usize).min(width);
+        let first_y = ((visible.top() - canvas_rect.top()) / pixel_size).floor().max(0.0) as usize;
+        let last_y = (((visible.bottom() - canvas_rect.top()) / pixel_size).ceil() as usize).min(height);
+
+        let column = |x: usize| canvas_rect.left() + x as f32 * pixel_size;
+        let row = |y: usize| canvas_rect.top() + y as f32 * pixel_size;
+
+        let draw_lines = |step_x: usize, step_y: usize, stroke: Stroke| {
+            for x in (first_x..=last_x).filter(|x| x.is_multiple_of(step_x)) {
+                painter.line_segment([Pos2::new(column(x), visible.top()), Pos2::new(column(x), visible.bottom())], stroke);
+            }
+            for y in (first_y..=last_y).filter(|y| y.is_multiple_of(step_y)) {
+                painter.line_segment([Pos2::new(visible.left(), row(y)), Pos2::new(visible.right(), row(y))], stroke);
+            }
+        };
+
+        if self.show_pixel_grid && pixel_size >= self.grid_min_zoom {
+            draw_lines(1, 1, Stroke::new(1.0, Color32::from_rgba_unmultiplied(128, 128, 128, 90)));
+        }
+
+        let tile_width = self.tile_width.max(1) as usize;
+        let tile_height = self.tile_height.max(1) as usize;
+        let tile_spacing = pixel_size * tile_width.min(tile_height) as f32;
+        if self.show_tile_grid && tile_spacing >= TILE_GRID_MIN_SPACING {
+            draw_lines(tile_width, tile_height, Stroke::new(1.0, Color32::from_rgba_unmultiplied(0, 170, 255, 160)));
+        }
+    }
+}
@@ -0,0 +1,676 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::localization::{Language, get_text};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PressureSample {
+    pub pressure: f32,
+    pub tilt_x: f32,
+    pub tilt_y: f32,
+    pub eraser: bool,
+    pub in_contact: bool,
+}
+
+impl Default for PressureSample {
+    fn default() -> Self {
+        Self {
+            pressure: 0.0,
+            tilt_x: 0.0,
+            tilt_y: 0.0,
+            eraser: false,
+            in_contact: false,
+        }
+    }
+}
+
+pub trait PressureSource: Send {
+    fn description(&self) -> String;
+    fn poll(&mut self) -> Option<PressureSample>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PressureBackend {
+    Velocity,
+    Evdev,
+    Replay,
+}
+
+impl PressureBackend {
+    pub fn get_name(&self, language: Language) -> String {
+        match self {
+            PressureBackend::Velocity => get_text("pressure_backend_velocity", language),
+            PressureBackend::Evdev => get_text("pressure_backend_tablet", language),
+            PressureBackend::Replay => get_text("pressure_backend_replay", language),
+        }
+    }
+
+    pub fn all_backends() -> Vec<PressureBackend> {
+        vec![
+            PressureBackend::Velocity,
+            PressureBackend::Evdev,
+            PressureBackend::Replay,
+        ]
+    }
+}
+
+struct Recording {
+    path: PathBuf,
+    started: Instant,
+    samples: Vec<(u64, PressureSample)>,
+}
+
+pub struct PressureInput {
+    pub backend: PressureBackend,
+    pub replay_path: Option<PathBuf>,
+    pub last_sample: Option<PressureSample>,
+    source: Option<Box<dyn PressureSource>>,
+    recording: Option<Recording>,
+}
+
+impl Default for PressureInput {
+    fn default() -> Self {
+        Self {
+            backend: PressureBackend::Velocity,
+            replay_path: None,
+            last_sample: None,
+            source: None,
+            recording: None,
+        }
+    }
+}
+
+impl PressureInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_backend(&mut self, backend: PressureBackend, language: Language) -> Result<(), String> {
+        self.source = None;
+        self.last_sample = None;
+        self.backend = backend;
+
+        let source: Option<Box<dyn PressureSource>> = match backend {
+            PressureBackend::Velocity => None,
+            PressureBackend::Evdev => Some(Box::new(evdev::EvdevPressureSource::open_first_tablet(language)?)),
+            PressureBackend::Replay => {
+                let path = self.replay_path
+                    .clone()
+                    .ok_or_else(|| get_text("no_replay_file_selected", language))?;
+                Some(Box::new(ReplayPressureSource::load(&path, language)?))
+            },
+        };
+
+        self.source = source;
+        Ok(())
+    }
+
+    pub fn load_replay(&mut self, path: PathBuf, language: Language) -> Result<(), String> {
+        let source = ReplayPressureSource::load(&path, language)?;
+        self.replay_path = Some(path);
+        self.backend = PressureBackend::Replay;
+        self.source = Some(Box::new(source));
+        self.last_sample = None;
+        Ok(())
+    }
+
+    pub fn fall_back_to_velocity(&mut self) {
+        self.backend = PressureBackend::Velocity;
+        self.source = None;
+        self.last_sample = None;
+    }
+
+    pub fn description(&self) -> Option<String> {
+        self.source.as_ref().map(|source| source.description())
+    }
+
+    pub fn poll(&mut self) -> Option<PressureSample> {
+        let sample = self.source.as_mut()?.poll()?;
+        self.last_sample = Some(sample);
+
+        if let Some(recording) = self.recording.as_mut() {
+            recording.samples.push((recording.started.elapsed().as_millis() as u64, sample));
+        }
+
+        Some(sample)
+    }
+
+    pub fn eraser_active(&self) -> bool {
+        self.last_sample.is_some_and(|sample| sample.eraser && sample.in_contact)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn start_recording(&mut self, path: PathBuf) {
+        self.recording = Some(Recording {
+            path,
+            started: Instant::now(),
+            samples: Vec::new(),
+        });
+    }
+
+    pub fn stop_recording(&mut self, language: Language) -> Result<usize, String> {
+        let Some(recording) = self.recording.take() else {
+            return Ok(0);
+        };
+        write_replay_file(&recording.path, &recording.samples, language)?;
+        Ok(recording.samples.len())
+    }
+}
+
+pub struct ReplayPressureSource {
+    name: String,
+    events: Vec<(u64, PressureSample)>,
+    started: Option<Instant>,
+    position: usize,
+}
+
+impl ReplayPressureSource {
+    pub fn load(path: &Path, language: Language) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("{}: {}", get_text("error_reading_file", language), e))?;
+        let events = parse_replay(&content)
+            .map_err(|line| format!("{} {}", get_text("invalid_replay_line", language), line))?;
+
+        if events.is_empty() {
+            return Err(get_text("empty_replay_file", language));
+        }
+
+        Ok(Self {
+            name: path.file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or("replay")
+                .to_string(),
+            events,
+            started: None,
+            position: 0,
+        })
+    }
+}
+
+impl PressureSource for ReplayPressureSource {
+    fn description(&self) -> String {
+        format!("{} ({}/{})", self.name, self.position, self.events.len())
+    }
+
+    fn poll(&mut self) -> Option<PressureSample> {
+        let started = *self.started.get_or_insert_with(Instant::now);
+        let elapsed = started.elapsed().as_millis() as u64;
+        self.sample_at(elapsed)
+    }
+}
+
+impl ReplayPressureSource {
+    fn sample_at(&mut self, elapsed: u64) -> Option<PressureSample> {
+        while self.position + 1 < self.events.len() && self.events[self.position + 1].0 <= elapsed {
+            self.position += 1;
+        }
+
+        let sample = self.events.get(self.position).map(|&(_, sample)| sample);
+
+        if self.position + 1 >= self.events.len() && elapsed > self.events[self.position].0 {
+            self.started = None;
+            self.position = 0;
+        }
+
+        sample
+    }
+}
+
+fn parse_replay(content: &str) -> Result<Vec<(u64, PressureSample)>, usize> {
+    let mut events = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 6 {
+            return Err(index + 1);
+        }
+
+        let parse_f32 = |field: &str| field.parse::<f32>().map_err(|_| index + 1);
+        let parse_flag = |field: &str| match field {
+            "0" => Ok(false),
+            "1" => Ok(true),
+            _ => Err(index + 1),
+        };
+
+        let time = fields[0].parse::<u64>().map_err(|_| index + 1)?;
+        let sample = PressureSample {
+            pressure: parse_f32(fields[1])?.clamp(0.0, 1.0),
+            tilt_x: parse_f32(fields[2])?.clamp(-1.0, 1.0),
+            tilt_y: parse_f32(fields[3])?.clamp(-1.0, 1.0),
+            eraser: parse_flag(fields[4])?,
+            in_contact: parse_flag(fields[5])?,
+        };
+        events.push((time, sample));
+    }
+
+    events.sort_by_key(|&(time, _)| time);
+    Ok(events)
+}
+
+fn write_replay_file(path: &Path, samples: &[(u64, PressureSample)], language: Language) -> Result<(), String> {
+    let mut file = fs::File::create(path)
+        .map_err(|e| format!("{}: {}", get_text("error_writing_replay_file", language), e))?;
+
+    let mut content = String::from("# time_ms pressure tilt_x tilt_y eraser in_contact\n");
+    for (time, sample) in samples {
+        content.push_str(&format!(
+            "{} {:.4} {:.4} {:.4} {} {}\n",
+            time,
+            sample.pressure,
+            sample.tilt_x,
+            sample.tilt_y,
+            sample.eraser as u8,
+            sample.in_contact as u8,
+        ));
+    }
+
+    file.write_all(content.as_bytes())
+        .map_err(|e| format!("{}: {}", get_text("error_writing_replay_file", language), e))
+}
+
+#[cfg(target_os = "linux")]
+mod evdev {
+    use parking_lot::Mutex;
+    use std::fs::{self, File};
+    use std::io::Read;
+    use std::os::unix::io::AsRawFd;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread::{self, JoinHandle};
+
+    use super::{PressureSample, PressureSource};
+    use crate::localization::{Language, get_text};
+
+    const EV_KEY: u16 = 0x01;
+    const EV_ABS: u16 = 0x03;
+    const ABS_PRESSURE: u16 = 0x18;
+    const ABS_TILT_X: u16 = 0x1a;
+    const ABS_TILT_Y: u16 = 0x1b;
+    const BTN_TOOL_PEN: u16 = 0x140;
+    const BTN_TOOL_RUBBER: u16 = 0x141;
+    const BTN_TOUCH: u16 = 0x14a;
+    const ABS_BITS_LEN: usize = 8;
+    const KEY_BITS_LEN: usize = 96;
+    const POLL_TIMEOUT_MS: i32 = 50;
+    const EVENT_BATCH: usize = 64;
+
+    fn ioc_read(nr: u64, size: usize) -> u64 {
+        (2 << 30) | ((size as u64) << 16) | ((b'E' as u64) << 8) | nr
+    }
+
+    fn read_bits<const N: usize>(file: &File, nr: u64) -> Option<[u8; N]> {
+        let mut bits = [0u8; N];
+        let request = ioc_read(nr, N);
+        let result = unsafe { libc::ioctl(file.as_raw_fd(), request as _, bits.as_mut_ptr()) };
+        (result >= 0).then_some(bits)
+    }
+
+    fn has_bit(bits: &[u8], code: u16) -> bool {
+        bits.get((code / 8) as usize).is_some_and(|byte| byte & (1 << (code % 8)) != 0)
+    }
+
+    /// A stylus reports pressure and a pen tool; touchpads and touchscreens
+    /// that also expose `ABS_PRESSURE` only announce finger tools.
+    fn is_stylus(abs_bits: &[u8], key_bits: &[u8]) -> bool {
+        has_bit(abs_bits, ABS_PRESSURE) && has_bit(key_bits, BTN_TOOL_PEN)
+    }
+
+    fn device_is_stylus(file: &File) -> bool {
+        let Some(abs_bits) = read_bits::<ABS_BITS_LEN>(file, 0x20 + EV_ABS as u64) else {
+            return false;
+        };
+        let Some(key_bits) = read_bits::<KEY_BITS_LEN>(file, 0x20 + EV_KEY as u64) else {
+            return false;
+        };
+        is_stylus(&abs_bits, &key_bits)
+    }
+
+    fn axis_range(file: &File, axis: u16) -> Option<(i32, i32)> {
+        let mut info = libc::input_absinfo {
+            value: 0,
+            minimum: 0,
+            maximum: 0,
+            fuzz: 0,
+            flat: 0,
+            resolution: 0,
+        };
+        let request = ioc_read(0x40 + axis as u64, std::mem::size_of::<libc::input_absinfo>());
+        let result = unsafe { libc::ioctl(file.as_raw_fd(), request as _, &mut info as *mut libc::input_absinfo) };
+        (result >= 0 && info.maximum > info.minimum).then_some((info.minimum, info.maximum))
+    }
+
+    fn normalize(value: i32, (min, max): (i32, i32)) -> f32 {
+        ((value - min) as f32 / (max - min) as f32).clamp(0.0, 1.0)
+    }
+
+    #[derive(Clone, Copy)]
+    struct AxisRanges {
+        pressure: (i32, i32),
+        tilt_x: Option<(i32, i32)>,
+        tilt_y: Option<(i32, i32)>,
+    }
+
+    /// Decodes one raw `input_event` (as read from the device node) into the sample.
+    fn apply_event(sample: &mut PressureSample, event: &[u8], ranges: &AxisRanges) {
+        let tail = &event[event.len() - 8..];
+        let event_type = u16::from_ne_bytes([tail[0], tail[1]]);
+        let code = u16::from_ne_bytes([tail[2], tail[3]]);
+        let value = i32::from_ne_bytes([tail[4], tail[5], tail[6], tail[7]]);
+
+        match (event_type, code) {
+            (EV_ABS, ABS_PRESSURE) => sample.pressure = normalize(value, ranges.pressure),
+            (EV_ABS, ABS_TILT_X) => {
+                sample.tilt_x = ranges.tilt_x.map(|r| normalize(value, r) * 2.0 - 1.0).unwrap_or(0.0);
+            },
+            (EV_ABS, ABS_TILT_Y) => {
+                sample.tilt_y = ranges.tilt_y.map(|r| normalize(value, r) * 2.0 - 1.0).unwrap_or(0.0);
+            },
+            (EV_KEY, BTN_TOOL_RUBBER) => sample.eraser = value != 0,
+            (EV_KEY, BTN_TOOL_PEN) if value != 0 => sample.eraser = false,
+            (EV_KEY, BTN_TOUCH) => sample.in_contact = value != 0,
+            _ => {}
+        }
+    }
+
+    /// Waits up to `POLL_TIMEOUT_MS` so the reader thread can notice shutdown;
+    /// errors and hang-ups also count as readable so the following read fails.
+    fn wait_readable(file: &File) -> bool {
+        let mut descriptor = libc::pollfd {
+            fd: file.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let result = unsafe { libc::poll(&mut descriptor, 1, POLL_TIMEOUT_MS) };
+        result > 0
+    }
+
+    pub struct EvdevPressureSource {
+        path: PathBuf,
+        state: Arc<Mutex<PressureSample>>,
+        running: Arc<AtomicBool>,
+        reader: Option<JoinHandle<()>>,
+    }
+
+    impl EvdevPressureSource {
+        pub fn open_first_tablet(language: Language) -> Result<Self, String> {
+            let mut entries: Vec<PathBuf> = fs::read_dir("/dev/input")
+                .map_err(|e| format!("{}: {}", get_text("no_tablet_found", language), e))?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("event")))
+                .collect();
+            entries.sort();
+
+            let mut permission_denied = false;
+            for path in entries {
+                match File::open(&path) {
+                    Ok(file) if device_is_stylus(&file) => return Self::open(&path, file),
+                    Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => permission_denied = true,
+                    _ => {}
+                }
+            }
+
+            if permission_denied {
+                Err(get_text("tablet_permission_denied", language))
+            } else {
+                Err(get_text("no_tablet_found", language))
+            }
+        }
+
+        fn open(path: &Path, mut file: File) -> Result<Self, String> {
+            let ranges = AxisRanges {
+                pressure: axis_range(&file, ABS_PRESSURE).unwrap_or((0, 1023)),
+                tilt_x: axis_range(&file, ABS_TILT_X),
+                tilt_y: axis_range(&file, ABS_TILT_Y),
+            };
+
+            let state = Arc::new(Mutex::new(PressureSample::default()));
+            let running = Arc::new(AtomicBool::new(true));
+            let thread_state = Arc::clone(&state);
+            let thread_running = Arc::clone(&running);
+
+            let reader = thread::spawn(move || {
+                let event_size = std::mem::size_of::<libc::input_event>();
+                let mut buffer = vec![0u8; event_size * EVENT_BATCH];
+
+                while thread_running.load(Ordering::Relaxed) {
+                    if !wait_readable(&file) {
+                        continue;
+                    }
+                    let read = match file.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => read,
+                    };
+
+                    let mut sample = thread_state.lock();
+                    for event in buffer[..read - read % event_size].chunks_exact(event_size) {
+                        apply_event(&mut sample, event, &ranges);
+                    }
+                }
+            });
+
+            Ok(Self {
+                path: path.to_path_buf(),
+                state,
+                running,
+                reader: Some(reader),
+            })
+        }
+    }
+
+    impl PressureSource for EvdevPressureSource {
+        fn description(&self) -> String {
+            self.path.display().to_string()
+        }
+
+        fn poll(&mut self) -> Option<PressureSample> {
+            Some(*self.state.lock())
+        }
+    }
+
+    impl Drop for EvdevPressureSource {
+        fn drop(&mut self) {
+            self.running.store(false, Ordering::Relaxed);
+            if let Some(reader) = self.reader.take() {
+                let _ = reader.join();
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const RANGES: AxisRanges = AxisRanges {
+            pressure: (0, 2047),
+            tilt_x: Some((-64, 63)),
+            tilt_y: None,
+        };
+
+        fn raw_event(event_type: u16, code: u16, value: i32) -> Vec<u8> {
+            let mut event = vec![0u8; std::mem::size_of::<libc::input_event>() - 8];
+            event.extend_from_slice(&event_type.to_ne_bytes());
+            event.extend_from_slice(&code.to_ne_bytes());
+            event.extend_from_slice(&value.to_ne_bytes());
+            event
+        }
+
+        fn replay(events: &[(u16, u16, i32)]) -> PressureSample {
+            let mut sample = PressureSample::default();
+            for &(event_type, code, value) in events {
+                apply_event(&mut sample, &raw_event(event_type, code, value), &RANGES);
+            }
+            sample
+        }
+
+        fn bits_with(codes: &[u16], len: usize) -> Vec<u8> {
+            let mut bits = vec![0u8; len];
+            for &code in codes {
+                bits[(code / 8) as usize] |= 1 << (code % 8);
+            }
+            bits
+        }
+
+        #[test]
+        fn pen_stroke_is_decoded() {
+            let sample = replay(&[
+                (EV_KEY, BTN_TOOL_PEN, 1),
+                (EV_KEY, BTN_TOUCH, 1),
+                (EV_ABS, ABS_PRESSURE, 512),
+                (EV_ABS, ABS_TILT_X, 63),
+                (EV_ABS, ABS_PRESSURE, 2047),
+            ]);
+            assert!(sample.in_contact);
+            assert!(!sample.eraser);
+            assert_eq!(sample.pressure, 1.0);
+            assert_eq!(sample.tilt_x, 1.0);
+            assert_eq!(sample.tilt_y, 0.0);
+        }
+
+        #[test]
+        fn eraser_and_lift_are_decoded() {
+            let sample = replay(&[
+                (EV_KEY, BTN_TOOL_RUBBER, 1),
+                (EV_KEY, BTN_TOUCH, 1),
+                (EV_ABS, ABS_PRESSURE, 1024),
+                (EV_KEY, BTN_TOUCH, 0),
+            ]);
+            assert!(sample.eraser);
+            assert!(!sample.in_contact);
+            assert!((sample.pressure - 0.5).abs() < 0.01);
+
+            let sample = replay(&[(EV_KEY, BTN_TOOL_RUBBER, 1), (EV_KEY, BTN_TOOL_PEN, 1)]);
+            assert!(!sample.eraser);
+        }
+
+        #[test]
+        fn only_pen_devices_are_styluses() {
+            let pressure = bits_with(&[ABS_PRESSURE], ABS_BITS_LEN);
+            let pen = bits_with(&[BTN_TOOL_PEN, BTN_TOUCH], KEY_BITS_LEN);
+            let touchpad = bits_with(&[BTN_TOUCH, 0x145], KEY_BITS_LEN);
+
+            assert!(is_stylus(&pressure, &pen));
+            assert!(!is_stylus(&pressure, &touchpad));
+            assert!(!is_stylus(&bits_with(&[], ABS_BITS_LEN), &pen));
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod evdev {
+    use super::{PressureSample, PressureSource};
+    use crate::localization::{Language, get_text};
+
+    pub struct EvdevPressureSource;
+
+    impl EvdevPressureSource {
+        pub fn open_first_tablet(language: Language) -> Result<Self, String> {
+            Err(get_text("tablet_backend_unsupported", language))
+        }
+    }
+
+    impl PressureSource for EvdevPressureSource {
+        fn description(&self) -> String {
+            String::new()
+        }
+
+        fn poll(&mut self) -> Option<PressureSample> {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECORDING: &str = "\
+# time_ms pressure tilt_x tilt_y eraser in_contact
+0 0.0000 0.0000 0.0000 0 0
+20 0.2500 0.1000 -0.1000 0 1
+40 0.7500 0.2000 -0.2000 0 1
+
+60 0.0000 0.0000 0.0000 0 0
+";
+
+    fn source(content: &str) -> ReplayPressureSource {
+        ReplayPressureSource {
+            name: "test".to_string(),
+            events: parse_replay(content).unwrap(),
+            started: None,
+            position: 0,
+        }
+    }
+
+    #[test]
+    fn recording_replays_in_time_order() {
+        let mut replay = source(RECORDING);
+
+        assert!(!replay.sample_at(0).unwrap().in_contact);
+        let sample = replay.sample_at(25).unwrap();
+        assert!(sample.in_contact);
+        assert_eq!(sample.pressure, 0.25);
+        assert_eq!(replay.sample_at(45).unwrap().pressure, 0.75);
+        assert!(!replay.sample_at(60).unwrap().in_contact);
+    }
+
+    #[test]
+    fn replay_restarts_after_the_last_event() {
+        let mut replay = source(RECORDING);
+
+        replay.sample_at(61);
+        assert_eq!(replay.position, 0);
+        assert!(replay.started.is_none());
+    }
+
+    #[test]
+    fn invalid_lines_are_reported() {
+        assert_eq!(parse_replay("0 0.5 0 0 0 1\n10 0.5 0 0 2 1\n").unwrap_err(), 2);
+        assert_eq!(parse_replay("0 0.5 0 0 0\n").unwrap_err(), 1);
+    }
+
+    #[test]
+    fn written_recording_parses_back() {
+        let events = parse_replay(RECORDING).unwrap();
+        let path = std::env::temp_dir().join(format!("rustique_replay_{}.txt", std::process::id()));
+
+        write_replay_file(&path, &events, Language::English).unwrap();
+        let reloaded = ReplayPressureSource::load(&path, Language::English).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(reloaded.events, events);
+    }
+
+    #[test]
+    fn backend_switches_to_replay_only_once_a_file_loads() {
+        let mut input = PressureInput::new();
+        let missing = std::env::temp_dir().join(format!("rustique_missing_{}.txt", std::process::id()));
+        assert!(input.load_replay(missing, Language::English).is_err());
+        assert!(input.set_backend(PressureBackend::Replay, Language::English).is_err());
+        input.fall_back_to_velocity();
+        assert_eq!(input.backend, PressureBackend::Velocity);
+        assert!(input.replay_path.is_none());
+
+        let path = std::env::temp_dir().join(format!("rustique_backend_{}.txt", std::process::id()));
+        fs::write(&path, RECORDING).unwrap();
+        let loaded = input.load_replay(path.clone(), Language::English);
+        let _ = fs::remove_file(&path);
+
+        assert!(loaded.is_ok());
+        assert_eq!(input.backend, PressureBackend::Replay);
+        assert_eq!(input.replay_path, Some(path));
+        assert!(input.description().is_some());
+    }
+}
@@ -0,0 +1,352 @@
+use color_quant::NeuQuant;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+
+use crate::gradient::BAYER_4X4;
+use crate::localization::{Language, get_text};
+use crate::palette::{ExtractionMethod, extract_palette};
+
+const ALPHA_THRESHOLD: u8 = 128;
+const OCTREE_DEPTH: usize = 8;
+const NEUQUANT_SAMPLE_FACTOR: i32 = 10;
+
+const FLOYD_STEINBERG: [(isize, usize, f32); 4] = [
+    (1, 0, 7.0 / 16.0),
+    (-1, 1, 3.0 / 16.0),
+    (0, 1, 5.0 / 16.0),
+    (1, 1, 1.0 / 16.0),
+];
+const ATKINSON: [(isize, usize, f32); 6] = [
+    (1, 0, 1.0 / 8.0),
+    (2, 0, 1.0 / 8.0),
+    (-1, 1, 1.0 / 8.0),
+    (0, 1, 1.0 / 8.0),
+    (1, 1, 1.0 / 8.0),
+    (0, 2, 1.0 / 8.0),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Quantizer {
+    MedianCut,
+    Octree,
+    NeuQuant,
+}
+
+impl Quantizer {
+    pub fn get_name(&self, language: Language) -> String {
+        match self {
+            Quantizer::MedianCut => get_text("extraction_median_cut", language),
+            Quantizer::Octree => get_text("quantizer_octree", language),
+            Quantizer::NeuQuant => get_text("quantizer_neuquant", language),
+        }
+    }
+
+    pub fn all_quantizers() -> Vec<Quantizer> {
+        vec![Quantizer::MedianCut, Quantizer::Octree, Quantizer::NeuQuant]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Dithering {
+    None,
+    FloydSteinberg,
+    Atkinson,
+    Bayer,
+}
+
+impl Dithering {
+    pub fn get_name(&self, language: Language) -> String {
+        match self {
+            Dithering::None => get_text("dithering_none", language),
+            Dithering::FloydSteinberg => get_text("dithering_floyd_steinberg", language),
+            Dithering::Atkinson => get_text("dithering_atkinson", language),
+            Dithering::Bayer => get_text("dithering_bayer", language),
+        }
+    }
+
+    pub fn all_ditherings() -> Vec<Dithering> {
+        vec![Dithering::None, Dithering::FloydSteinberg, Dithering::Atkinson, Dithering::Bayer]
+    }
+}
+
+#[derive(Default)]
+struct OctreeNode {
+    sum: [u64; 3],
+    count: u64,
+    children: [Option<usize>; 8],
+    leaf: bool,
+}
+
+fn octree(pixels: &[[u8; 4]], size: usize) -> Vec<[u8; 3]> {
+    let mut nodes = vec![OctreeNode::default()];
+    let mut levels: Vec<Vec<usize>> = vec![Vec::new(); OCTREE_DEPTH];
+    let mut leaf_count = 0;
+
+    for pixel in pixels.iter().filter(|pixel| pixel[3] >= ALPHA_THRESHOLD) {
+        let mut node = 0;
+        nodes[0].count += 1;
+        for (depth, level) in levels.iter_mut().enumerate() {
+            let shift = 7 - depth;
+            let branch = (((pixel[0] >> shift) & 1) << 2 | ((pixel[1] >> shift) & 1) << 1 | ((pixel[2] >> shift) & 1)) as usize;
+            node = match nodes[node].children[branch] {
+                Some(child) => child,
+                None => {
+                    if nodes[node].children.iter().all(Option::is_none) {
+                        level.push(node);
+                    }
+                    nodes.push(OctreeNode::default());
+                    let child = nodes.len() - 1;
+                    nodes[node].children[branch] = Some(child);
+                    child
+                },
+            };
+            nodes[node].count += 1;
+        }
+
+        let leaf = &mut nodes[node];
+        if !leaf.leaf {
+            leaf.leaf = true;
+            leaf_count += 1;
+        }
+        for (sum, &component) in leaf.sum.iter_mut().zip(pixel) {
+            *sum += component as u64;
+        }
+    }
+
+    for level in levels.iter_mut().rev() {
+        if leaf_count <= size {
+            break;
+        }
+        level.sort_by_key(|&node| nodes[node].count);
+        for &node in level.iter() {
+            if leaf_count <= size {
+                break;
+            }
+            let children: Vec<usize> = nodes[node].children.iter_mut().filter_map(Option::take).collect();
+            let mut sum = [0; 3];
+            for &child in &children {
+                for (total, value) in sum.iter_mut().zip(nodes[child].sum) {
+                    *total += value;
+                }
+            }
+            nodes[node].sum = sum;
+            nodes[node].leaf = true;
+            leaf_count = leaf_count + 1 - children.len();
+        }
+    }
+
+    let mut palette = Vec::with_capacity(leaf_count);
+    let mut stack = vec![0];
+    while let Some(index) = stack.pop() {
+        let node = &nodes[index];
+        if node.leaf && node.count > 0 {
+            palette.push(node.sum.map(|total| (total / node.count) as u8));
+        } else {
+            stack.extend(node.children.iter().flatten());
+        }
+    }
+    palette
+}
+
+fn neuquant(pixels: &[[u8; 4]], size: usize) -> Vec<[u8; 3]> {
+    let opaque: Vec<u8> = pixels
+        .iter()
+        .filter(|pixel| pixel[3] >= ALPHA_THRESHOLD)
+        .flat_map(|pixel| [pixel[0], pixel[1], pixel[2], 255])
+        .collect();
+    if opaque.is_empty() {
+        return Vec::new();
+    }
+
+    let quantizer = NeuQuant::new(NEUQUANT_SAMPLE_FACTOR, size, &opaque);
+    quantizer
+        .color_map_rgb()
+        .chunks_exact(3)
+        .map(|rgb| [rgb[0], rgb[1], rgb[2]])
+        .collect()
+}
+
+fn exact_colors(pixels: &[[u8; 4]], size: usize) -> Option<Vec<[u8; 3]>> {
+    let mut colors = Vec::new();
+    for &[r, g, b, a] in pixels {
+        if a >= ALPHA_THRESHOLD && !colors.contains(&[r, g, b]) {
+            if colors.len() == size {
+                return None;
+            }
+            colors.push([r, g, b]);
+        }
+    }
+    Some(colors)
+}
+
+pub fn build_palette(pixels: &[[u8; 4]], size: usize, quantizer: Quantizer) -> Vec<[u8; 3]> {
+    let mut palette = match exact_colors(pixels, size) {
+        Some(colors) => colors,
+        None => match quantizer {
+            Quantizer::MedianCut => extract_palette(pixels, size, ExtractionMethod::MedianCut)
+                .into_iter()
+                .map(|[r, g, b, _]| [r, g, b])
+                .collect(),
+            Quantizer::Octree => octree(pixels, size),
+            Quantizer::NeuQuant => neuquant(pixels, size),
+        },
+    };
+    palette.sort_unstable();
+    palette.dedup();
+    palette
+}
+
+fn nearest(palette: &[[u8; 3]], color: [f32; 3]) -> u8 {
+    palette
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| {
+            let distance = |entry: &[u8; 3]| (0..3).map(|c| (entry[c] as f32 - color[c]).powi(2)).sum::<f32>();
+            distance(a).total_cmp(&distance(b))
+        })
+        .map_or(0, |(index, _)| index as u8)
+}
+
+pub fn map_to_palette(pixels: &[[u8; 4]], width: usize, palette: &[[u8; 3]], dithering: Dithering) -> Vec<Option<u8>> {
+    if palette.is_empty() {
+        return vec![None; pixels.len()];
+    }
+
+    let mut cache: HashMap<[u8; 3], u8> = HashMap::new();
+    let mut lookup = |color: [f32; 3]| {
+        let key = color.map(|v| v.round().clamp(0.0, 255.0) as u8);
+        *cache.entry(key).or_insert_with(|| nearest(palette, key.map(|v| v as f32)))
+    };
+
+    let kernel: &[(isize, usize, f32)] = match dithering {
+        Dithering::FloydSteinberg => &FLOYD_STEINBERG,
+        Dithering::Atkinson => &ATKINSON,
+        Dithering::None | Dithering::Bayer => &[],
+    };
+    let spread = 255.0 / (palette.len() as f32).cbrt();
+    let mut errors = vec![[0.0_f32; 3]; if kernel.is_empty() { 0 } else { pixels.len() }];
+    let height = pixels.len() / width.max(1);
+
+    let mut indices = Vec::with_capacity(pixels.len());
+    for (i, pixel) in pixels.iter().enumerate() {
+        if pixel[3] < ALPHA_THRESHOLD {
+            indices.push(None);
+            continue;
+        }
+
+        let (x, y) = (i % width, i / width);
+        let mut color = [pixel[0] as f32, pixel[1] as f32, pixel[2] as f32];
+        if dithering == Dithering::Bayer {
+            let threshold = (BAYER_4X4[(y % 4) * 4 + x % 4] + 0.5) / 16.0 - 0.5;
+            color = color.map(|v| v + threshold * spread);
+        }
+        if let Some(error) = errors.get(i) {
+            for (value, e) in color.iter_mut().zip(error) {
+                *value += e;
+            }
+        }
+
+        let index = lookup(color);
+        indices.push(Some(index));
+
+        let chosen = palette[index as usize];
+        for &(dx, dy, weight) in kernel {
+            let (nx, ny) = (x as isize + dx, y + dy);
+            if nx < 0 || nx as usize >= width || ny >= height {
+                continue;
+            }
+            let target = &mut errors[ny * width + nx as usize];
+            for ((error, value), entry) in target.iter_mut().zip(color).zip(chosen) {
+                *error += (value.clamp(0.0, 255.0) - entry as f32) * weight;
+            }
+        }
+    }
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient(width: usize, height: usize) -> Vec<[u8; 4]> {
+        (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                [(x * 255 / (width - 1)) as u8, (y * 255 / (height - 1)) as u8, 128, 255]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn quantizers_respect_palette_size() {
+        let pixels = gradient(32, 32);
+        for quantizer in Quantizer::all_quantizers() {
+            let palette = build_palette(&pixels, 16, quantizer);
+            assert!(!palette.is_empty(), "{quantizer:?}");
+            assert!(palette.len() <= 16, "{quantizer:?} built {} colors", palette.len());
+            assert!(palette.windows(2).all(|pair| pair[0] < pair[1]), "{quantizer:?}");
+        }
+    }
+
+    #[test]
+    fn quantizers_keep_exact_colors_when_they_fit() {
+        let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255]];
+        let pixels: Vec<[u8; 4]> = colors.iter().cycle().take(300).copied().collect();
+        for quantizer in Quantizer::all_quantizers() {
+            assert_eq!(build_palette(&pixels, 8, quantizer), vec![[0, 0, 255], [0, 255, 0], [255, 0, 0]], "{quantizer:?}");
+        }
+    }
+
+    #[test]
+    fn octree_merges_down_to_the_requested_size() {
+        let pixels = gradient(64, 64);
+        let palette = octree(&pixels, 8);
+        assert!(!palette.is_empty() && palette.len() <= 8, "built {} colors", palette.len());
+    }
+
+    #[test]
+    fn transparent_pixels_are_ignored() {
+        let pixels = vec![[10, 20, 30, 0]; 16];
+        for quantizer in Quantizer::all_quantizers() {
+            assert!(build_palette(&pixels, 4, quantizer).is_empty(), "{quantizer:?}");
+        }
+        assert_eq!(map_to_palette(&pixels, 4, &[[0, 0, 0]], Dithering::FloydSteinberg), vec![None; 16]);
+    }
+
+    #[test]
+    fn mapping_without_dithering_picks_nearest_entry() {
+        let palette = [[0, 0, 0], [255, 255, 255]];
+        let pixels = [[20, 20, 20, 255], [240, 230, 250, 255], [0, 0, 0, 10]];
+        assert_eq!(map_to_palette(&pixels, 3, &palette, Dithering::None), vec![Some(0), Some(1), None]);
+    }
+
+    #[test]
+    fn exact_palette_colors_are_stable_under_every_dithering() {
+        let palette = [[0, 0, 0], [255, 255, 255]];
+        let pixels: Vec<[u8; 4]> = (0..64).map(|i| if i % 3 == 0 { [255, 255, 255, 255] } else { [0, 0, 0, 255] }).collect();
+        let expected: Vec<Option<u8>> = (0..64).map(|i| Some(u8::from(i % 3 == 0))).collect();
+        for dithering in [Dithering::None, Dithering::FloydSteinberg, Dithering::Atkinson] {
+            assert_eq!(map_to_palette(&pixels, 8, &palette, dithering), expected, "{dithering:?}");
+        }
+    }
+
+    #[test]
+    fn dithering_preserves_average_tone() {
+        let palette = [[0, 0, 0], [255, 255, 255]];
+        let pixels = vec![[128, 128, 128, 255]; 16 * 16];
+        assert!(map_to_palette(&pixels, 16, &palette, Dithering::None).iter().all(|&index| index == Some(1)));
+
+        for dithering in [Dithering::FloydSteinberg, Dithering::Atkinson, Dithering::Bayer] {
+            let indices = map_to_palette(&pixels, 16, &palette, dithering);
+            let white = indices.iter().filter(|&&index| index == Some(1)).count();
+            let ratio = white as f32 / indices.len() as f32;
+            assert!((0.35..=0.65).contains(&ratio), "{dithering:?} gave {ratio}");
+        }
+    }
+
+    #[test]
+    fn empty_palette_maps_to_nothing() {
+        let pixels = gradient(4, 4);
+        assert_eq!(map_to_palette(&pixels, 4, &[], Dithering::Bayer), vec![None; 16]);
+    }
+}
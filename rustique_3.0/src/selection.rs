@@ -0,0 +1,82 @@
+#[derive(Clone)]
+pub struct Selection {
+    pub width: usize,
+    pub height: usize,
+    pub mask: Vec<u8>,
+    bounds: Option<(usize, usize, usize, usize)>,
+}
+
+impl Selection {
+    pub fn rectangle(width: usize, height: usize, start: (i32, i32), end: (i32, i32)) -> Self {
+        let mut mask = vec![0; width * height];
+        let x0 = start.0.min(end.0).clamp(0, width as i32) as usize;
+        let x1 = start.0.max(end.0).clamp(0, width as i32) as usize;
+        let y0 = start.1.min(end.1).clamp(0, height as i32) as usize;
+        let y1 = start.1.max(end.1).clamp(0, height as i32) as usize;
+
+        for y in y0..y1 {
+            for x in x0..x1 {
+                mask[y * width + x] = 255;
+            }
+        }
+
+        Self::from_mask(width, height, mask)
+    }
+
+    pub fn from_mask(width: usize, height: usize, mask: Vec<u8>) -> Self {
+        let mut selection = Self {
+            width,
+            height,
+            mask,
+            bounds: None,
+        };
+        selection.bounds = selection.compute_bounds();
+        selection
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bounds.is_none()
+    }
+
+    #[inline]
+    pub fn value(&self, x: usize, y: usize) -> u8 {
+        if x < self.width && y < self.height {
+            self.mask[y * self.width + x]
+        } else {
+            0
+        }
+    }
+
+    #[inline]
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        self.value(x, y) > 0
+    }
+
+    pub fn bounds(&self) -> Option<(usize, usize, usize, usize)> {
+        self.bounds
+    }
+
+    fn compute_bounds(&self) -> Option<(usize, usize, usize, usize)> {
+        let mut min_x = usize::MAX;
+        let mut min_y = usize::MAX;
+        let mut max_x = 0;
+        let mut max_y = 0;
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.mask[y * self.width + x] > 0 {
+                    min_x = min_x.min(x);
+                    min_y = min_y.min(y);
+                    max_x = max_x.max(x);
+                    max_y = max_y.max(y);
+                }
+            }
+        }
+
+        if min_x == usize::MAX {
+            None
+        } else {
+            Some((min_x, min_y, max_x + 1, max_y + 1))
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use eframe::egui;
+use egui::{Color32, Pos2, Stroke};
+use std::f32::consts::PI;
+
+use crate::brush_system::BrushManager;
+use crate::localization::{Language, get_text};
+
+const CENTER_HANDLE_RADIUS: f32 = 8.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SymmetryMode {
+    Off,
+    Vertical,
+    Horizontal,
+    BothAxes,
+    Radial,
+    Kaleidoscope,
+}
+
+impl SymmetryMode {
+    pub fn get_name(&self, language: Language) -> String {
+        match self {
+            SymmetryMode::Off => get_text("symmetry_off", language),
+            SymmetryMode::Vertical => get_text("symmetry_vertical", language),
+            SymmetryMode::Horizontal => get_text("symmetry_horizontal", language),
+            SymmetryMode::BothAxes => get_text("symmetry_both", language),
+            SymmetryMode::Radial => get_text("symmetry_radial", language),
+            SymmetryMode::Kaleidoscope => get_text("symmetry_kaleidoscope", language),
+        }
+    }
+
+    pub fn all_modes() -> Vec<SymmetryMode> {
+        vec![
+            SymmetryMode::Off,
+            SymmetryMode::Vertical,
+            SymmetryMode::Horizontal,
+            SymmetryMode::BothAxes,
+            SymmetryMode::Radial,
+            SymmetryMode::Kaleidoscope,
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MirrorTransform {
+    matrix: [f32; 4],
+    center: (f32, f32),
+}
+
+impl MirrorTransform {
+    fn rotation(angle: f32, center: (f32, f32)) -> Self {
+        let (sin, cos) = angle.sin_cos();
+        Self { matrix: [cos, -sin, sin, cos], center }
+    }
+
+    fn reflection(axis_angle: f32, center: (f32, f32)) -> Self {
+        let (sin, cos) = (2.0 * axis_angle).sin_cos();
+        Self { matrix: [cos, sin, sin, -cos], center }
+    }
+
+    pub fn apply_f32(&self, x: f32, y: f32) -> (f32, f32) {
+        let [a, b, c, d] = self.matrix;
+        let dx = x - self.center.0;
+        let dy = y - self.center.1;
+        (self.center.0 + a * dx + b * dy, self.center.1 + c * dx + d * dy)
+    }
+
+    pub fn apply(&self, point: (i32, i32)) -> (i32, i32) {
+        let (x, y) = self.apply_f32(point.0 as f32, point.1 as f32);
+        (x.round() as i32, y.round() as i32)
+    }
+}
+
+pub struct Symmetry {
+    pub mode: SymmetryMode,
+    pub center: Option<(f32, f32)>,
+    pub radial_count: u32,
+    pub show_guides: bool,
+    pub moving_center: bool,
+    brush_states: Vec<(f32, Option<(f32, f32)>)>,
+}
+
+impl Default for Symmetry {
+    fn default() -> Self {
+        Self {
+            mode: SymmetryMode::Off,
+            center: None,
+            radial_count: 6,
+            show_guides: true,
+            moving_center: false,
+            brush_states: Vec::new(),
+        }
+    }
+}
+
+impl Symmetry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.mode != SymmetryMode::Off
+    }
+
+    pub fn center_or_default(&self, width: usize, height: usize) -> (f32, f32) {
+        self.center.unwrap_or((width as f32 * 0.5, height as f32 * 0.5))
+    }
+
+    pub fn transforms(&self, width: usize, height: usize) -> Vec<MirrorTransform> {
+        let center = self.center_or_default(width, height);
+        let count = self.radial_count.max(2);
+
+        match self.mode {
+            SymmetryMode::Off => vec![MirrorTransform::rotation(0.0, center)],
+            SymmetryMode::Vertical => vec![
+                MirrorTransform::rotation(0.0, center),
+                MirrorTransform::reflection(PI * 0.5, center),
+            ],
+            SymmetryMode::Horizontal => vec![
+                MirrorTransform::rotation(0.0, center),
+                MirrorTransform::reflection(0.0, center),
+            ],
+            SymmetryMode::BothAxes => vec![
+                MirrorTransform::rotation(0.0, center),
+                MirrorTransform::reflection(PI * 0.5, center),
+                MirrorTransform::reflection(0.0, center),
+                MirrorTransform::rotation(PI, center),
+            ],
+            SymmetryMode::Radial => (0..count)
+                .map(|k| MirrorTransform::rotation(2.0 * PI * k as f32 / count as f32, center))
+                .collect(),
+            SymmetryMode::Kaleidoscope => (0..count)
+                .map(|k| MirrorTransform::rotation(2.0 * PI * k as f32 / count as f32, center))
+                .chain((0..count).map(|k| MirrorTransform::reflection(PI * k as f32 / count as f32, center)))
+                .collect(),
+        }
+    }
+
+    pub fn reset_brush_states(&mut self) {
+        self.brush_states.clear();
+    }
+
+    pub fn swap_brush_state(&mut self, mirror_index: usize, brush_manager: &mut BrushManager) {
+        if mirror_index == 0 {
+            return;
+        }
+        if self.brush_states.len() < mirror_index {
+            self.brush_states.resize(mirror_index, (brush_manager.current_angle, None));
+        }
+
+        let state = &mut self.brush_states[mirror_index - 1];
+        std::mem::swap(&mut brush_manager.current_angle, &mut state.0);
+        std::mem::swap(&mut brush_manager.last_position, &mut state.1);
+    }
+
+    pub fn hits_center(&self, canvas_pos: Pos2, width: usize, height: usize, radius: f32) -> bool {
+        let (cx, cy) = self.center_or_default(width, height);
+        self.is_active() && self.show_guides && Pos2::new(cx, cy).distance(canvas_pos) <= radius
+    }
+
+    pub fn center_handle_radius() -> f32 {
+        CENTER_HANDLE_RADIUS
+    }
+
+    pub fn draw_guides(&self, painter: &egui::Painter, width: usize, height: usize, to_screen: impl Fn(Pos2) -> Pos2) {
+        if !self.is_active() || !self.show_guides {
+            return;
+        }
+
+        let (cx, cy) = self.center_or_default(width, height);
+        let center = to_screen(Pos2::new(cx, cy));
+        let reach = (width.max(height) as f32) * 1.5;
+        let guide = Stroke::new(1.0, Color32::from_rgba_unmultiplied(0, 170, 255, 140));
+
+        let axis = |angle: f32, full: bool| {
+            let (sin, cos) = angle.sin_cos();
+            let end = to_screen(Pos2::new(cx + cos * reach, cy + sin * reach));
+            let start = if full {
+                to_screen(Pos2::new(cx - cos * reach, cy - sin * reach))
+            } else {
+                center
+            };
+            painter.line_segment([start, end], guide);
+        };
+
+        match self.mode {
+            SymmetryMode::Off => {},
+            SymmetryMode::Vertical => axis(PI * 0.5, true),
+            SymmetryMode::Horizontal => axis(0.0, true),
+            SymmetryMode::BothAxes => {
+                axis(0.0, true);
+                axis(PI * 0.5, true);
+            },
+            SymmetryMode::Radial | SymmetryMode::Kaleidoscope => {
+                let count = self.radial_count.max(2);
+                for k in 0..count {
+                    axis(-PI * 0.5 + 2.0 * PI * k as f32 / count as f32, false);
+                }
+            },
+        }
+
+        painter.circle_filled(center, CENTER_HANDLE_RADIUS * 0.6, Color32::WHITE);
+        painter.circle_stroke(center, CENTER_HANDLE_RADIUS * 0.6, Stroke::new(1.5, Color32::from_rgb(0, 170, 255)));
+    }
+}
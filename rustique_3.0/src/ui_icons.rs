@@ -117,6 +117,38 @@ impl ToolIcons {
         IconWidget::new("line_tool", "📏", Vec2::new(24.0, 24.0))
     }
     
+    pub fn select() -> IconWidget {
+        IconWidget::new("select_tool", "⬚", Vec2::new(24.0, 24.0))
+    }
+    
+    pub fn smudge() -> IconWidget {
+        IconWidget::new("smudge_tool", "☝", Vec2::new(24.0, 24.0))
+    }
+    
+    pub fn clone_stamp() -> IconWidget {
+        IconWidget::new("clone_stamp_tool", "⎘", Vec2::new(24.0, 24.0))
+    }
+    
+    pub fn heal() -> IconWidget {
+        IconWidget::new("heal_tool", "✚", Vec2::new(24.0, 24.0))
+    }
+    
+    pub fn gradient() -> IconWidget {
+        IconWidget::new("gradient_tool", "▤", Vec2::new(24.0, 24.0))
+    }
+    
+    pub fn pen() -> IconWidget {
+        IconWidget::new("pen_tool", "✒", Vec2::new(24.0, 24.0))
+    }
+    
+    pub fn pencil() -> IconWidget {
+        IconWidget::new("pencil_tool", "✏", Vec2::new(24.0, 24.0))
+    }
+    
+    pub fn shade() -> IconWidget {
+        IconWidget::new("shade_tool", "◐", Vec2::new(24.0, 24.0))
+    }
+    
     pub fn undo() -> IconWidget {
         IconWidget::new("undo_icon", "↶", Vec2::new(20.0, 20.0)).with_color(Color32::WHITE)
     }
@@ -160,6 +192,30 @@ impl ToolIcons {
     pub fn edit() -> IconWidget {
         IconWidget::new("layer_edit", "✏️", Vec2::new(16.0, 16.0))
     }
+    
+    pub fn group() -> IconWidget {
+        IconWidget::new("layer_group", "📁", Vec2::new(18.0, 18.0)).with_color(Color32::WHITE)
+    }
+    
+    pub fn duplicate() -> IconWidget {
+        IconWidget::new("layer_duplicate", "🗐", Vec2::new(18.0, 18.0)).with_color(Color32::WHITE)
+    }
+    
+    pub fn merge_down() -> IconWidget {
+        IconWidget::new("layer_merge_down", "⤵", Vec2::new(18.0, 18.0)).with_color(Color32::WHITE)
+    }
+    
+    pub fn merge_visible() -> IconWidget {
+        IconWidget::new("layer_merge_visible", "☰", Vec2::new(18.0, 18.0)).with_color(Color32::WHITE)
+    }
+    
+    pub fn flatten() -> IconWidget {
+        IconWidget::new("layer_flatten", "▬", Vec2::new(18.0, 18.0)).with_color(Color32::WHITE)
+    }
+    
+    pub fn background_layer() -> IconWidget {
+        IconWidget::new("layer_background", "▣", Vec2::new(18.0, 18.0)).with_color(Color32::WHITE)
+    }
 
     pub fn brush_text() -> RichText {
         RichText::new("🖌️").size(20.0)
@@ -0,0 +1,358 @@
+use eframe::egui;
+use egui::Pos2;
+use serde::{Serialize, Deserialize};
+
+const FLATTEN_STEP: f32 = 2.0;
+const SUBSAMPLES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PathAnchor {
+    pub point: [f32; 2],
+    pub handle_in: [f32; 2],
+    pub handle_out: [f32; 2],
+}
+
+impl PathAnchor {
+    pub fn corner(x: f32, y: f32) -> Self {
+        Self {
+            point: [x, y],
+            handle_in: [x, y],
+            handle_out: [x, y],
+        }
+    }
+
+    pub fn translate(&mut self, dx: f32, dy: f32) {
+        for p in [&mut self.point, &mut self.handle_in, &mut self.handle_out] {
+            p[0] += dx;
+            p[1] += dy;
+        }
+    }
+
+    pub fn set_handle_out(&mut self, x: f32, y: f32, mirror: bool) {
+        self.handle_out = [x, y];
+        if mirror {
+            self.handle_in = [2.0 * self.point[0] - x, 2.0 * self.point[1] - y];
+        }
+    }
+
+    pub fn set_handle_in(&mut self, x: f32, y: f32, mirror: bool) {
+        self.handle_in = [x, y];
+        if mirror {
+            self.handle_out = [2.0 * self.point[0] - x, 2.0 * self.point[1] - y];
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathHandle {
+    Anchor(usize),
+    HandleIn(usize),
+    HandleOut(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VectorPath {
+    pub name: String,
+    pub anchors: Vec<PathAnchor>,
+    pub closed: bool,
+}
+
+impl VectorPath {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            anchors: Vec::new(),
+            closed: false,
+        }
+    }
+
+    fn segments(&self) -> Vec<[Pos2; 4]> {
+        let to_pos = |p: [f32; 2]| Pos2::new(p[0], p[1]);
+        let count = self.anchors.len();
+        let segment_count = if self.closed && count > 2 { count } else { count.saturating_sub(1) };
+
+        (0..segment_count)
+            .map(|i| {
+                let a = &self.anchors[i];
+                let b = &self.anchors[(i + 1) % count];
+                [to_pos(a.point), to_pos(a.handle_out), to_pos(b.handle_in), to_pos(b.point)]
+            })
+            .collect()
+    }
+
+    pub fn flatten(&self) -> Vec<Pos2> {
+        let mut points = Vec::new();
+        if let Some(first) = self.anchors.first() {
+            points.push(Pos2::new(first.point[0], first.point[1]));
+        }
+
+        for [p0, p1, p2, p3] in self.segments() {
+            let hull = p0.distance(p1) + p1.distance(p2) + p2.distance(p3);
+            let steps = (hull / FLATTEN_STEP).ceil().max(1.0) as usize;
+            for i in 1..=steps {
+                let t = i as f32 / steps as f32;
+                let u = 1.0 - t;
+                let x = u * u * u * p0.x + 3.0 * u * u * t * p1.x + 3.0 * u * t * t * p2.x + t * t * t * p3.x;
+                let y = u * u * u * p0.y + 3.0 * u * u * t * p1.y + 3.0 * u * t * t * p2.y + t * t * t * p3.y;
+                points.push(Pos2::new(x, y));
+            }
+        }
+
+        points
+    }
+
+    pub fn sample_evenly(&self, spacing: f32) -> Vec<(Pos2, f32)> {
+        let points = self.flatten();
+        let total: f32 = points.windows(2).map(|w| w[0].distance(w[1])).sum();
+        if points.is_empty() || total <= 0.0 {
+            return points.into_iter().map(|p| (p, 0.0)).collect();
+        }
+
+        let spacing = spacing.max(0.5);
+        let mut samples = vec![(points[0], 0.0)];
+        let mut travelled = 0.0;
+        let mut next = spacing;
+
+        for w in points.windows(2) {
+            let length = w[0].distance(w[1]);
+            while length > 0.0 && next <= travelled + length {
+                let t = (next - travelled) / length;
+                samples.push((w[0] + (w[1] - w[0]) * t, next / total));
+                next += spacing;
+            }
+            travelled += length;
+        }
+
+        if let Some(&last) = points.last() {
+            samples.push((last, 1.0));
+        }
+        samples
+    }
+
+    pub fn coverage_mask(&self, width: usize, height: usize) -> Vec<f32> {
+        let mut coverage = vec![0.0_f32; width * height];
+        let mut polygon = self.flatten();
+        if polygon.len() < 3 {
+            return coverage;
+        }
+        polygon.push(polygon[0]);
+
+        let sub_weight = 1.0 / SUBSAMPLES as f32;
+        for y in 0..height {
+            for sub in 0..SUBSAMPLES {
+                let sample_y = y as f32 + (sub as f32 + 0.5) * sub_weight;
+                let mut crossings: Vec<f32> = polygon
+                    .windows(2)
+                    .filter(|w| (w[0].y <= sample_y) != (w[1].y <= sample_y))
+                    .map(|w| w[0].x + (sample_y - w[0].y) / (w[1].y - w[0].y) * (w[1].x - w[0].x))
+                    .collect();
+                crossings.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+                for span in crossings.chunks_exact(2) {
+                    let start = span[0].clamp(0.0, width as f32);
+                    let end = span[1].clamp(0.0, width as f32);
+                    if end <= start {
+                        continue;
+                    }
+
+                    let first = start.floor() as usize;
+                    let last = (end.ceil() as usize).min(width);
+                    for x in first..last {
+                        let covered = (end.min(x as f32 + 1.0) - start.max(x as f32)).max(0.0);
+                        coverage[y * width + x] += covered * sub_weight;
+                    }
+                }
+            }
+        }
+
+        for value in coverage.iter_mut() {
+            *value = value.min(1.0);
+        }
+        coverage
+    }
+
+    pub fn hit_test(&self, pos: Pos2, radius: f32, with_handles: bool) -> Option<PathHandle> {
+        let near = |p: [f32; 2]| Pos2::new(p[0], p[1]).distance(pos) <= radius;
+
+        for (i, anchor) in self.anchors.iter().enumerate().rev() {
+            if with_handles && anchor.handle_out != anchor.point && near(anchor.handle_out) {
+                return Some(PathHandle::HandleOut(i));
+            }
+            if with_handles && anchor.handle_in != anchor.point && near(anchor.handle_in) {
+                return Some(PathHandle::HandleIn(i));
+            }
+            if near(anchor.point) {
+                return Some(PathHandle::Anchor(i));
+            }
+        }
+        None
+    }
+}
+
+pub struct PenTool {
+    pub paths: Vec<VectorPath>,
+    pub active_path: Option<usize>,
+    pub dragging: Option<PathHandle>,
+    pub taper: bool,
+    pub taper_length: f32,
+}
+
+impl Default for PenTool {
+    fn default() -> Self {
+        Self {
+            paths: Vec::new(),
+            active_path: None,
+            dragging: None,
+            taper: true,
+            taper_length: 0.2,
+        }
+    }
+}
+
+impl PenTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_paths(paths: Vec<VectorPath>) -> Self {
+        Self {
+            paths,
+            ..Self::default()
+        }
+    }
+
+    pub fn active_path_mut(&mut self) -> Option<&mut VectorPath> {
+        self.active_path.and_then(|index| self.paths.get_mut(index))
+    }
+
+    pub fn taper_pressure(&self, t: f32) -> f32 {
+        if !self.taper || self.taper_length <= 0.0 {
+            return 1.0;
+        }
+        let edge = t.min(1.0 - t).max(0.0);
+        (edge / self.taper_length).clamp(0.0, 1.0).sqrt()
+    }
+
+    pub fn press(&mut self, pos: Pos2, radius: f32, name: String) {
+        let active_hit = self.active_path_mut()
+            .and_then(|path| path.hit_test(pos, radius, true).map(|hit| (path, hit)));
+        if let Some((path, hit)) = active_hit {
+            if hit == PathHandle::Anchor(0) && !path.closed && path.anchors.len() > 2 {
+                path.closed = true;
+                self.dragging = Some(PathHandle::HandleIn(0));
+            } else {
+                self.dragging = Some(hit);
+            }
+            return;
+        }
+
+        let hit_other = self.paths
+            .iter()
+            .position(|path| path.hit_test(pos, radius, false).is_some());
+        if let Some(index) = hit_other.filter(|&index| Some(index) != self.active_path) {
+            self.active_path = Some(index);
+            self.dragging = None;
+            return;
+        }
+
+        let needs_new_path = self.active_path_mut().is_none_or(|path| path.closed);
+        if needs_new_path {
+            self.paths.push(VectorPath::new(name));
+            self.active_path = Some(self.paths.len() - 1);
+        }
+
+        if let Some(path) = self.active_path_mut() {
+            path.anchors.push(PathAnchor::corner(pos.x, pos.y));
+            self.dragging = Some(PathHandle::HandleOut(path.anchors.len() - 1));
+        }
+    }
+
+    pub fn drag_to(&mut self, pos: Pos2, break_handles: bool) {
+        let Some(handle) = self.dragging else {
+            return;
+        };
+        let Some(path) = self.active_path_mut() else {
+            return;
+        };
+
+        match handle {
+            PathHandle::Anchor(i) => {
+                if let Some(anchor) = path.anchors.get_mut(i) {
+                    anchor.translate(pos.x - anchor.point[0], pos.y - anchor.point[1]);
+                }
+            },
+            PathHandle::HandleOut(i) => {
+                if let Some(anchor) = path.anchors.get_mut(i) {
+                    anchor.set_handle_out(pos.x, pos.y, !break_handles);
+                }
+            },
+            PathHandle::HandleIn(i) => {
+                if let Some(anchor) = path.anchors.get_mut(i) {
+                    anchor.set_handle_in(pos.x, pos.y, !break_handles);
+                }
+            },
+        }
+    }
+
+    pub fn release(&mut self) {
+        self.dragging = None;
+    }
+
+    pub fn finish_path(&mut self) {
+        self.active_path = None;
+        self.dragging = None;
+    }
+
+    pub fn remove_last_anchor(&mut self) {
+        if let Some(path) = self.active_path_mut() {
+            path.anchors.pop();
+            path.closed = false;
+        }
+        self.dragging = None;
+    }
+
+    pub fn delete_path(&mut self, index: usize) {
+        if index < self.paths.len() {
+            self.paths.remove(index);
+        }
+        self.active_path = match self.active_path {
+            Some(active) if active == index => None,
+            Some(active) if active > index => Some(active - 1),
+            other => other,
+        };
+        self.dragging = None;
+    }
+
+    pub fn draw_overlay(&self, painter: &egui::Painter, to_screen: impl Fn(Pos2) -> Pos2, accent: egui::Color32) {
+        use egui::{Color32, Stroke};
+
+        for (index, path) in self.paths.iter().enumerate() {
+            let active = Some(index) == self.active_path;
+            let points: Vec<Pos2> = path.flatten().into_iter().map(&to_screen).collect();
+            if points.len() > 1 {
+                let color = if active { accent } else { Color32::from_gray(160) };
+                painter.add(egui::Shape::line(points, Stroke::new(1.5, color)));
+            }
+
+            if !active {
+                continue;
+            }
+
+            let to_pos = |p: [f32; 2]| to_screen(Pos2::new(p[0], p[1]));
+            for anchor in &path.anchors {
+                let point = to_pos(anchor.point);
+                for handle in [anchor.handle_in, anchor.handle_out] {
+                    if handle != anchor.point {
+                        let handle_pos = to_pos(handle);
+                        painter.line_segment([point, handle_pos], Stroke::new(1.0, Color32::from_gray(200)));
+                        painter.circle_filled(handle_pos, 3.0, Color32::WHITE);
+                        painter.circle_stroke(handle_pos, 3.0, Stroke::new(1.0, accent));
+                    }
+                }
+                let rect = egui::Rect::from_center_size(point, egui::Vec2::splat(7.0));
+                painter.rect_filled(rect, 0.0, Color32::WHITE);
+                painter.rect_stroke(rect, 0.0, Stroke::new(1.0, accent));
+            }
+        }
+    }
+}